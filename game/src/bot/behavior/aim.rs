@@ -1,4 +1,7 @@
-use crate::bot::{behavior::Action, behavior::BehaviorContext};
+use crate::{
+    bot::{behavior::Action, behavior::BehaviorContext},
+    character::Character,
+};
 use fyrox::{
     core::{
         algebra::{UnitQuaternion, Vector3},
@@ -54,9 +57,11 @@ impl AimOnTarget {
     ) -> bool {
         let angle = self.pitch.angle();
 
+        let max_pitch = Character::MAX_SPINE_PITCH.to_radians();
         self.pitch
             .set_target(
-                look_dir.dot(&Vector3::y()).acos() - std::f32::consts::PI / 2.0 + angle_hack,
+                (look_dir.dot(&Vector3::y()).acos() - std::f32::consts::PI / 2.0 + angle_hack)
+                    .clamp(-max_pitch, max_pitch),
             )
             .update(dt);
 
@@ -0,0 +1,133 @@
+//! Bots under fire try to hide behind cover and peek out of it from time to time to shoot back.
+
+use crate::{
+    bot::behavior::{movement::MoveToTarget, BehaviorContext},
+    utils, CollisionGroups,
+};
+use fyrox::{
+    core::{
+        algebra::{Point3, Vector3},
+        math::ray::Ray,
+        visitor::prelude::*,
+    },
+    scene::{
+        collider::{BitMask, InteractionGroups},
+        graph::{physics::RayCastOptions, Graph},
+    },
+    utils::behavior::{Behavior, Status},
+};
+
+/// Checks whether there is any level geometry between two points. Actors, their hit boxes and
+/// sensors (triggers, pickup areas) do not block line of sight.
+pub fn is_line_of_sight_blocked(graph: &Graph, from: Vector3<f32>, to: Vector3<f32>) -> bool {
+    let ray = Ray::from_two_points(from, to);
+    let mut query_buffer = Vec::default();
+    graph.physics.cast_ray(
+        RayCastOptions {
+            ray_origin: Point3::from(ray.origin),
+            ray_direction: ray.dir,
+            groups: InteractionGroups::new(
+                BitMask(0xFFFF),
+                BitMask(!(CollisionGroups::ActorCapsule as u32)),
+            ),
+            max_len: ray.dir.norm(),
+            sort_results: false,
+        },
+        &mut query_buffer,
+    );
+    query_buffer
+        .iter()
+        .any(|hit| utils::is_obstacle(graph, hit.collider))
+}
+
+#[derive(Default, Debug, PartialEq, Visit, Clone)]
+pub struct TakeCover {
+    #[visit(skip)]
+    mover: MoveToTarget,
+    // Time spent hiding in current cover.
+    #[visit(skip)]
+    hide_timer: f32,
+    // Time left to peek out of cover, while it is positive the bot attacks in usual manner.
+    #[visit(skip)]
+    peek_timer: f32,
+}
+
+impl TakeCover {
+    /// Bots look for cover only this close to them (in meters).
+    const MAX_COVER_DISTANCE: f32 = 12.0;
+    /// Bot is considered to be in cover when it is this close to a cover point.
+    const COVER_REACH_DISTANCE: f32 = 0.4;
+    /// Height of the point above a cover point which must be hidden from the target.
+    const COVER_HEIGHT: f32 = 0.5;
+    const HIDE_TIME: f32 = 2.5;
+    const PEEK_TIME: f32 = 1.5;
+
+    fn is_cover_valid(graph: &Graph, cover: Vector3<f32>, threat: Vector3<f32>) -> bool {
+        is_line_of_sight_blocked(
+            graph,
+            threat,
+            cover + Vector3::new(0.0, Self::COVER_HEIGHT, 0.0),
+        )
+    }
+
+    fn find_cover(context: &BehaviorContext, threat: Vector3<f32>) -> Option<Vector3<f32>> {
+        let position = context.character.position(&context.scene.graph);
+        context
+            .cover_points
+            .iter()
+            .map(|&cover| (cover, cover.metric_distance(&position)))
+            .filter(|&(cover, distance)| {
+                distance <= Self::MAX_COVER_DISTANCE
+                    && Self::is_cover_valid(&context.scene.graph, cover, threat)
+            })
+            .min_by(|a, b| a.1.total_cmp(&b.1))
+            .map(|(cover, _)| cover)
+    }
+}
+
+impl<'a> Behavior<'a> for TakeCover {
+    type Context = BehaviorContext<'a>;
+
+    fn tick(&mut self, context: &mut Self::Context) -> Status {
+        let threat = match context.target.as_ref() {
+            Some(target) if context.under_fire => target.position,
+            _ => {
+                *context.cover = None;
+                return Status::Failure;
+            }
+        };
+
+        // Peeking out - let the usual attack sequence run.
+        if self.peek_timer > 0.0 {
+            self.peek_timer -= context.dt;
+            return Status::Failure;
+        }
+
+        let current_cover = *context.cover;
+        let cover = match current_cover {
+            Some(cover) if Self::is_cover_valid(&context.scene.graph, cover, threat) => cover,
+            _ => match Self::find_cover(context, threat) {
+                Some(cover) => {
+                    self.hide_timer = 0.0;
+                    *context.cover = Some(cover);
+                    cover
+                }
+                None => {
+                    *context.cover = None;
+                    return Status::Failure;
+                }
+            },
+        };
+
+        self.mover.min_distance = Self::COVER_REACH_DISTANCE;
+        if let Status::Success = self.mover.move_to(context, Some(cover)) {
+            self.hide_timer += context.dt;
+            if self.hide_timer >= Self::HIDE_TIME {
+                self.hide_timer = 0.0;
+                self.peek_timer = Self::PEEK_TIME;
+            }
+        }
+
+        Status::Running
+    }
+}
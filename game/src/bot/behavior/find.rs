@@ -1,5 +1,8 @@
 use crate::{
-    bot::{behavior::BehaviorContext, Bot, BotHostility, Target},
+    bot::{
+        behavior::{cover::is_line_of_sight_blocked, BehaviorContext},
+        Bot, BotHostility, Target,
+    },
     character::{try_get_character_ref, Character},
 };
 use fyrox::{
@@ -20,16 +23,29 @@ use fyrox::{
 #[derive(Default, Debug, PartialEq, Visit, Clone)]
 pub struct FindTarget {
     frustum: Frustum,
+    /// Time (in seconds) the current target is out of sight.
+    #[visit(optional)]
+    unseen_time: f32,
 }
 
 impl FindTarget {
-    fn update_frustum(&mut self, position: Vector3<f32>, graph: &Graph, model: Handle<Node>) {
+    /// Bot keeps track of a hidden target for this time (in seconds), then it goes to search
+    /// the target at its last known position.
+    const LOSE_SIGHT_TIME: f32 = 2.0;
+
+    fn update_frustum(
+        &mut self,
+        position: Vector3<f32>,
+        graph: &Graph,
+        model: Handle<Node>,
+        detection_range: f32,
+    ) {
         let head_pos = position + Vector3::new(0.0, 0.4, 0.0);
         let up = graph[model].up_vector();
         let look_at = head_pos + graph[model].look_vector();
         let view_matrix = Matrix4::look_at_rh(&Point3::from(head_pos), &Point3::from(look_at), &up);
         let projection_matrix =
-            Matrix4::new_perspective(16.0 / 9.0, 90.0f32.to_radians(), 0.1, 20.0);
+            Matrix4::new_perspective(16.0 / 9.0, 90.0f32.to_radians(), 0.1, detection_range);
         let view_projection_matrix = projection_matrix * view_matrix;
         self.frustum = Frustum::from(view_projection_matrix).unwrap();
     }
@@ -41,7 +57,12 @@ impl<'a> Behavior<'a> for FindTarget {
     fn tick(&mut self, ctx: &mut Self::Context) -> Status {
         let position = ctx.character.position(&ctx.scene.graph);
 
-        self.update_frustum(position, &ctx.scene.graph, ctx.model);
+        self.update_frustum(
+            position,
+            &ctx.scene.graph,
+            ctx.model,
+            ctx.definition.detection_range,
+        );
 
         // Check if existing target is valid.
         if let Some(target) = ctx.target {
@@ -49,8 +70,22 @@ impl<'a> Behavior<'a> for FindTarget {
                 if actor_handle != ctx.bot_handle && actor_handle == target.handle {
                     if let Some(character) = try_get_character_ref(actor_handle, &ctx.scene.graph) {
                         if character.health > 0.0 {
-                            target.position = character.position(&ctx.scene.graph);
-                            return Status::Success;
+                            let target_position = character.position(&ctx.scene.graph);
+                            if is_line_of_sight_blocked(&ctx.scene.graph, target_position, position)
+                            {
+                                self.unseen_time += ctx.dt;
+                            } else {
+                                self.unseen_time = 0.0;
+                            }
+
+                            if self.unseen_time < Self::LOSE_SIGHT_TIME {
+                                target.position = target_position;
+                                return Status::Success;
+                            }
+
+                            // Lost sight of the target, remember where it was.
+                            *ctx.search_point = Some(target.position);
+                            *ctx.search_timer = 0.0;
                         }
                     }
                 }
@@ -59,6 +94,7 @@ impl<'a> Behavior<'a> for FindTarget {
 
         // Reset target and try to find new one.
         *ctx.target = None;
+        self.unseen_time = 0.0;
         let mut closest_distance = f32::MAX;
         let mut query_buffer = Vec::default();
         'target_loop: for &actor_handle in ctx
@@ -140,11 +176,18 @@ impl<'a> Behavior<'a> for FindTarget {
             }
         }
 
+        // Squad mates share their knowledge about the target.
+        if ctx.target.is_none() {
+            *ctx.target = ctx.squad.and_then(|squad| squad.target.clone());
+        }
+
         if ctx.target.is_some() {
+            *ctx.search_point = None;
+            *ctx.search_timer = 0.0;
             Status::Success
         } else {
-            // Keep looking.
-            Status::Running
+            // Let the bot search for the lost target, if any.
+            Status::Failure
         }
     }
 }
@@ -0,0 +1,100 @@
+//! Bots that lost sight of their target go to the place where they saw it last time and look
+//! around for a while before giving up.
+
+use crate::bot::behavior::{movement::MoveToTarget, BehaviorContext};
+use fyrox::{
+    core::{
+        algebra::{UnitQuaternion, Vector3},
+        visitor::prelude::*,
+    },
+    utils::behavior::{Behavior, Status},
+};
+
+#[derive(Default, Debug, PartialEq, Visit, Clone)]
+pub struct Investigate {
+    #[visit(skip)]
+    mover: MoveToTarget,
+    // Time spent walking to the search point.
+    #[visit(skip)]
+    travel_time: f32,
+    // Yaw of the bot when it has reached the search point.
+    #[visit(skip)]
+    base_yaw: Option<f32>,
+}
+
+impl Investigate {
+    /// Bot starts looking around when it is this close to the search point.
+    const SEARCH_POINT_REACH_DISTANCE: f32 = 1.0;
+    /// Bot gives up if it can't reach the search point in this time (in seconds).
+    const MAX_TRAVEL_TIME: f32 = 15.0;
+    const LOOK_AROUND_TIME: f32 = 4.0;
+    /// Max angle (in degrees) the bot turns to each side while looking around.
+    const LOOK_AROUND_ANGLE: f32 = 70.0;
+    /// Angular frequency (in radians per second) of turns while looking around.
+    const LOOK_AROUND_SPEED: f32 = 1.5;
+
+    fn set_yaw(context: &mut BehaviorContext, yaw: f32) {
+        if let Some(body) = context.scene.graph.try_get_mut(context.character.body) {
+            body.local_transform_mut()
+                .set_rotation(UnitQuaternion::from_axis_angle(&Vector3::y_axis(), yaw));
+        }
+    }
+
+    fn finish(&mut self, context: &mut BehaviorContext) -> Status {
+        *context.search_point = None;
+        *context.search_timer = 0.0;
+        self.travel_time = 0.0;
+        self.base_yaw = None;
+        Status::Success
+    }
+}
+
+impl<'a> Behavior<'a> for Investigate {
+    type Context = BehaviorContext<'a>;
+
+    fn tick(&mut self, context: &mut Self::Context) -> Status {
+        let search_point = match *context.search_point {
+            Some(search_point) => search_point,
+            None => {
+                self.travel_time = 0.0;
+                self.base_yaw = None;
+                return Status::Failure;
+            }
+        };
+
+        let position = context.character.position(&context.scene.graph);
+
+        if self.base_yaw.is_none() {
+            self.mover.min_distance = Self::SEARCH_POINT_REACH_DISTANCE;
+            if let Status::Running = self.mover.move_to(context, Some(search_point)) {
+                self.travel_time += context.dt;
+                if self.travel_time >= Self::MAX_TRAVEL_TIME {
+                    return self.finish(context);
+                }
+
+                // There is no target to aim at, so face the direction of movement.
+                let direction = context.agent.position() - position;
+                if direction.x != 0.0 || direction.z != 0.0 {
+                    Self::set_yaw(context, direction.x.atan2(direction.z));
+                }
+
+                return Status::Running;
+            }
+
+            let look = context.scene.graph[context.model].look_vector();
+            self.base_yaw = Some(look.x.atan2(look.z));
+        }
+
+        *context.search_timer += context.dt;
+        if *context.search_timer >= Self::LOOK_AROUND_TIME {
+            return self.finish(context);
+        }
+
+        let base_yaw = self.base_yaw.unwrap_or_default();
+        let offset = (*context.search_timer * Self::LOOK_AROUND_SPEED).sin()
+            * Self::LOOK_AROUND_ANGLE.to_radians();
+        Self::set_yaw(context, base_yaw + offset);
+
+        Status::Running
+    }
+}
@@ -93,7 +93,7 @@ impl<'a> Behavior<'a> for DoMeleeAttack {
                         try_get_character_mut(target.handle, &mut context.scene.graph)
                     {
                         character.push_command(CharacterCommand::Damage {
-                            who: Default::default(),
+                            who: context.bot_handle,
                             hitbox: None,
                             /// TODO: Find hit box maybe?
                             amount: context.definition.attack_animations
@@ -3,14 +3,17 @@ use crate::{
     bot::{
         behavior::{
             aim::AimOnTarget,
+            cover::TakeCover,
             death::{IsDead, StayDead},
             find::FindTarget,
+            investigate::Investigate,
             melee::{CanMeleeAttack, DoMeleeAttack},
             movement::MoveToTarget,
             shoot::{CanShootTarget, ShootTarget},
             threat::{NeedsThreatenTarget, ThreatenTarget},
         },
         lower_body::LowerBodyMachine,
+        squad::SquadMember,
         upper_body::UpperBodyMachine,
         BotDefinition, BotKind, Target,
     },
@@ -19,7 +22,7 @@ use crate::{
     MessageSender,
 };
 use fyrox::{
-    core::{math::SmoothAngle, pool::Handle, visitor::prelude::*},
+    core::{algebra::Vector3, math::SmoothAngle, pool::Handle, visitor::prelude::*},
     scene::{node::Node, Scene},
     utils::{
         behavior::{
@@ -32,8 +35,10 @@ use fyrox::{
 };
 
 pub mod aim;
+pub mod cover;
 pub mod death;
 pub mod find;
+pub mod investigate;
 pub mod melee;
 pub mod movement;
 pub mod shoot;
@@ -53,6 +58,8 @@ pub enum Action {
     ShootTarget(ShootTarget),
     NeedsThreatenTarget(NeedsThreatenTarget),
     ThreatenTarget(ThreatenTarget),
+    TakeCover(TakeCover),
+    Investigate(Investigate),
 }
 
 impl Default for Action {
@@ -78,6 +85,8 @@ impl<'a> Behavior<'a> for Action {
             Action::CanShootTarget(v) => v.tick(context),
             Action::NeedsThreatenTarget(v) => v.tick(context),
             Action::ThreatenTarget(v) => v.tick(context),
+            Action::TakeCover(v) => v.tick(context),
+            Action::Investigate(v) => v.tick(context),
         }
     }
 }
@@ -106,6 +115,18 @@ pub struct BehaviorContext<'a> {
     pub threaten_timeout: &'a mut f32,
     pub sound_manager: &'a SoundManager,
     pub animation_player: Handle<Node>,
+    /// Whether the bot was damaged recently.
+    pub under_fire: bool,
+    pub cover_points: &'a [Vector3<f32>],
+    pub cover: &'a mut Option<Vector3<f32>>,
+    /// Last known position of a lost target.
+    pub search_point: &'a mut Option<Vector3<f32>>,
+    /// Time (in seconds) spent looking around at the search point.
+    pub search_timer: &'a mut f32,
+    /// `None` for bots that act on their own.
+    pub squad: Option<&'a SquadMember>,
+    /// Multiplier of the spread of shots caused by movement, depends on difficulty.
+    pub spread_scale: f32,
 
     // Output
     pub attack_animation_index: usize,
@@ -114,6 +135,10 @@ pub struct BehaviorContext<'a> {
     pub is_attacking: bool,
     pub is_aiming_weapon: bool,
     pub is_screaming: bool,
+    /// Spread (in degrees) of a shot made in this tick.
+    pub inaccuracy: Option<f32>,
+    /// Point on the smoothed path the bot is walking to, used for debug drawing.
+    pub steering_point: &'a mut Option<Vector3<f32>>,
 }
 
 #[derive(Default, Debug, Visit, Clone)]
@@ -150,17 +175,22 @@ impl BotBehavior {
                             CompositeNode::new_sequence(vec![
                                 LeafNode::new(Action::CanShootTarget(CanShootTarget))
                                     .add_to(&mut tree),
-                                LeafNode::new(Action::MoveToTarget(MoveToTarget {
-                                    min_distance: 4.0,
-                                }))
-                                .add_to(&mut tree),
+                                LeafNode::new(Action::TakeCover(TakeCover::default()))
+                                    .add_to(&mut tree),
+                            ])
+                            .add_to(&mut tree),
+                            CompositeNode::new_sequence(vec![
+                                LeafNode::new(Action::CanShootTarget(CanShootTarget))
+                                    .add_to(&mut tree),
+                                LeafNode::new(Action::MoveToTarget(MoveToTarget::new(4.0)))
+                                    .add_to(&mut tree),
                                 LeafNode::new(Action::ShootTarget(ShootTarget)).add_to(&mut tree),
                             ])
                             .add_to(&mut tree),
                             CompositeNode::new_sequence(vec![
-                                LeafNode::new(Action::MoveToTarget(MoveToTarget {
-                                    min_distance: definition.close_combat_distance,
-                                }))
+                                LeafNode::new(Action::MoveToTarget(MoveToTarget::new(
+                                    definition.close_combat_distance,
+                                )))
                                 .add_to(&mut tree),
                                 LeafNode::new(Action::CanMeleeAttack(CanMeleeAttack))
                                     .add_to(&mut tree),
@@ -175,6 +205,7 @@ impl BotBehavior {
                 .add_to(&mut tree),
             ])
             .add_to(&mut tree),
+            LeafNode::new(Action::Investigate(Investigate::default())).add_to(&mut tree),
         ])
         .add_to(&mut tree);
 
@@ -1,18 +1,142 @@
 use crate::{
-    bot::{behavior::BehaviorContext, lower_body::LowerBodyMachine, upper_body::UpperBodyMachine},
+    bot::{
+        behavior::{cover::is_line_of_sight_blocked, BehaviorContext},
+        lower_body::LowerBodyMachine,
+        upper_body::UpperBodyMachine,
+    },
     character::HitBox,
     utils,
-    utils::BodyImpactHandler,
+    utils::{
+        navmesh::{NavmeshAgent, NavmeshAgentBuilder},
+        BodyImpactHandler,
+    },
 };
 use fyrox::{
     core::{algebra::Vector3, pool::Handle, visitor::prelude::*},
     scene::{node::Node, Scene},
-    utils::behavior::{Behavior, Status},
+    utils::{
+        behavior::{Behavior, Status},
+        navmesh::Navmesh,
+    },
 };
 
 #[derive(Default, Debug, PartialEq, Visit, Clone)]
 pub struct MoveToTarget {
     pub min_distance: f32,
+    #[visit(skip)]
+    last_position: Vector3<f32>,
+    #[visit(skip)]
+    stuck_time: f32,
+    /// Last requested destination and its projection on the navmesh.
+    #[visit(skip)]
+    snapped_destination: Option<(Vector3<f32>, Vector3<f32>)>,
+    /// Position at which the navmesh to move on was picked and its index.
+    #[visit(skip)]
+    navmesh_choice: Option<(Vector3<f32>, usize)>,
+}
+
+impl MoveToTarget {
+    /// Squad members stop following their approach point when they're this close to it.
+    const APPROACH_POINT_REACH_DISTANCE: f32 = 1.5;
+    /// Time (in seconds) a bot can stay on the same spot while moving before its path is rebuilt.
+    const STUCK_TIMEOUT: f32 = 2.0;
+    /// Minimal distance a bot should travel in a second to be considered moving.
+    const MIN_SPEED: f32 = 0.1;
+    /// Navmesh to move on is picked again only when the bot moves further than this.
+    const NAVMESH_RECHECK_DISTANCE: f32 = 2.0;
+    /// Destination is projected on the navmesh again only when it moves further than this.
+    const SNAP_UPDATE_DISTANCE: f32 = 0.25;
+    /// How far ahead along the path a bot looks for a point it can walk to directly.
+    const LOOK_AHEAD_DISTANCE: f32 = 4.0;
+    /// Height above the ground at which straight walkability is checked.
+    const LOOK_AHEAD_HEIGHT: f32 = 0.5;
+
+    pub fn new(min_distance: f32) -> Self {
+        Self {
+            min_distance,
+            ..Default::default()
+        }
+    }
+
+    fn is_stuck(&mut self, position: Vector3<f32>, dt: f32) -> bool {
+        if position.metric_distance(&self.last_position) < Self::MIN_SPEED * dt {
+            self.stuck_time += dt;
+        } else {
+            self.stuck_time = 0.0;
+        }
+        self.last_position = position;
+
+        if self.stuck_time >= Self::STUCK_TIMEOUT {
+            self.stuck_time = 0.0;
+            true
+        } else {
+            false
+        }
+    }
+
+    fn navmesh_index(&mut self, scene: &Scene, position: Vector3<f32>) -> usize {
+        match self.navmesh_choice {
+            Some((checked_position, index))
+                if checked_position.metric_distance(&position) < Self::NAVMESH_RECHECK_DISTANCE =>
+            {
+                index
+            }
+            _ => {
+                let index = closest_navmesh_index(scene, position);
+                self.navmesh_choice = Some((position, index));
+                index
+            }
+        }
+    }
+
+    /// Targets could be off the navmesh (standing on a crate for example), such targets can't
+    /// be reached, so the bot goes to the closest point on the navmesh instead.
+    fn snap_destination(&mut self, navmesh: &Navmesh, destination: Vector3<f32>) -> Vector3<f32> {
+        match self.snapped_destination {
+            Some((requested, snapped))
+                if requested.metric_distance(&destination) < Self::SNAP_UPDATE_DISTANCE =>
+            {
+                snapped
+            }
+            _ => {
+                let snapped =
+                    utils::closest_point_on_navmesh(navmesh, destination).unwrap_or(destination);
+                self.snapped_destination = Some((destination, snapped));
+                snapped
+            }
+        }
+    }
+}
+
+/// String pulling over the path of the agent: returns the farthest point of the path ahead
+/// of the bot (within look ahead distance) that can be walked to in a straight line. This
+/// removes zig-zags of paths built over navmesh vertices.
+fn smoothed_steering_point(
+    agent: &NavmeshAgent,
+    scene: &Scene,
+    position: Vector3<f32>,
+) -> Option<Vector3<f32>> {
+    let path = agent.path();
+
+    let closest = path
+        .iter()
+        .enumerate()
+        .min_by(|(_, a), (_, b)| {
+            a.metric_distance(&position)
+                .total_cmp(&b.metric_distance(&position))
+        })
+        .map(|(index, _)| index)?;
+
+    let offset = Vector3::new(0.0, MoveToTarget::LOOK_AHEAD_HEIGHT, 0.0);
+    path[closest..]
+        .iter()
+        .skip(1)
+        .take_while(|point| point.metric_distance(&position) <= MoveToTarget::LOOK_AHEAD_DISTANCE)
+        .take_while(|point| {
+            !is_line_of_sight_blocked(&scene.graph, position + offset, **point + offset)
+        })
+        .last()
+        .cloned()
 }
 
 fn calculate_movement_speed_factor(
@@ -39,10 +163,37 @@ fn calculate_movement_speed_factor(
     k
 }
 
-impl<'a> Behavior<'a> for MoveToTarget {
-    type Context = BehaviorContext<'a>;
+/// Levels could have multiple navmeshes (for example one per floor), pick the one which has
+/// a vertex closest to the given position. It scans every vertex, so the result must be cached.
+fn closest_navmesh_index(scene: &Scene, position: Vector3<f32>) -> usize {
+    if scene.navmeshes.iter().nth(1).is_none() {
+        return 0;
+    }
 
-    fn tick(&mut self, context: &mut Self::Context) -> Status {
+    scene
+        .navmeshes
+        .iter()
+        .enumerate()
+        .map(|(index, navmesh)| {
+            let distance = navmesh
+                .vertices()
+                .iter()
+                .map(|v| v.position().metric_distance(&position))
+                .fold(f32::MAX, f32::min);
+            (index, distance)
+        })
+        .min_by(|a, b| a.1.total_cmp(&b.1))
+        .map_or(0, |(index, _)| index)
+}
+
+impl MoveToTarget {
+    /// Moves the bot along a path on navmesh to the given destination. Returns `Status::Success`
+    /// when the destination is reached.
+    pub fn move_to(
+        &mut self,
+        context: &mut BehaviorContext,
+        destination: Option<Vector3<f32>>,
+    ) -> Status {
         context.movement_speed_factor = calculate_movement_speed_factor(
             context.upper_body_machine,
             &context.character.hit_boxes,
@@ -51,26 +202,46 @@ impl<'a> Behavior<'a> for MoveToTarget {
             context.animation_player,
         );
 
-        let body = context.scene.graph[context.character.body].as_rigid_body_mut();
-        let position = body.global_position();
+        let position = context.scene.graph[context.character.body].global_position();
+        let navmesh_index = self.navmesh_index(context.scene, position);
 
         *context.target_move_speed = context.definition.walk_speed * context.movement_speed_factor;
 
         context.agent.set_speed(context.move_speed);
-        let navmesh = context.scene.navmeshes.iter_mut().next().unwrap();
         context.agent.set_position(position);
 
-        if let Some(target) = context.target.as_ref() {
-            context.agent.set_target(target.position);
+        if let (Some(destination), Some(navmesh)) = (
+            destination,
+            context.scene.navmeshes.iter_mut().nth(navmesh_index),
+        ) {
+            let destination = self.snap_destination(navmesh, destination);
+            context.agent.set_target(destination);
             let _ = context.agent.update(context.dt, navmesh);
         }
 
+        *context.steering_point = smoothed_steering_point(context.agent, context.scene, position);
+        let body = context.scene.graph[context.character.body].as_rigid_body_mut();
+
         let has_reached_destination =
             context.agent.target().metric_distance(&position) <= self.min_distance;
         if has_reached_destination {
+            self.stuck_time = 0.0;
             body.set_lin_vel(Vector3::new(0.0, body.lin_vel().y, 0.0));
+        } else if destination.is_some() && self.is_stuck(position, context.dt) {
+            // Path is most likely blocked by something that isn't on navmesh, reset agent
+            // so the path will be built from scratch on next update.
+            *context.agent = NavmeshAgentBuilder::new()
+                .with_position(position)
+                .with_speed(context.move_speed)
+                .build();
         } else {
-            let mut vel = (context.agent.position() - position).scale(1.0 / context.dt);
+            let mut vel = match context.steering_point.and_then(|point| {
+                Vector3::new(point.x - position.x, 0.0, point.z - position.z)
+                    .try_normalize(f32::EPSILON)
+            }) {
+                Some(direction) => direction.scale(context.move_speed),
+                None => (context.agent.position() - position).scale(1.0 / context.dt),
+            };
             vel.y = body.lin_vel().y;
             body.set_lin_vel(vel);
         }
@@ -109,3 +280,28 @@ impl<'a> Behavior<'a> for MoveToTarget {
         }
     }
 }
+
+impl<'a> Behavior<'a> for MoveToTarget {
+    type Context = BehaviorContext<'a>;
+
+    fn tick(&mut self, context: &mut Self::Context) -> Status {
+        let approach_point = context.squad.and_then(|squad| squad.approach_point);
+        let destination = match (context.target.as_ref(), approach_point) {
+            (Some(target), Some(approach_point)) => {
+                // Flanking bots go around the target first and attack it directly when they're
+                // either at their approach point or closer to the target than the point is.
+                let position = context.character.position(&context.scene.graph);
+                if position.metric_distance(&approach_point) > Self::APPROACH_POINT_REACH_DISTANCE
+                    && position.metric_distance(&target.position)
+                        > approach_point.metric_distance(&target.position)
+                {
+                    Some(approach_point)
+                } else {
+                    Some(target.position)
+                }
+            }
+            (target, _) => target.map(|target| target.position),
+        };
+        self.move_to(context, destination)
+    }
+}
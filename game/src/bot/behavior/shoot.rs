@@ -1,16 +1,90 @@
 use crate::{
     bot::behavior::BehaviorContext,
-    level::item::ItemKind,
-    weapon::{weapon_mut, weapon_ref},
+    character::try_get_character_ref,
+    weapon::{
+        definition::{gen_cone_direction, WeaponProjectile},
+        projectile::Projectile,
+        weapon_mut, weapon_ref, Weapon,
+    },
 };
 use fyrox::{
-    core::visitor::prelude::*,
+    core::{algebra::Vector3, pool::Handle, visitor::prelude::*},
+    scene::{graph::Graph, node::Node, rigidbody::RigidBody},
     utils::behavior::{Behavior, Status},
 };
 
 #[derive(Default, Debug, PartialEq, Visit, Eq, Clone)]
 pub struct ShootTarget;
 
+/// Max spread (in degrees) of shots caused by movement.
+const MAX_MOVING_INACCURACY: f32 = 15.0;
+
+fn body_velocity(body: Handle<Node>, graph: &Graph) -> Option<Vector3<f32>> {
+    graph
+        .try_get(body)
+        .and_then(|b| b.cast::<RigidBody>())
+        .map(|b| b.lin_vel())
+}
+
+fn character_velocity(character: Handle<Node>, graph: &Graph) -> Option<Vector3<f32>> {
+    try_get_character_ref(character, graph).and_then(|c| body_velocity(c.body, graph))
+}
+
+/// Calculates spread (in degrees) of a shot, it depends on horizontal speeds of the bot and its
+/// target and on difficulty. Shots of a standing bot at a standing target are precise.
+fn movement_inaccuracy(context: &BehaviorContext, graph: &Graph) -> f32 {
+    let horizontal_speed = |velocity: Option<Vector3<f32>>| {
+        velocity.map_or(0.0, |v| Vector3::new(v.x, 0.0, v.z).norm())
+    };
+
+    let own_speed = horizontal_speed(body_velocity(context.character.body, graph));
+    let target_speed = horizontal_speed(
+        context
+            .target
+            .as_ref()
+            .and_then(|target| character_velocity(target.handle, graph)),
+    );
+
+    ((own_speed * context.definition.moving_inaccuracy
+        + target_speed * context.definition.target_moving_inaccuracy)
+        * context.spread_scale)
+        .min(MAX_MOVING_INACCURACY)
+}
+
+/// Calculates shot direction that leads moving target, so slow projectiles will have a chance
+/// to hit it. Returns `None` if there is no need to correct weapon's direction.
+fn lead_direction(
+    context: &BehaviorContext,
+    weapon: &Weapon,
+    graph: &Graph,
+) -> Option<Vector3<f32>> {
+    let prediction = context.definition.aim_prediction;
+    if prediction <= 0.0 || context.dt <= 0.0 {
+        return None;
+    }
+
+    let definition = match weapon.definition.projectile {
+        WeaponProjectile::Projectile(kind) => Projectile::get_definition(kind),
+        // Rays hit instantly.
+        WeaponProjectile::Ray { .. } => return None,
+    };
+    if !definition.is_kinematic() || definition.speed() <= 0.0 {
+        return None;
+    }
+    let projectile_speed = definition.speed() / context.dt;
+
+    let target = context.target.as_ref()?;
+    let target_velocity = character_velocity(target.handle, graph)?;
+
+    let shot_position = weapon.shot_position(graph);
+    let distance = target.position.metric_distance(&shot_position);
+    let flight_time = distance / projectile_speed;
+    let lead = target_velocity.scale(flight_time * prediction.min(1.0));
+
+    // Keep the vertical aim of the weapon and just shift the point it aims at.
+    (weapon.shot_direction(graph).scale(distance) + lead).try_normalize(f32::EPSILON)
+}
+
 impl<'a> Behavior<'a> for ShootTarget {
     type Context = BehaviorContext<'a>;
 
@@ -31,17 +105,25 @@ impl<'a> Behavior<'a> for ShootTarget {
                 if context
                     .character
                     .inventory
-                    .try_extract_exact_items(ItemKind::Ammo, ammo_per_shot)
+                    .try_extract_exact_items(weapon.definition.ammo_item, ammo_per_shot)
                     == ammo_per_shot
                 {
-                    context
-                        .v_recoil
-                        .set_target(weapon.definition.gen_v_recoil_angle());
-                    context
-                        .h_recoil
-                        .set_target(weapon.definition.gen_h_recoil_angle());
+                    context.v_recoil.set_target(weapon.gen_v_recoil_angle());
+                    context.h_recoil.set_target(weapon.gen_h_recoil_angle());
 
-                    weapon_mut(weapon_handle, &mut context.scene.graph).request_shot(None);
+                    let mut direction = lead_direction(context, weapon, &context.scene.graph);
+
+                    let inaccuracy = movement_inaccuracy(context, &context.scene.graph);
+                    if inaccuracy > 0.0 {
+                        direction = Some(gen_cone_direction(
+                            direction
+                                .unwrap_or_else(|| weapon.shot_direction(&context.scene.graph)),
+                            inaccuracy,
+                        ));
+                    }
+                    context.inaccuracy = Some(inaccuracy);
+
+                    weapon_mut(weapon_handle, &mut context.scene.graph).request_shot(direction);
 
                     return Status::Success;
                 } else {
@@ -57,6 +139,21 @@ impl<'a> Behavior<'a> for ShootTarget {
 #[derive(Default, Debug, PartialEq, Visit, Eq, Clone)]
 pub struct CanShootTarget;
 
+impl CanShootTarget {
+    /// Weapons are unhandy when the target is that close, bots prefer melee attacks instead.
+    const MELEE_REACH: f32 = 1.2;
+
+    fn is_target_in_melee_reach(context: &BehaviorContext) -> bool {
+        context.target.as_ref().map_or(false, |target| {
+            let reach = Self::MELEE_REACH.max(context.definition.close_combat_distance);
+            target
+                .position
+                .metric_distance(&context.character.position(&context.scene.graph))
+                <= reach
+        })
+    }
+}
+
 impl<'a> Behavior<'a> for CanShootTarget {
     type Context = BehaviorContext<'a>;
 
@@ -72,13 +169,18 @@ impl<'a> Behavior<'a> for CanShootTarget {
 
             if context.restoration_time <= 0.0
                 && context.definition.can_use_weapons
+                && !Self::is_target_in_melee_reach(context)
                 && context
                     .character
                     .inventory
                     .items()
                     .iter()
                     .any(|i| i.kind.associated_weapon().is_some())
-                && context.character.inventory.item_count(ItemKind::Ammo) >= ammo_per_shot
+                && context
+                    .character
+                    .inventory
+                    .item_count(weapon.definition.ammo_item)
+                    >= ammo_per_shot
             {
                 Status::Success
             } else {
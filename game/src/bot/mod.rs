@@ -7,6 +7,7 @@ use crate::{
     character::{Character, CharacterCommand},
     current_level_mut, current_level_ref,
     door::{door_mut, door_ref, DoorContainer},
+    effects::{self, EffectKind},
     game_ref,
     inventory::{Inventory, ItemEntry},
     level::item::ItemKind,
@@ -14,7 +15,7 @@ use crate::{
     utils,
     utils::{is_probability_event_occurred, BodyImpactHandler},
     weapon::projectile::Damage,
-    Weapon,
+    MessageSender, Weapon,
 };
 use fyrox::{
     core::{
@@ -58,8 +59,11 @@ use strum_macros::{AsRefStr, EnumString, EnumVariantNames};
 
 mod behavior;
 mod lower_body;
+pub mod squad;
 mod upper_body;
 
+pub use behavior::cover::is_line_of_sight_blocked;
+
 #[derive(
     Deserialize,
     Copy,
@@ -79,6 +83,7 @@ pub enum BotKind {
     Mutant = 0,
     Parasite = 1,
     Zombie = 2,
+    ShieldedMutant = 3,
 }
 
 impl BotKind {
@@ -87,6 +92,7 @@ impl BotKind {
             BotKind::Mutant => "Mutant",
             BotKind::Parasite => "Parasite",
             BotKind::Zombie => "Zombie",
+            BotKind::ShieldedMutant => "Shielded Mutant",
         }
     }
 }
@@ -146,6 +152,41 @@ pub struct Bot {
     threaten_timeout: f32,
     #[visit(optional)]
     animation_player: Handle<Node>,
+    #[visit(optional)]
+    #[reflect(hidden)]
+    items_dropped: bool,
+    #[visit(optional)]
+    #[reflect(hidden)]
+    gibbed: bool,
+    #[visit(optional)]
+    #[reflect(hidden)]
+    under_fire_timer: f32,
+    /// Time (in seconds) since the dying animation of the bot has ended.
+    #[visit(optional)]
+    #[reflect(hidden)]
+    corpse_timer: f32,
+    #[visit(skip)]
+    #[reflect(hidden)]
+    cover: Option<Vector3<f32>>,
+    #[visit(skip)]
+    #[reflect(hidden)]
+    steering_point: Option<Vector3<f32>>,
+    /// Position where the lost target was seen last time, the bot goes there to search it.
+    #[visit(optional)]
+    #[reflect(hidden)]
+    last_known_target_position: Option<Vector3<f32>>,
+    /// Time (in seconds) the bot spent looking around at the last known target position.
+    #[visit(optional)]
+    #[reflect(hidden)]
+    search_timer: f32,
+    /// Total amount of damage absorbed by the shield of the bot.
+    #[visit(optional)]
+    #[reflect(hidden)]
+    shield_absorbed: f32,
+    /// Spread (in degrees) of the last shot of the bot.
+    #[visit(skip)]
+    #[reflect(hidden)]
+    inaccuracy: f32,
     #[visit(skip)]
     #[reflect(hidden)]
     pub commands_queue: VecDeque<BotCommand>,
@@ -190,6 +231,16 @@ impl Default for Bot {
             target_move_speed: 0.0,
             threaten_timeout: 0.0,
             animation_player: Default::default(),
+            items_dropped: false,
+            gibbed: false,
+            under_fire_timer: 0.0,
+            corpse_timer: 0.0,
+            cover: None,
+            steering_point: None,
+            last_known_target_position: None,
+            search_timer: 0.0,
+            shield_absorbed: 0.0,
+            inaccuracy: 0.0,
             commands_queue: Default::default(),
         }
     }
@@ -204,6 +255,35 @@ pub struct AttackAnimationDefinition {
     speed: f32,
 }
 
+/// Energy barrier in front of a bot that absorbs damage of hits coming from its front.
+#[derive(Deserialize, Copy, Clone, Debug)]
+pub struct ShieldDefinition {
+    /// Half-angle (in degrees) of the frontal arc covered by the shield.
+    pub half_angle: f32,
+    /// Fraction of damage absorbed by the shield in [0; 1] range.
+    pub absorption: f32,
+    /// Total amount of damage the shield absorbs before it breaks, zero means unbreakable.
+    pub capacity: f32,
+}
+
+impl ShieldDefinition {
+    /// Checks whether a hit from the given direction (from the bot to the attacker) hits the
+    /// shield of a bot that looks in the given direction.
+    fn covers(&self, look: Vector3<f32>, direction: Vector3<f32>) -> bool {
+        let look = Vector3::new(look.x, 0.0, look.z);
+        let direction = Vector3::new(direction.x, 0.0, direction.z);
+        match (
+            look.try_normalize(f32::EPSILON),
+            direction.try_normalize(f32::EPSILON),
+        ) {
+            (Some(look), Some(direction)) => {
+                look.dot(&direction) >= self.half_angle.to_radians().cos()
+            }
+            _ => false,
+        }
+    }
+}
+
 #[derive(Deserialize, Debug)]
 pub struct BotDefinition {
     pub scale: f32,
@@ -220,6 +300,29 @@ pub struct BotDefinition {
     pub v_aim_angle_hack: f32,
     pub can_use_weapons: bool,
     pub close_combat_distance: f32,
+    /// How much a bot leads a moving target when shooting projectiles. Zero means that the bot
+    /// shoots straight at the target, one - at the point where the target will be.
+    #[serde(default)]
+    pub aim_prediction: f32,
+    /// Spread (in degrees) added to shots of a bot per each m/s of its own speed.
+    #[serde(default)]
+    pub moving_inaccuracy: f32,
+    /// Spread (in degrees) added to shots of a bot per each m/s of its target's speed.
+    #[serde(default)]
+    pub target_moving_inaccuracy: f32,
+    /// Bot is torn apart instead of playing dying animation if the lethal blow exceeds remaining
+    /// health by this amount.
+    #[serde(default = "default_gib_threshold")]
+    pub gib_threshold: f32,
+    /// Max distance (in meters) at which a bot can see its target.
+    #[serde(default = "default_detection_range")]
+    pub detection_range: f32,
+    /// Time (in seconds) a corpse stays in the level after the dying animation has ended, zero
+    /// means that corpses are never removed.
+    #[serde(default = "default_corpse_lifetime")]
+    pub corpse_lifetime: f32,
+    #[serde(default)]
+    pub shield: Option<ShieldDefinition>,
     pub pain_sounds: Vec<String>,
     pub scream_sounds: Vec<String>,
     pub idle_sounds: Vec<String>,
@@ -235,6 +338,18 @@ pub struct BotDefinition {
     pub dying_animation: String,
 }
 
+fn default_gib_threshold() -> f32 {
+    500.0
+}
+
+fn default_detection_range() -> f32 {
+    20.0
+}
+
+fn default_corpse_lifetime() -> f32 {
+    30.0
+}
+
 #[derive(Deserialize, Default)]
 pub struct BotDefinitionsContainer {
     map: HashMap<BotKind, BotDefinition>,
@@ -252,6 +367,14 @@ lazy_static! {
 }
 
 impl Bot {
+    /// Knockback slower than this does not interrupt movement of a bot.
+    const MIN_KNOCKBACK_SPEED: f32 = 0.1;
+    /// Time (in seconds) a bot considers itself under fire after being damaged.
+    const UNDER_FIRE_TIME: f32 = 5.0;
+    /// Time (in seconds) it takes for a corpse to sink under the floor before removal.
+    const CORPSE_FADE_TIME: f32 = 2.0;
+    const CORPSE_SINK_SPEED: f32 = 0.5;
+
     pub fn get_definition(kind: BotKind) -> &'static BotDefinition {
         DEFINITIONS.map.get(&kind).unwrap()
     }
@@ -270,7 +393,11 @@ impl Bot {
 
         let node = &mut scene.graph[bot];
 
-        assert!(node.has_script::<Bot>());
+        // Several kinds could share the same model (i.e. a shielded mutant), so the kind stored
+        // in the prefab must be replaced with the requested one.
+        let script = node.try_get_script_mut::<Bot>().unwrap();
+        script.kind = kind;
+        script.definition = Self::get_definition(kind);
 
         node.local_transform_mut()
             .set_position(position)
@@ -339,6 +466,43 @@ impl Bot {
             });
         }
 
+        if let Some(steering_point) = self.steering_point {
+            context.add_line(scene::debug::Line {
+                begin: self.agent.position(),
+                end: steering_point,
+                color: Color::from_rgba(0, 255, 0, 255),
+            });
+        }
+
+        if let Some(cover) = self.cover {
+            context.draw_sphere(cover, 10, 10, 0.3, Color::from_rgba(0, 0, 255, 255));
+        }
+
+        if let Some(search_point) = self.last_known_target_position {
+            context.draw_sphere(
+                search_point,
+                10,
+                10,
+                0.3,
+                Color::from_rgba(255, 0, 255, 255),
+            );
+        }
+
+        // Area around the target where shots of the bot land.
+        if let Some(target) = self.target.as_ref() {
+            if self.inaccuracy > 0.0 {
+                let distance = target.position.metric_distance(&self.agent.position());
+                let radius = distance * self.inaccuracy.to_radians().tan();
+                context.draw_sphere(
+                    target.position,
+                    10,
+                    10,
+                    radius,
+                    Color::from_rgba(255, 200, 0, 255),
+                );
+            }
+        }
+
         // context.draw_frustum(&self.frustum, Color::from_rgba(0, 200, 0, 255)); TODO
     }
 
@@ -346,6 +510,41 @@ impl Bot {
         self.target = Some(Target { position, handle });
     }
 
+    /// Spread (in degrees) of the last shot of the bot, it grows when the bot or its target move.
+    pub fn inaccuracy(&self) -> f32 {
+        self.inaccuracy
+    }
+
+    /// Last known position of the lost target, the bot is searching around it.
+    pub fn search_point(&self) -> Option<Vector3<f32>> {
+        self.last_known_target_position
+    }
+
+    /// Returns true if the bot is alive and has the given actor as its target.
+    pub fn is_engaging(&self, actor: Handle<Node>) -> bool {
+        !self.character.is_dead()
+            && self
+                .target
+                .as_ref()
+                .map_or(false, |target| target.handle == actor)
+    }
+
+    fn gib(&mut self, graph: &mut Graph, resource_manager: &ResourceManager) {
+        self.gibbed = true;
+
+        let position = self.position(graph);
+        effects::create(
+            EffectKind::Gibs,
+            graph,
+            resource_manager,
+            position,
+            Default::default(),
+        );
+
+        // Weapons are attached to the model, so they will be hidden too.
+        graph[self.model].set_visibility(false);
+    }
+
     pub fn blow_up_head(&mut self, _graph: &mut Graph) {
         self.head_exploded = true;
 
@@ -356,6 +555,33 @@ impl Bot {
         self.character.clean_up(scene);
     }
 
+    /// Keeps the corpse of the bot for a while, then sinks it under the floor and removes the
+    /// bot from the scene. Items are dropped at the moment of death, so the corpse is purely
+    /// visual. Returns `true` if the bot was removed.
+    fn update_corpse(&mut self, scene: &mut Scene, self_handle: Handle<Node>, dt: f32) -> bool {
+        if self.definition.corpse_lifetime <= 0.0 || !(self.gibbed || self.can_be_removed(scene)) {
+            return false;
+        }
+
+        self.corpse_timer += dt;
+
+        let fade_time = self.corpse_timer - self.definition.corpse_lifetime;
+        if fade_time <= 0.0 {
+            false
+        } else if fade_time < Self::CORPSE_FADE_TIME {
+            scene.graph[self.model]
+                .local_transform_mut()
+                .offset(Vector3::new(0.0, -Self::CORPSE_SINK_SPEED * dt, 0.0));
+            false
+        } else {
+            self.clean_up(scene);
+            if scene.graph.is_valid_handle(self_handle) {
+                scene.graph.remove_node(self_handle);
+            }
+            true
+        }
+    }
+
     pub fn on_actor_removed(&mut self, handle: Handle<Node>) {
         if let Some(target) = self.target.as_ref() {
             if target.handle == handle {
@@ -364,21 +590,125 @@ impl Bot {
         }
     }
 
+    /// Scatters everything the bot had on the ground.
+    fn drop_items(&mut self) {
+        let items = self.inventory.items().to_vec();
+        for entry in items {
+            self.character.push_command(CharacterCommand::DropItems {
+                item: entry.kind,
+                count: entry.amount,
+            });
+        }
+        self.items_dropped = true;
+    }
+
     pub fn resolve(&mut self) {
         self.definition = Self::get_definition(self.kind);
     }
 
+    /// Checks whether the bot has a shield that is not broken yet.
+    pub fn has_shield(&self) -> bool {
+        self.definition.shield.map_or(false, |shield| {
+            shield.capacity <= 0.0 || self.shield_absorbed < shield.capacity
+        })
+    }
+
+    /// Reduces damage of queued hits that come from the front of the bot, if it has a shield.
+    fn absorb_damage(
+        &mut self,
+        graph: &mut Graph,
+        resource_manager: &ResourceManager,
+        sound_manager: &SoundManager,
+    ) {
+        let shield = match self.definition.shield {
+            Some(shield) if self.has_shield() => shield,
+            _ => return,
+        };
+
+        let position = self.position(graph);
+        let look = graph[self.model].look_vector();
+
+        let mut absorbed_any = false;
+        for command in self.character.commands.iter_mut() {
+            if let CharacterCommand::Damage { who, amount, .. } = command {
+                let attacker = match graph.try_get(*who) {
+                    Some(attacker) => attacker.global_position(),
+                    None => continue,
+                };
+
+                if *amount <= 0.0 || !shield.covers(look, attacker - position) {
+                    continue;
+                }
+
+                let mut absorbed = *amount * shield.absorption.clamp(0.0, 1.0);
+                if shield.capacity > 0.0 {
+                    absorbed = absorbed.min(shield.capacity - self.shield_absorbed);
+                }
+
+                *amount -= absorbed;
+                self.shield_absorbed += absorbed;
+                absorbed_any = true;
+
+                if shield.capacity > 0.0 && self.shield_absorbed >= shield.capacity {
+                    break;
+                }
+            }
+        }
+
+        if absorbed_any {
+            let impact_position = position + look.scale(0.5) + Vector3::new(0.0, 1.0, 0.0);
+            effects::create(
+                EffectKind::BulletImpact,
+                graph,
+                resource_manager,
+                impact_position,
+                Default::default(),
+            );
+            sound_manager.play_sound(
+                graph,
+                "data/sounds/bullet_impact_metal.ogg",
+                impact_position,
+                1.0,
+                1.0,
+                1.0,
+            );
+        }
+    }
+
+    /// Returns the amount of overkill damage of the hit that has killed the bot, if it was
+    /// killed by one of the commands.
     fn poll_commands(
         &mut self,
         scene: &mut Scene,
         self_handle: Handle<Node>,
         resource_manager: &ResourceManager,
         sound_manager: &SoundManager,
-    ) {
-        while let Some(command) =
-            self.character
-                .poll_command(scene, self_handle, resource_manager, sound_manager)
-        {
+        sender: &MessageSender,
+        friendly_fire: bool,
+    ) -> Option<f32> {
+        self.absorb_damage(&mut scene.graph, resource_manager, sound_manager);
+
+        let mut lethal_overkill = None;
+        loop {
+            let health_before_hit = self.health;
+            let (command, applied) = match self.character.poll_command(
+                scene,
+                self_handle,
+                resource_manager,
+                sound_manager,
+                sender,
+                friendly_fire,
+            ) {
+                Some(polled) => polled,
+                None => break,
+            };
+
+            // Rejected damage (a teammate, spawn protection or invulnerability) must not provoke
+            // the bot, nor blow up its head.
+            if !applied {
+                continue;
+            }
+
             if let CharacterCommand::Damage {
                 who,
                 amount,
@@ -386,6 +716,8 @@ impl Bot {
                 critical_shot_probability,
             } = command
             {
+                self.under_fire_timer = Self::UNDER_FIRE_TIME;
+
                 if let Some(shooter_script) = scene.graph.try_get(who).and_then(|n| n.script()) {
                     if let Some(character) = shooter_script.query_component_ref::<Character>() {
                         self.set_target(who, character.position(&scene.graph));
@@ -436,6 +768,10 @@ impl Bot {
                         );
                     }
                 }
+
+                if health_before_hit > 0.0 && self.is_dead() {
+                    lethal_overkill = Some(-self.health);
+                }
             }
         }
 
@@ -450,6 +786,8 @@ impl Bot {
                     .handle_impact(scene, handle, impact_point, direction),
             }
         }
+
+        lethal_overkill
     }
 }
 
@@ -481,6 +819,7 @@ impl ScriptTrait for Bot {
 
         let possible_item = [
             (ItemKind::Ammo, 10),
+            (ItemKind::ShotgunShells, 4),
             (ItemKind::Medkit, 1),
             (ItemKind::Medpack, 1),
         ];
@@ -515,8 +854,9 @@ impl ScriptTrait for Bot {
             .push(context.handle);
     }
 
-    fn on_start(&mut self, _ctx: &mut ScriptContext) {
+    fn on_start(&mut self, ctx: &mut ScriptContext) {
         self.definition = Self::get_definition(self.kind);
+        self.character.resolve_hit_boxes(&ctx.scene.graph);
     }
 
     fn on_deinit(&mut self, context: &mut ScriptDeinitContext) {
@@ -524,6 +864,12 @@ impl ScriptTrait for Bot {
             if let Some(position) = level.actors.iter().position(|a| *a == context.node_handle) {
                 level.actors.remove(position);
             }
+
+            for &actor in level.actors.iter() {
+                if let Some(bot) = try_get_bot_mut(actor, &mut context.scene.graph) {
+                    bot.on_actor_removed(context.node_handle);
+                }
+            }
         }
     }
 
@@ -531,13 +877,65 @@ impl ScriptTrait for Bot {
         let game = game_ref(ctx.plugins);
         let level = current_level_ref(ctx.plugins).unwrap();
 
-        self.poll_commands(
+        if self.is_dead() && !self.items_dropped {
+            self.drop_items();
+        }
+
+        self.character.update_status_effects(ctx.dt);
+        if !self.gibbed {
+            self.character.update_spawn_protection(
+                &mut ctx.scene.graph,
+                self.model,
+                game.accessibility.flashing,
+                ctx.dt,
+            );
+        }
+
+        let lethal_overkill = self.poll_commands(
             ctx.scene,
             ctx.handle,
             ctx.resource_manager,
             &level.sound_manager,
+            &game.message_sender,
+            level.friendly_fire,
         );
 
+        if !self.is_dead()
+            && self
+                .character
+                .update_fall_damage(
+                    ctx.handle,
+                    &ctx.scene.graph,
+                    &level.physics,
+                    &game.message_sender,
+                )
+                .is_some()
+        {
+            if let Some(grunt_sound) = self.definition.pain_sounds.choose(&mut rand::thread_rng()) {
+                let position = self.position(&ctx.scene.graph);
+                level.sound_manager.play_sound(
+                    &mut ctx.scene.graph,
+                    grunt_sound,
+                    position,
+                    0.8,
+                    1.0,
+                    0.6,
+                );
+            }
+        }
+
+        // Only the blow that kills the bot can tear it apart, shooting a corpse never gibs it.
+        if game.accessibility.gore
+            && !self.gibbed
+            && lethal_overkill.map_or(false, |overkill| overkill >= self.definition.gib_threshold)
+        {
+            self.gib(&mut ctx.scene.graph, ctx.resource_manager);
+        }
+
+        if self.is_dead() && self.update_corpse(ctx.scene, ctx.handle, ctx.dt) {
+            return;
+        }
+
         let movement_speed_factor;
         let is_attacking;
         let is_moving;
@@ -568,6 +966,13 @@ impl ScriptTrait for Bot {
                 move_speed: self.move_speed,
                 threaten_timeout: &mut self.threaten_timeout,
                 sound_manager: &level.sound_manager,
+                under_fire: self.under_fire_timer > 0.0,
+                cover_points: &level.cover_points,
+                cover: &mut self.cover,
+                search_point: &mut self.last_known_target_position,
+                search_timer: &mut self.search_timer,
+                squad: level.squads.member(ctx.handle),
+                spread_scale: game.control_scheme.difficulty.bot_spread_scale(),
 
                 // Output
                 animation_player: self.animation_player,
@@ -577,6 +982,8 @@ impl ScriptTrait for Bot {
                 is_attacking: false,
                 is_aiming_weapon: false,
                 is_screaming: false,
+                inaccuracy: None,
+                steering_point: &mut self.steering_point,
             };
 
             self.behavior.tree.tick(&mut behavior_ctx);
@@ -587,11 +994,22 @@ impl ScriptTrait for Bot {
             is_aiming = behavior_ctx.is_aiming_weapon;
             attack_animation_index = behavior_ctx.attack_animation_index;
             is_screaming = behavior_ctx.is_screaming;
+            if let Some(inaccuracy) = behavior_ctx.inaccuracy {
+                self.inaccuracy = inaccuracy;
+            }
+        }
+
+        // Knockback overrides own movement of a bot while it lasts.
+        let knockback = self.character.update_knockback(ctx.dt);
+        if knockback.norm() > Self::MIN_KNOCKBACK_SPEED {
+            let body = ctx.scene.graph[self.character.body].as_rigid_body_mut();
+            body.set_lin_vel(Vector3::new(knockback.x, body.lin_vel().y, knockback.z));
         }
 
         self.restoration_time -= ctx.dt;
         self.move_speed += (self.target_move_speed - self.move_speed) * 0.1;
         self.threaten_timeout -= ctx.dt;
+        self.under_fire_timer -= ctx.dt;
 
         self.check_doors(ctx.scene, &level.doors_container);
 
@@ -651,6 +1069,12 @@ impl ScriptTrait for Bot {
     }
 }
 
+pub fn try_get_bot_ref(handle: Handle<Node>, graph: &Graph) -> Option<&Bot> {
+    graph
+        .try_get(handle)
+        .and_then(|b| b.try_get_script::<Bot>())
+}
+
 pub fn try_get_bot_mut(handle: Handle<Node>, graph: &mut Graph) -> Option<&mut Bot> {
     graph
         .try_get_mut(handle)
@@ -0,0 +1,230 @@
+//! Bots that are close to each other form squads. Squad members share knowledge about their
+//! target and approach it from different sides instead of walking in a line.
+
+use crate::bot::{try_get_bot_ref, Target};
+use fyrox::{
+    core::{algebra::Vector3, color::Color, pool::Handle},
+    scene::{debug::SceneDrawingContext, node::Node, Scene},
+};
+use std::collections::HashMap;
+
+/// Bots closer than this distance (in meters) to any member of a squad join the squad.
+const SQUAD_RADIUS: f32 = 10.0;
+/// Distance (in meters) between approach points of flanking bots and their target.
+const FLANK_OFFSET: f32 = 5.0;
+/// Flank points are snapped to the navmesh again only when they move further than this.
+const FLANK_POINT_RESNAP_DISTANCE: f32 = 1.0;
+
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum SquadRole {
+    /// Moves straight to the target.
+    Assault,
+    FlankLeft,
+    FlankRight,
+}
+
+impl SquadRole {
+    const ALL: [Self; 3] = [Self::Assault, Self::FlankLeft, Self::FlankRight];
+
+    fn debug_color(self) -> Color {
+        match self {
+            Self::Assault => Color::opaque(255, 0, 0),
+            Self::FlankLeft => Color::opaque(255, 200, 0),
+            Self::FlankRight => Color::opaque(0, 200, 255),
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct SquadMember {
+    pub squad: usize,
+    pub role: SquadRole,
+    /// Target shared by the squad, it is known to at least one of the members.
+    pub target: Option<Target>,
+    /// Point the bot should go through to reach the target from its side.
+    pub approach_point: Option<Vector3<f32>>,
+    /// Flank point before it was snapped to the navmesh.
+    flank_point: Option<Vector3<f32>>,
+    position: Vector3<f32>,
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct Squad {
+    pub members: Vec<Handle<Node>>,
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct SquadManager {
+    squads: Vec<Squad>,
+    members: HashMap<Handle<Node>, SquadMember>,
+}
+
+impl SquadManager {
+    pub fn update(&mut self, scene: &Scene, actors: &[Handle<Node>]) {
+        let graph = &scene.graph;
+
+        self.squads.clear();
+        // Members keep their roles while they stay in a squad, so they don't switch flanks
+        // when other bots join or leave.
+        let previous_members = std::mem::take(&mut self.members);
+
+        let mut bots = actors
+            .iter()
+            .filter_map(|&handle| {
+                try_get_bot_ref(handle, graph)
+                    .filter(|bot| !bot.is_dead())
+                    .map(|bot| (handle, bot))
+            })
+            .collect::<Vec<_>>();
+
+        while let Some((leader_handle, leader)) = bots.pop() {
+            let mut squad = vec![(leader_handle, leader)];
+
+            // Grow the squad until there are no more bots near any of its members.
+            let mut i = 0;
+            while i < squad.len() {
+                let position = squad[i].1.position(graph);
+                let team = squad[i].1.team;
+                let mut j = 0;
+                while j < bots.len() {
+                    let (_, other) = bots[j];
+                    if other.team == team
+                        && other.position(graph).metric_distance(&position) <= SQUAD_RADIUS
+                    {
+                        squad.push(bots.swap_remove(j));
+                    } else {
+                        j += 1;
+                    }
+                }
+                i += 1;
+            }
+
+            // Solo bots act on their own.
+            if squad.len() < 2 {
+                continue;
+            }
+
+            let target = squad.iter().find_map(|(_, bot)| bot.target.clone());
+
+            let center = squad
+                .iter()
+                .map(|(_, bot)| bot.position(graph))
+                .sum::<Vector3<f32>>()
+                .scale(1.0 / squad.len() as f32);
+
+            let side = target.as_ref().and_then(|target| {
+                let mut direction = target.position - center;
+                direction.y = 0.0;
+                direction
+                    .try_normalize(f32::EPSILON)
+                    .map(|direction| direction.cross(&Vector3::y()))
+            });
+
+            let mut roles = squad
+                .iter()
+                .map(|(handle, _)| previous_members.get(handle).map(|member| member.role))
+                .collect::<Vec<_>>();
+            for i in 0..roles.len() {
+                if roles[i].is_none() {
+                    // Newcomers take the least occupied role.
+                    roles[i] = SquadRole::ALL
+                        .iter()
+                        .copied()
+                        .min_by_key(|&role| roles.iter().filter(|&&r| r == Some(role)).count());
+                }
+            }
+
+            let squad_index = self.squads.len();
+            for (&(handle, bot), role) in squad.iter().zip(roles) {
+                let role = role.unwrap_or(SquadRole::Assault);
+                let flank_point = match (target.as_ref(), side) {
+                    (Some(target), Some(side)) => match role {
+                        SquadRole::Assault => None,
+                        SquadRole::FlankLeft => Some(target.position + side.scale(FLANK_OFFSET)),
+                        SquadRole::FlankRight => Some(target.position - side.scale(FLANK_OFFSET)),
+                    },
+                    _ => None,
+                };
+                let approach_point = match flank_point {
+                    Some(flank_point) => Some(
+                        previous_members
+                            .get(&handle)
+                            .filter(|member| {
+                                member.flank_point.map_or(false, |previous| {
+                                    previous.metric_distance(&flank_point)
+                                        <= FLANK_POINT_RESNAP_DISTANCE
+                                })
+                            })
+                            .and_then(|member| member.approach_point)
+                            .unwrap_or_else(|| closest_navmesh_vertex(scene, flank_point)),
+                    ),
+                    None => target.as_ref().map(|target| target.position),
+                };
+
+                self.members.insert(
+                    handle,
+                    SquadMember {
+                        squad: squad_index,
+                        role,
+                        target: target.clone(),
+                        approach_point,
+                        flank_point,
+                        position: bot.position(graph),
+                    },
+                );
+            }
+
+            self.squads.push(Squad {
+                members: squad.into_iter().map(|(handle, _)| handle).collect(),
+            });
+        }
+    }
+
+    pub fn squads(&self) -> &[Squad] {
+        &self.squads
+    }
+
+    /// Returns `None` if the bot is not a member of any squad.
+    pub fn member(&self, bot: Handle<Node>) -> Option<&SquadMember> {
+        self.members.get(&bot)
+    }
+
+    pub fn debug_draw(&self, context: &mut SceneDrawingContext) {
+        for squad in self.squads.iter() {
+            for pair in squad.members.windows(2) {
+                if let (Some(a), Some(b)) = (self.member(pair[0]), self.member(pair[1])) {
+                    context.add_line(fyrox::scene::debug::Line {
+                        begin: a.position,
+                        end: b.position,
+                        color: Color::opaque(255, 255, 255),
+                    });
+                }
+            }
+        }
+
+        for member in self.members.values() {
+            if let Some(approach_point) = member.approach_point {
+                context.add_line(fyrox::scene::debug::Line {
+                    begin: member.position,
+                    end: approach_point,
+                    color: member.role.debug_color(),
+                });
+            }
+        }
+    }
+}
+
+/// Raw flank points could be inside walls or off the navmesh, so bots go to the closest navmesh
+/// vertex instead.
+fn closest_navmesh_vertex(scene: &Scene, point: Vector3<f32>) -> Vector3<f32> {
+    scene
+        .navmeshes
+        .iter()
+        .flat_map(|navmesh| navmesh.vertices().iter())
+        .map(|vertex| vertex.position())
+        .min_by(|a, b| {
+            a.metric_distance(&point)
+                .total_cmp(&b.metric_distance(&point))
+        })
+        .unwrap_or(point)
+}
@@ -1,10 +1,16 @@
 use crate::{
     block_on,
     inventory::Inventory,
-    level::item::{item_mut, ItemKind},
+    level::{
+        item::{item_mut, ItemKind},
+        physics::LevelPhysics,
+    },
+    message::Message,
     sound::{SoundKind, SoundManager},
-    weapon::{definition::WeaponKind, weapon_mut, weapon_ref},
-    Item, Weapon,
+    weapon::{
+        balance::WeaponBalance, definition::WeaponKind, try_weapon_ref, weapon_mut, weapon_ref,
+    },
+    Item, MessageSender, Weapon,
 };
 use fyrox::{
     core::{
@@ -15,14 +21,18 @@ use fyrox::{
         visitor::prelude::*,
     },
     engine::resource_manager::ResourceManager,
+    rand::Rng,
     scene::{
         collider::Collider,
         graph::{map::NodeHandleMap, physics::RayCastOptions, Graph},
         node::Node,
+        rigidbody::RigidBody,
         Scene,
     },
 };
+use serde::Deserialize;
 use std::collections::VecDeque;
+use strum_macros::{AsRefStr, EnumString, EnumVariantNames};
 
 #[derive(Debug, Clone)]
 pub enum CharacterCommand {
@@ -44,6 +54,69 @@ pub enum CharacterCommand {
         /// Only takes effect iff damage was applied to a head hit box!
         critical_shot_probability: f32,
     },
+    /// Pushes actor in given direction, the push is proportional to the damage amount above
+    /// [`Character::KNOCKBACK_DAMAGE_THRESHOLD`], so light hits do not push at all.
+    Knockback {
+        /// Actor who pushed target actor, the push is ignored in the same cases as damage.
+        who: Handle<Node>,
+        direction: Vector3<f32>,
+        amount: f32,
+    },
+    ApplyStatus(StatusEffect),
+}
+
+#[derive(
+    Copy,
+    Clone,
+    PartialEq,
+    Eq,
+    Debug,
+    Deserialize,
+    Visit,
+    Reflect,
+    AsRefStr,
+    EnumString,
+    EnumVariantNames,
+)]
+pub enum StatusEffectKind {
+    Burning,
+    Poisoned,
+}
+
+impl Default for StatusEffectKind {
+    fn default() -> Self {
+        Self::Burning
+    }
+}
+
+/// Damage that is dealt to an actor periodically for some time.
+#[derive(Default, Copy, Clone, Debug, Deserialize, Visit, Reflect)]
+pub struct StatusEffect {
+    pub kind: StatusEffectKind,
+    /// Time (in seconds) left until the effect wears off.
+    pub duration: f32,
+    /// Damage dealt every [`Character::STATUS_TICK_INTERVAL`] seconds.
+    pub tick_amount: f32,
+    /// Actor (or weapon) that applied the effect, damage will be counted for it.
+    #[serde(skip)]
+    pub who: Handle<Node>,
+    #[serde(skip)]
+    tick_timer: f32,
+}
+
+/// Actors of the same team do not damage each other, unless friendly fire is enabled on a level.
+#[derive(
+    Copy, Clone, PartialEq, Eq, Debug, Hash, Visit, Reflect, AsRefStr, EnumString, EnumVariantNames,
+)]
+pub enum Team {
+    Players,
+    Monsters,
+}
+
+impl Default for Team {
+    fn default() -> Self {
+        Self::Monsters
+    }
 }
 
 #[derive(Visit, Reflect, Debug, Clone)]
@@ -52,15 +125,38 @@ pub struct Character {
     pub body: Handle<Node>,
     pub health: f32,
     pub last_health: f32,
+    #[visit(optional)]
+    pub armor: f32,
     pub weapons: Vec<Handle<Node>>,
     pub current_weapon: u32,
     pub weapon_pivot: Handle<Node>,
     #[visit(optional)]
     pub hit_boxes: Vec<HitBox>,
     pub inventory: Inventory,
+    #[visit(optional)]
+    pub team: Team,
     #[visit(skip)]
     #[reflect(hidden)]
     pub commands: VecDeque<CharacterCommand>,
+    #[visit(skip)]
+    #[reflect(hidden)]
+    knockback: Vector3<f32>,
+    #[visit(optional)]
+    #[reflect(hidden)]
+    status_effects: Vec<StatusEffect>,
+    /// Time (in seconds) left until the character stops being invulnerable after spawn.
+    #[visit(optional)]
+    #[reflect(hidden)]
+    spawn_protection: f32,
+    /// Debug flag, invulnerable characters still react to hits, but do not lose health. It is
+    /// not stored in saves.
+    #[visit(skip)]
+    #[reflect(hidden)]
+    invulnerable: bool,
+    /// Max downward speed reached during current fall.
+    #[visit(skip)]
+    #[reflect(hidden)]
+    fall_speed: f32,
 }
 
 impl Default for Character {
@@ -70,17 +166,40 @@ impl Default for Character {
             body: Default::default(),
             health: 100.0,
             last_health: 100.0,
+            armor: 0.0,
             weapons: Vec::new(),
             current_weapon: 0,
             weapon_pivot: Handle::NONE,
             hit_boxes: Default::default(),
             inventory: Default::default(),
+            team: Default::default(),
             commands: Default::default(),
+            knockback: Default::default(),
+            status_effects: Default::default(),
+            spawn_protection: 0.0,
+            invulnerable: false,
+            fall_speed: 0.0,
         }
     }
 }
 
 impl Character {
+    pub const MAX_ARMOR: f32 = 100.0;
+    /// Fraction of incoming damage absorbed by armor (while there is any).
+    pub const ARMOR_ABSORPTION: f32 = 0.65;
+    pub const KNOCKBACK_DAMAGE_THRESHOLD: f32 = 25.0;
+    /// Maximum angle (in degrees, in both directions) the spine can be bent to aim up or down.
+    pub const MAX_SPINE_PITCH: f32 = 60.0;
+    /// Knockback speed (m/s) per each point of damage above the threshold.
+    const KNOCKBACK_PER_DAMAGE: f32 = 0.05;
+    const MAX_KNOCKBACK_SPEED: f32 = 4.0;
+    /// Fraction of knockback speed lost per second.
+    const KNOCKBACK_DECAY: f32 = 8.0;
+    /// Period (in seconds) between damage ticks of status effects.
+    pub const STATUS_TICK_INTERVAL: f32 = 0.5;
+    /// Period (in seconds) of blinking of protected characters.
+    const SHIMMER_PERIOD: f32 = 0.1;
+
     pub fn has_ground_contact(&self, graph: &Graph) -> bool {
         if let Some(collider) = graph
             .try_get(self.capsule_collider)
@@ -97,10 +216,55 @@ impl Character {
         false
     }
 
+    /// Tracks downward speed of the character while it is in the air and sends fall damage
+    /// scaled by impact speed once the capsule lands on the ground. Returns the amount of damage
+    /// if the landing was hard enough.
+    pub fn update_fall_damage(
+        &mut self,
+        self_handle: Handle<Node>,
+        graph: &Graph,
+        physics: &LevelPhysics,
+        sender: &MessageSender,
+    ) -> Option<f32> {
+        if self.has_ground_contact(graph) {
+            let impact_speed = std::mem::take(&mut self.fall_speed);
+            // Landing speed after a fall from the same height is lower in low gravity.
+            let threshold = physics.fall_damage_threshold / physics.fall_time_factor();
+            if impact_speed > threshold {
+                let amount = (impact_speed - threshold) * physics.fall_damage_scale;
+                sender.send(Message::DamageActor {
+                    actor: self_handle,
+                    who: Handle::NONE,
+                    amount,
+                });
+                return Some(amount);
+            }
+        } else if let Some(body) = graph.try_get(self.body).and_then(|n| n.cast::<RigidBody>()) {
+            self.fall_speed = self.fall_speed.max(-body.lin_vel().y);
+        }
+        None
+    }
+
     pub fn get_health(&self) -> f32 {
         self.health
     }
 
+    /// Marks hit boxes attached to arms and legs as limbs, hit boxes without a bone use the
+    /// name of their collider. Must be called once the character is in the scene.
+    pub fn resolve_hit_boxes(&mut self, graph: &Graph) {
+        for hit_box in self.hit_boxes.iter_mut() {
+            let node = if hit_box.bone.is_some() {
+                hit_box.bone
+            } else {
+                hit_box.collider
+            };
+            hit_box.is_limb = !hit_box.is_head
+                && graph
+                    .try_get(node)
+                    .map_or(false, |n| HitBox::is_limb_bone_name(n.name()));
+        }
+    }
+
     pub fn set_position(&mut self, graph: &mut Graph, position: Vector3<f32>) {
         if let Some(body) = graph.try_get_mut(self.body) {
             body.local_transform_mut().set_position(position);
@@ -111,8 +275,48 @@ impl Character {
         graph[self.body].global_position()
     }
 
-    pub fn damage(&mut self, amount: f32) {
-        self.health -= amount.abs();
+    fn apply_knockback(&mut self, direction: Vector3<f32>, amount: f32) {
+        let excess = amount - Self::KNOCKBACK_DAMAGE_THRESHOLD;
+        if excess <= 0.0 {
+            return;
+        }
+
+        // Actors are pushed only horizontally, otherwise they could be thrown into the air.
+        if let Some(direction) =
+            Vector3::new(direction.x, 0.0, direction.z).try_normalize(f32::EPSILON)
+        {
+            self.knockback = (self.knockback
+                + direction.scale(excess * Self::KNOCKBACK_PER_DAMAGE))
+            .cap_magnitude(Self::MAX_KNOCKBACK_SPEED);
+        }
+    }
+
+    /// Returns current knockback velocity and fades it out. Must be added to the velocity of
+    /// the body by an actor each frame.
+    pub fn update_knockback(&mut self, dt: f32) -> Vector3<f32> {
+        let knockback = self.knockback;
+        self.knockback = self
+            .knockback
+            .scale((1.0 - Self::KNOCKBACK_DECAY * dt).max(0.0));
+        knockback
+    }
+
+    /// Damages the character, armor absorbs a part of the damage. Returns amount of health
+    /// actually lost.
+    pub fn damage(&mut self, amount: f32) -> f32 {
+        let amount = amount.abs();
+        let absorbed = (amount * Self::ARMOR_ABSORPTION).min(self.armor);
+        self.armor -= absorbed;
+        self.health -= amount - absorbed;
+        amount - absorbed
+    }
+
+    pub fn get_armor(&self) -> f32 {
+        self.armor
+    }
+
+    pub fn restore_armor(&mut self) {
+        self.armor = Self::MAX_ARMOR;
     }
 
     pub fn heal(&mut self, amount: f32) {
@@ -146,6 +350,24 @@ impl Character {
         self.request_current_weapon_enabled(true, graph);
     }
 
+    /// Detaches the weapon from the character, if it was the current one, the next weapon (or
+    /// the previous one, if there is no next) becomes current. The weapon node is not removed.
+    pub fn remove_weapon(&mut self, weapon: Handle<Node>, graph: &mut Graph) {
+        if let Some(index) = self.weapons.iter().position(|&w| w == weapon) {
+            let was_current = index == self.current_weapon as usize;
+
+            self.weapons.remove(index);
+
+            if was_current {
+                weapon_mut(weapon, graph).enabled = false;
+                self.current_weapon = index.min(self.weapons.len().saturating_sub(1)) as u32;
+                self.request_current_weapon_enabled(true, graph);
+            } else if index < self.current_weapon as usize {
+                self.current_weapon -= 1;
+            }
+        }
+    }
+
     pub fn use_item(&mut self, kind: ItemKind) {
         match kind {
             ItemKind::Medkit => self.heal(40.0),
@@ -156,24 +378,142 @@ impl Character {
             | ItemKind::M4
             | ItemKind::Glock
             | ItemKind::Ammo
+            | ItemKind::ShotgunShells
             | ItemKind::RailGun
+            | ItemKind::Shotgun
             | ItemKind::Grenade
+            | ItemKind::Armor
             | ItemKind::MasterKey => (),
         }
     }
 
+    fn ammo_pickup_amount(ammo_item: ItemKind) -> u32 {
+        WeaponBalance::ammo_pickup_amount(ammo_item)
+    }
+
+    /// Applies given status effect. Effects of the same kind do not stack, instead the active
+    /// one is refreshed.
+    pub fn apply_status(&mut self, effect: StatusEffect) {
+        if let Some(active) = self
+            .status_effects
+            .iter_mut()
+            .find(|active| active.kind == effect.kind)
+        {
+            active.duration = active.duration.max(effect.duration);
+            active.tick_amount = active.tick_amount.max(effect.tick_amount);
+            active.who = effect.who;
+        } else {
+            self.status_effects.push(effect);
+        }
+    }
+
+    pub fn clear_status(&mut self, kind: StatusEffectKind) {
+        self.status_effects.retain(|effect| effect.kind != kind);
+    }
+
+    pub fn clear_all_statuses(&mut self) {
+        self.status_effects.clear();
+    }
+
+    pub fn has_status(&self, kind: StatusEffectKind) -> bool {
+        self.status_effects.iter().any(|effect| effect.kind == kind)
+    }
+
+    pub fn status_effects(&self) -> &[StatusEffect] {
+        &self.status_effects
+    }
+
+    /// Makes the character invulnerable to attacks of other actors for given amount of time.
+    /// Environmental damage (falls, death zones) is still applied.
+    pub fn protect(&mut self, duration: f32) {
+        self.spawn_protection = duration;
+    }
+
+    pub fn is_protected(&self) -> bool {
+        self.spawn_protection > 0.0
+    }
+
+    pub fn set_invulnerable(&mut self, invulnerable: bool) {
+        self.invulnerable = invulnerable;
+    }
+
+    pub fn is_invulnerable(&self) -> bool {
+        self.invulnerable
+    }
+
+    /// Counts down spawn protection and makes the given model blink while it lasts (unless
+    /// flashing is disabled).
+    pub fn update_spawn_protection(
+        &mut self,
+        graph: &mut Graph,
+        model: Handle<Node>,
+        flashing: bool,
+        dt: f32,
+    ) {
+        if !self.is_protected() {
+            return;
+        }
+
+        self.spawn_protection -= dt;
+
+        if let Some(model) = graph.try_get_mut(model) {
+            let visible = !flashing
+                || !self.is_protected()
+                || (self.spawn_protection / Self::SHIMMER_PERIOD) as u32 % 2 == 0;
+            model.set_visibility(visible);
+        }
+    }
+
+    /// Queues periodic damage of active status effects, must be called before polling commands.
+    pub fn update_status_effects(&mut self, dt: f32) {
+        if self.is_dead() {
+            self.status_effects.clear();
+            return;
+        }
+
+        for effect in self.status_effects.iter_mut() {
+            effect.duration -= dt;
+            effect.tick_timer += dt;
+            if effect.tick_timer >= Self::STATUS_TICK_INTERVAL {
+                effect.tick_timer -= Self::STATUS_TICK_INTERVAL;
+                self.commands.push_back(CharacterCommand::Damage {
+                    who: effect.who,
+                    hitbox: None,
+                    amount: effect.tick_amount,
+                    critical_shot_probability: 0.0,
+                });
+            }
+        }
+
+        self.status_effects.retain(|effect| effect.duration > 0.0);
+    }
+
     pub fn push_command(&mut self, command: CharacterCommand) {
         self.commands.push_back(command);
     }
 
+    /// Checks whether the damage initiator (a character or a weapon) is on the same team as
+    /// this character. Self-damage is not counted as a damage from a teammate.
+    fn is_teammate(&self, self_handle: Handle<Node>, who: Handle<Node>, graph: &Graph) -> bool {
+        let attacker = try_weapon_ref(who, graph).map_or(who, |w| w.owner());
+        attacker != self_handle
+            && try_get_character_ref(attacker, graph).map_or(false, |c| c.team == self.team)
+    }
+
+    /// Executes the next command and returns it along with a flag that tells whether the command
+    /// had any effect. Damage and status effects are rejected for protected and invulnerable
+    /// characters and for teammates when friendly fire is off.
     pub fn poll_command(
         &mut self,
         scene: &mut Scene,
         self_handle: Handle<Node>,
         resource_manager: &ResourceManager,
         sound_manager: &SoundManager,
-    ) -> Option<CharacterCommand> {
+        sender: &MessageSender,
+        friendly_fire: bool,
+    ) -> Option<(CharacterCommand, bool)> {
         if let Some(command) = self.commands.pop_front() {
+            let mut applied = true;
             match command {
                 CharacterCommand::SelectWeapon(kind) => self.select_weapon(kind, &mut scene.graph),
                 CharacterCommand::AddWeapon(kind) => {
@@ -196,9 +536,24 @@ impl Character {
                     let position = scene.graph[item_handle].global_position();
                     let item = item_mut(item_handle, &mut scene.graph);
 
+                    // Someone else picked up the item in this frame.
+                    if !item.is_available() {
+                        return Some((command, false));
+                    }
+
                     let kind = item.get_kind();
+                    let stack_size = item.stack_size;
+                    let is_dropped = item.is_dropped();
 
-                    scene.graph.remove_node(item_handle);
+                    if !item.pick_up() {
+                        let root = item.root(item_handle);
+                        scene.graph.remove_node(root);
+                    }
+
+                    sender.send(Message::ItemPickedUp {
+                        actor: self_handle,
+                        kind,
+                    });
 
                     sound_manager.play_sound(
                         &mut scene.graph,
@@ -216,7 +571,8 @@ impl Character {
                         | ItemKind::PlasmaGun
                         | ItemKind::M4
                         | ItemKind::Glock
-                        | ItemKind::RailGun => {
+                        | ItemKind::RailGun
+                        | ItemKind::Shotgun => {
                             let weapon_kind = kind.associated_weapon().unwrap();
 
                             let mut found = false;
@@ -228,15 +584,24 @@ impl Character {
                                 }
                             }
                             if found {
-                                self.inventory.add_item(ItemKind::Ammo, 24);
+                                let ammo_item = Weapon::definition(weapon_kind).ammo_item;
+                                self.inventory
+                                    .add_item(ammo_item, Self::ammo_pickup_amount(ammo_item));
                             } else {
                                 // Finally if actor does not have such weapon, give new one to him.
                                 self.commands
                                     .push_back(CharacterCommand::AddWeapon(weapon_kind));
                             }
                         }
-                        ItemKind::Ammo => {
-                            self.inventory.add_item(ItemKind::Ammo, 24);
+                        ItemKind::Ammo | ItemKind::ShotgunShells => {
+                            // Placed ammo boxes give standard amount of ammo, dropped ammo keeps
+                            // exact amount of ammo it was dropped with.
+                            let amount = if is_dropped {
+                                stack_size
+                            } else {
+                                Self::ammo_pickup_amount(kind)
+                            };
+                            self.inventory.add_item(kind, amount);
                         }
                         ItemKind::Grenade => {
                             self.inventory.add_item(ItemKind::Grenade, 1);
@@ -244,6 +609,7 @@ impl Character {
                         ItemKind::MasterKey => {
                             self.inventory.add_item(ItemKind::MasterKey, 1);
                         }
+                        ItemKind::Armor => self.restore_armor(),
                     }
                 }
                 CharacterCommand::DropItems { item, count } => {
@@ -255,26 +621,93 @@ impl Character {
                         if let Some(weapon_kind) = item.associated_weapon() {
                             for weapon in weapons {
                                 if weapon_ref(weapon, &scene.graph).kind() == weapon_kind {
+                                    self.remove_weapon(weapon, &mut scene.graph);
                                     scene.graph.remove_node(weapon);
                                 }
                             }
                         }
 
-                        Item::add_to_scene(
+                        let mut rng = fyrox::rand::thread_rng();
+                        let angle = rng.gen_range(0.0..std::f32::consts::TAU);
+                        let speed = rng.gen_range(0.5..2.0);
+                        let velocity = Vector3::new(
+                            angle.cos() * speed,
+                            rng.gen_range(1.5..3.0),
+                            angle.sin() * speed,
+                        );
+
+                        Item::add_to_scene_with_velocity(
                             scene,
                             resource_manager.clone(),
                             item,
                             drop_position,
-                            true,
+                            velocity,
+                            count,
                         );
                     }
                 }
-                CharacterCommand::Damage { amount, .. } => {
-                    self.damage(amount);
+                CharacterCommand::Damage { who, amount, .. } => {
+                    let is_protected = self.is_protected() && who.is_some();
+                    applied = false;
+                    if !is_protected
+                        && (friendly_fire || !self.is_teammate(self_handle, who, &scene.graph))
+                    {
+                        applied = !self.invulnerable;
+                        // Invulnerable characters are still hit, so hit markers and reactions
+                        // are shown, but the damage is zero.
+                        let amount = if self.invulnerable { 0.0 } else { amount };
+                        let was_alive = !self.is_dead();
+                        let applied = self.damage(amount);
+                        let killed = was_alive && self.is_dead();
+                        // Stats must see the damage after armor.
+                        sender.send(Message::ActorDamaged {
+                            actor: self_handle,
+                            who,
+                            amount: applied,
+                            killed,
+                        });
+                        sender.send(Message::HitConfirmed {
+                            who,
+                            position: self.position(&scene.graph),
+                            amount: amount.abs(),
+                            killed,
+                        });
+                        if let Some(attacker) = scene.graph.try_get(who) {
+                            sender.send(Message::DamageDirection {
+                                actor: self_handle,
+                                direction: attacker.global_position() - self.position(&scene.graph),
+                                amount: amount.abs(),
+                            });
+                        }
+                    }
+                }
+                CharacterCommand::Knockback {
+                    who,
+                    direction,
+                    amount,
+                } => {
+                    let is_protected = self.is_protected() && who.is_some();
+                    applied = !self.is_dead()
+                        && !is_protected
+                        && !self.invulnerable
+                        && (friendly_fire || !self.is_teammate(self_handle, who, &scene.graph));
+                    if applied {
+                        self.apply_knockback(direction, amount);
+                    }
+                }
+                CharacterCommand::ApplyStatus(effect) => {
+                    applied = !self.is_dead()
+                        && !self.is_protected()
+                        && !self.invulnerable
+                        && (friendly_fire
+                            || !self.is_teammate(self_handle, effect.who, &scene.graph));
+                    if applied {
+                        self.apply_status(effect);
+                    }
                 }
             }
 
-            Some(command)
+            Some((command, applied))
         } else {
             None
         }
@@ -410,9 +843,43 @@ pub struct HitBox {
     pub damage_factor: f32,
     pub movement_speed_factor: f32,
     pub is_head: bool,
+    /// Arms and legs, detected by the name of the bone, see [`Character::resolve_hit_boxes`].
+    #[visit(skip)]
+    #[reflect(hidden)]
+    pub is_limb: bool,
 }
 
 impl HitBox {
+    pub const HEAD_DAMAGE_FACTOR: f32 = 2.0;
+    pub const TORSO_DAMAGE_FACTOR: f32 = 1.0;
+    pub const LIMB_DAMAGE_FACTOR: f32 = 0.7;
+
+    /// Returns damage multiplier for a hit into this hit box. Hit boxes with unset (zero) damage
+    /// factor are treated as head, limb or torso depending on `is_head` and `is_limb` flags.
+    pub fn effective_damage_factor(&self) -> f32 {
+        if self.damage_factor > 0.0 {
+            self.damage_factor
+        } else if self.is_head {
+            Self::HEAD_DAMAGE_FACTOR
+        } else if self.is_limb {
+            Self::LIMB_DAMAGE_FACTOR
+        } else {
+            Self::TORSO_DAMAGE_FACTOR
+        }
+    }
+
+    fn is_limb_bone_name(name: &str) -> bool {
+        ["Arm", "Hand", "Leg", "Foot"]
+            .iter()
+            .any(|part| name.contains(part))
+    }
+
+    /// Damage multiplier for an optional hit box, hits that cannot be attributed to any hit box
+    /// are counted as torso hits.
+    pub fn damage_factor_of(hit_box: Option<HitBox>) -> f32 {
+        hit_box.map_or(Self::TORSO_DAMAGE_FACTOR, |h| h.effective_damage_factor())
+    }
+
     pub fn remap_handles(&mut self, old_new_mapping: &NodeHandleMap) {
         old_new_mapping.map(&mut self.collider);
     }
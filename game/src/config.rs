@@ -7,26 +7,90 @@ use std::fs::File;
 #[derive(Deserialize, Serialize, Clone)]
 pub struct SoundConfig {
     pub master_volume: f32,
+    #[serde(default = "default_sfx_volume")]
+    pub sfx_volume: f32,
     pub music_volume: f32,
     pub use_hrtf: bool,
 }
 
+fn default_sfx_volume() -> f32 {
+    1.0
+}
+
 impl Default for SoundConfig {
     fn default() -> Self {
         Self {
             master_volume: 1.0,
+            sfx_volume: default_sfx_volume(),
             music_volume: 0.5,
             use_hrtf: true,
         }
     }
 }
 
+/// Settings for players sensitive to violent or intense visuals.
+#[derive(Deserialize, Serialize, Clone)]
+pub struct AccessibilityConfig {
+    /// Blood sprays, blood decals and gibs.
+    #[serde(default = "default_enabled")]
+    pub gore: bool,
+    /// Multiplier of camera shake amplitude, zero disables shaking.
+    #[serde(default = "default_camera_shake_scale")]
+    pub camera_shake_scale: f32,
+    /// Blinking of characters with spawn protection.
+    #[serde(default = "default_enabled")]
+    pub flashing: bool,
+    /// Turns the view to the closest visible enemy once, when the player starts aiming.
+    #[serde(default)]
+    pub aim_snap: bool,
+    /// Max angle (in degrees) between the view direction and an enemy that can be snapped to.
+    #[serde(default = "default_aim_snap_angle")]
+    pub aim_snap_angle: f32,
+    /// Fraction of the angle to an enemy the view is turned by, 1.0 aims right at the enemy.
+    #[serde(default = "default_aim_snap_strength")]
+    pub aim_snap_strength: f32,
+}
+
+fn default_enabled() -> bool {
+    true
+}
+
+fn default_camera_shake_scale() -> f32 {
+    1.0
+}
+
+fn default_aim_snap_angle() -> f32 {
+    10.0
+}
+
+fn default_aim_snap_strength() -> f32 {
+    1.0
+}
+
+impl Default for AccessibilityConfig {
+    fn default() -> Self {
+        Self {
+            gore: default_enabled(),
+            camera_shake_scale: default_camera_shake_scale(),
+            flashing: default_enabled(),
+            aim_snap: false,
+            aim_snap_angle: default_aim_snap_angle(),
+            aim_snap_strength: default_aim_snap_strength(),
+        }
+    }
+}
+
 #[derive(Deserialize, Serialize, Default, Clone)]
 pub struct Config {
     pub graphics_settings: QualitySettings,
-    pub controls: ControlScheme,
+    /// Controls are stored in their own file (see [`ControlScheme::PATH`]), this field is only
+    /// read to migrate bindings from older settings files.
+    #[serde(default, skip_serializing)]
+    pub controls: Option<ControlScheme>,
     pub sound: SoundConfig,
     pub show_debug_info: bool,
+    #[serde(default)]
+    pub accessibility: AccessibilityConfig,
 }
 
 #[derive(Debug)]
@@ -57,18 +121,54 @@ impl Config {
 
     pub fn save(
         context: &PluginContext,
-        control_scheme: ControlScheme,
         sound_config: SoundConfig,
         show_debug_info: bool,
+        accessibility: AccessibilityConfig,
     ) -> Result<(), ConfigError> {
         let config = Self {
             graphics_settings: context.renderer.get_quality_settings(),
-            controls: control_scheme,
+            controls: None,
             sound: sound_config,
             show_debug_info,
+            accessibility,
         };
         let file = File::create(Self::PATH)?;
         ron::ser::to_writer_pretty(file, &config, PrettyConfig::default())?;
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_accessibility_defaults() {
+        // Settings saved before accessibility options were added have none of the fields.
+        let config: AccessibilityConfig = ron::de::from_str("()").unwrap();
+        assert!(config.gore);
+        assert_eq!(config.camera_shake_scale, 1.0);
+        assert!(config.flashing);
+        assert!(!config.aim_snap);
+        assert_eq!(config.aim_snap_angle, 10.0);
+        assert_eq!(config.aim_snap_strength, 1.0);
+    }
+
+    #[test]
+    fn test_accessibility_round_trip() {
+        let config = AccessibilityConfig {
+            gore: false,
+            camera_shake_scale: 0.3,
+            flashing: false,
+            ..Default::default()
+        };
+
+        let string = ron::ser::to_string(&config).unwrap();
+        let loaded: AccessibilityConfig = ron::de::from_str(&string).unwrap();
+
+        assert!(!loaded.gore);
+        assert_eq!(loaded.camera_shake_scale, 0.3);
+        assert!(!loaded.flashing);
+        assert!(!loaded.aim_snap);
+    }
+}
@@ -1,5 +1,8 @@
+use crate::{config::ConfigError, player::HealthMode};
 use fyrox::event::VirtualKeyCode;
+use ron::ser::PrettyConfig;
 use serde::{Deserialize, Serialize};
+use std::{fs::File, path::Path};
 
 #[derive(Copy, Clone, PartialEq, Eq, Hash, Deserialize, Serialize)]
 pub enum ControlButton {
@@ -44,6 +47,14 @@ pub struct ControlScheme {
     pub next_weapon: ControlButtonDefinition,
     pub prev_weapon: ControlButtonDefinition,
     pub run: ControlButtonDefinition,
+    #[serde(default = "default_lean_left")]
+    pub lean_left: ControlButtonDefinition,
+    #[serde(default = "default_lean_right")]
+    pub lean_right: ControlButtonDefinition,
+    #[serde(default = "default_melee")]
+    pub melee: ControlButtonDefinition,
+    #[serde(default = "default_free_fly_camera")]
+    pub free_fly_camera: ControlButtonDefinition,
     pub aim: ControlButtonDefinition,
     pub toss_grenade: ControlButtonDefinition,
     pub journal: ControlButtonDefinition,
@@ -55,12 +66,156 @@ pub struct ControlScheme {
     pub inventory: ControlButtonDefinition,
     pub action: ControlButtonDefinition,
     pub drop_item: ControlButtonDefinition,
+    #[serde(default = "default_drop_weapon")]
+    pub drop_weapon: ControlButtonDefinition,
+    #[serde(default = "default_hold_breath")]
+    pub hold_breath: ControlButtonDefinition,
+    #[serde(default = "default_toggle_suppressor")]
+    pub toggle_suppressor: ControlButtonDefinition,
     pub cursor_up: ControlButtonDefinition,
     pub cursor_down: ControlButtonDefinition,
     pub cursor_left: ControlButtonDefinition,
     pub cursor_right: ControlButtonDefinition,
     pub mouse_sens: f32,
     pub mouse_y_inverse: bool,
+    /// Magnitude of procedural weapon sway, zero disables it.
+    #[serde(default = "default_weapon_sway")]
+    pub weapon_sway: f32,
+    /// Vertical field of view (in degrees) of the player's camera when not aiming.
+    #[serde(default = "default_field_of_view")]
+    pub field_of_view: f32,
+    /// Whether ammo and health items are picked up by walking over them. Weapons are always
+    /// picked up manually.
+    #[serde(default)]
+    pub auto_pickup: bool,
+    /// Whether nearby ammo and health items drift towards the player when they're running low.
+    #[serde(default = "default_item_magnet")]
+    pub item_magnet: bool,
+    /// Speed multiplier of weapon switch animations.
+    #[serde(default = "default_weapon_switch_speed")]
+    pub weapon_switch_speed: f32,
+    /// Whether the player can drop the only weapon they have.
+    #[serde(default)]
+    pub allow_drop_last_weapon: bool,
+    #[serde(default)]
+    pub difficulty: Difficulty,
+}
+
+#[derive(Copy, Clone, PartialEq, Eq, Debug, Deserialize, Serialize)]
+pub enum Difficulty {
+    Easy,
+    Normal,
+    Hard,
+}
+
+impl Default for Difficulty {
+    fn default() -> Self {
+        Self::Normal
+    }
+}
+
+impl Difficulty {
+    pub const ALL: [Difficulty; 3] = [Difficulty::Easy, Difficulty::Normal, Difficulty::Hard];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Difficulty::Easy => "Easy",
+            Difficulty::Normal => "Normal",
+            Difficulty::Hard => "Hard",
+        }
+    }
+
+    pub fn index(self) -> usize {
+        Self::ALL
+            .iter()
+            .position(|d| *d == self)
+            .unwrap_or_default()
+    }
+
+    /// Health mode of the player on this difficulty - health regenerates on easy and bleeds out
+    /// on hard.
+    pub fn health_mode(self) -> HealthMode {
+        match self {
+            Difficulty::Easy => HealthMode::Regeneration,
+            Difficulty::Normal => HealthMode::Normal,
+            Difficulty::Hard => HealthMode::BleedOut,
+        }
+    }
+
+    /// Multiplier of the spread of bot shots caused by movement, bots that stand still are
+    /// precise on any difficulty.
+    pub fn bot_spread_scale(self) -> f32 {
+        match self {
+            Difficulty::Easy => 1.5,
+            Difficulty::Normal => 1.0,
+            Difficulty::Hard => 0.6,
+        }
+    }
+}
+
+fn default_weapon_sway() -> f32 {
+    1.0
+}
+
+fn default_field_of_view() -> f32 {
+    75.0
+}
+
+fn default_item_magnet() -> bool {
+    true
+}
+
+fn default_weapon_switch_speed() -> f32 {
+    1.0
+}
+
+fn default_lean_left() -> ControlButtonDefinition {
+    ControlButtonDefinition {
+        description: "Lean Left".to_string(),
+        button: ControlButton::Key(VirtualKeyCode::Q),
+    }
+}
+
+fn default_lean_right() -> ControlButtonDefinition {
+    ControlButtonDefinition {
+        description: "Lean Right".to_string(),
+        button: ControlButton::Key(VirtualKeyCode::C),
+    }
+}
+
+fn default_melee() -> ControlButtonDefinition {
+    ControlButtonDefinition {
+        description: "Melee Attack".to_string(),
+        button: ControlButton::Key(VirtualKeyCode::V),
+    }
+}
+
+fn default_drop_weapon() -> ControlButtonDefinition {
+    ControlButtonDefinition {
+        description: "Drop Weapon".to_string(),
+        button: ControlButton::Key(VirtualKeyCode::X),
+    }
+}
+
+fn default_hold_breath() -> ControlButtonDefinition {
+    ControlButtonDefinition {
+        description: "Hold Breath".to_string(),
+        button: ControlButton::Key(VirtualKeyCode::Z),
+    }
+}
+
+fn default_toggle_suppressor() -> ControlButtonDefinition {
+    ControlButtonDefinition {
+        description: "Toggle Suppressor".to_string(),
+        button: ControlButton::Key(VirtualKeyCode::B),
+    }
+}
+
+fn default_free_fly_camera() -> ControlButtonDefinition {
+    ControlButtonDefinition {
+        description: "Free Fly Camera".to_string(),
+        button: ControlButton::Key(VirtualKeyCode::F8),
+    }
 }
 
 impl Default for ControlScheme {
@@ -102,6 +257,10 @@ impl Default for ControlScheme {
                 description: "Run".to_string(),
                 button: ControlButton::Key(VirtualKeyCode::LShift),
             },
+            lean_left: default_lean_left(),
+            lean_right: default_lean_right(),
+            melee: default_melee(),
+            free_fly_camera: default_free_fly_camera(),
             aim: ControlButtonDefinition {
                 description: "Aim".to_string(),
                 button: ControlButton::Mouse(3),
@@ -146,6 +305,9 @@ impl Default for ControlScheme {
                 description: "Drop Item".to_string(),
                 button: ControlButton::Key(VirtualKeyCode::R),
             },
+            drop_weapon: default_drop_weapon(),
+            hold_breath: default_hold_breath(),
+            toggle_suppressor: default_toggle_suppressor(),
             cursor_up: ControlButtonDefinition {
                 description: "Cursor Up".to_string(),
                 button: ControlButton::Key(VirtualKeyCode::Up),
@@ -164,12 +326,23 @@ impl Default for ControlScheme {
             },
             mouse_sens: 0.3,
             mouse_y_inverse: false,
+            weapon_sway: default_weapon_sway(),
+            field_of_view: default_field_of_view(),
+            auto_pickup: false,
+            item_magnet: default_item_magnet(),
+            weapon_switch_speed: default_weapon_switch_speed(),
+            allow_drop_last_weapon: false,
+            difficulty: Default::default(),
         }
     }
 }
 
 impl ControlScheme {
-    pub fn buttons_mut(&mut self) -> [&mut ControlButtonDefinition; 24] {
+    pub const PATH: &'static str = "data/configs/controls.ron";
+    /// Control schemes of additional local players, the first player uses the one from `PATH`.
+    pub const CO_OP_PATH: &'static str = "data/configs/co_op_controls.ron";
+
+    pub fn buttons_mut(&mut self) -> [&mut ControlButtonDefinition; 31] {
         [
             &mut self.move_forward,
             &mut self.move_backward,
@@ -177,14 +350,20 @@ impl ControlScheme {
             &mut self.move_right,
             &mut self.action,
             &mut self.drop_item,
+            &mut self.drop_weapon,
             &mut self.jump,
             &mut self.shoot,
             &mut self.next_weapon,
             &mut self.prev_weapon,
             &mut self.run,
+            &mut self.lean_left,
+            &mut self.lean_right,
             &mut self.aim,
+            &mut self.hold_breath,
+            &mut self.toggle_suppressor,
             &mut self.inventory,
             &mut self.toss_grenade,
+            &mut self.melee,
             &mut self.journal,
             &mut self.flash_light,
             &mut self.grab_pistol,
@@ -195,10 +374,11 @@ impl ControlScheme {
             &mut self.cursor_down,
             &mut self.cursor_left,
             &mut self.cursor_right,
+            &mut self.free_fly_camera,
         ]
     }
 
-    pub fn buttons(&self) -> [&ControlButtonDefinition; 24] {
+    pub fn buttons(&self) -> [&ControlButtonDefinition; 31] {
         [
             &self.move_forward,
             &self.move_backward,
@@ -206,14 +386,20 @@ impl ControlScheme {
             &self.move_right,
             &self.action,
             &self.drop_item,
+            &self.drop_weapon,
             &self.jump,
             &self.shoot,
             &self.next_weapon,
             &self.prev_weapon,
             &self.run,
+            &self.lean_left,
+            &self.lean_right,
             &self.aim,
+            &self.hold_breath,
+            &self.toggle_suppressor,
             &self.inventory,
             &self.toss_grenade,
+            &self.melee,
             &self.journal,
             &self.flash_light,
             &self.grab_pistol,
@@ -224,10 +410,27 @@ impl ControlScheme {
             &self.cursor_down,
             &self.cursor_left,
             &self.cursor_right,
+            &self.free_fly_camera,
         ]
     }
 
     pub fn reset(&mut self) {
         *self = Default::default();
     }
+
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Self, ConfigError> {
+        let file = File::open(path)?;
+        Ok(ron::de::from_reader(file)?)
+    }
+
+    pub fn load_list<P: AsRef<Path>>(path: P) -> Result<Vec<Self>, ConfigError> {
+        let file = File::open(path)?;
+        Ok(ron::de::from_reader(file)?)
+    }
+
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> Result<(), ConfigError> {
+        let file = File::create(path)?;
+        ron::ser::to_writer_pretty(file, self, PrettyConfig::default())?;
+        Ok(())
+    }
 }
@@ -1,4 +1,7 @@
-use crate::{character::character_ref, current_level_mut, game_mut};
+use crate::{
+    character::character_ref, current_level_mut, game_mut, message::Message, sound::SoundManager,
+    MessageSender,
+};
 use fyrox::{
     core::{
         algebra::Vector3,
@@ -20,6 +23,7 @@ use fyrox::{
         mesh::Mesh,
         node::{Node, NodeHandle, TypeUuidProvider},
         rigidbody::RigidBody,
+        Scene,
     },
     script::{ScriptContext, ScriptDeinitContext, ScriptTrait},
     utils::log::Log,
@@ -105,6 +109,16 @@ pub struct Door {
     #[visit(skip)]
     open_request: Option<OpenRequest>,
 
+    // State requested by gameplay code (switches, scripted events), it is applied on next update.
+    #[reflect(hidden)]
+    #[visit(skip)]
+    forced_state: Option<DoorState>,
+
+    // Progress of the light color transition, 0.0 - "closed" color, 1.0 - "locked" color.
+    #[reflect(hidden)]
+    #[visit(skip)]
+    lock_color_factor: f32,
+
     #[reflect(hidden)]
     #[visit(skip)]
     self_handle: Handle<Node>,
@@ -131,6 +145,12 @@ impl ScriptTrait for Door {
 
     fn on_start(&mut self, ctx: &mut ScriptContext) {
         self.self_handle = ctx.handle;
+        // Lights show the current state right away, the transition is only for state changes.
+        self.lock_color_factor = if matches!(self.state, DoorState::Locked) {
+            1.0
+        } else {
+            0.0
+        };
 
         let game = game_mut(ctx.plugins);
         let texture = game.door_ui_container.create_ui(
@@ -159,6 +179,7 @@ impl ScriptTrait for Door {
     fn on_update(&mut self, ctx: &mut ScriptContext) {
         let game = game_mut(ctx.plugins);
         let level = game.level.as_ref().unwrap();
+        let powered = !level.power_outage;
 
         let speed = 0.55;
 
@@ -194,6 +215,7 @@ impl ScriptTrait for Door {
 
         if let Some(ui) = game.door_ui_container.get_ui_mut(ctx.handle) {
             let text = match self.state {
+                DoorState::Closed | DoorState::Locked if !powered => "No Power",
                 DoorState::Opened => "Opened",
                 DoorState::Opening => "Opening...",
                 DoorState::Closed => {
@@ -236,11 +258,11 @@ impl ScriptTrait for Door {
             }
             DoorState::Closed => {
                 self.set_lights_enabled(&mut ctx.scene.graph, true);
-                self.set_lights_color(&mut ctx.scene.graph, Color::opaque(0, 200, 0));
+                self.update_lights_color(&mut ctx.scene.graph, 0.0, ctx.dt);
             }
             DoorState::Locked => {
                 self.set_lights_enabled(&mut ctx.scene.graph, true);
-                self.set_lights_color(&mut ctx.scene.graph, Color::opaque(200, 0, 0));
+                self.update_lights_color(&mut ctx.scene.graph, 1.0, ctx.dt);
             }
             DoorState::Broken | DoorState::Opened => {
                 self.set_lights_enabled(&mut ctx.scene.graph, false);
@@ -257,10 +279,25 @@ impl ScriptTrait for Door {
             );
         }
 
+        if !powered {
+            self.set_lights_enabled(&mut ctx.scene.graph, false);
+        }
+
         if let Some(open_request) = self.open_request.take() {
             let position = self.actual_position(&ctx.scene.graph);
 
-            if self.state == DoorState::Closed {
+            if !powered {
+                if matches!(self.state, DoorState::Closed | DoorState::Locked) {
+                    level.sound_manager.play_sound(
+                        &mut ctx.scene.graph,
+                        "data/sounds/door_deny.ogg",
+                        position,
+                        1.0,
+                        1.0,
+                        1.0,
+                    );
+                }
+            } else if self.state == DoorState::Closed {
                 self.state = DoorState::Opening;
 
                 level.sound_manager.play_sound(
@@ -304,6 +341,15 @@ impl ScriptTrait for Door {
                 }
             }
         }
+
+        if let Some(forced_state) = self.forced_state.take() {
+            self.apply_forced_state(
+                forced_state,
+                &mut ctx.scene.graph,
+                &level.sound_manager,
+                &game.message_sender,
+            );
+        }
     }
 
     fn id(&self) -> Uuid {
@@ -312,6 +358,23 @@ impl ScriptTrait for Door {
 }
 
 impl Door {
+    const CLOSED_LIGHT_COLOR: Color = Color::opaque(0, 200, 0);
+    const LOCKED_LIGHT_COLOR: Color = Color::opaque(200, 0, 0);
+    /// Time (in seconds) that is needed for lights to fade from one color to another.
+    const LIGHT_COLOR_TRANSITION_TIME: f32 = 0.4;
+
+    fn update_lights_color(&mut self, graph: &mut Graph, target_factor: f32, dt: f32) {
+        let step = dt / Self::LIGHT_COLOR_TRANSITION_TIME;
+        if self.lock_color_factor < target_factor {
+            self.lock_color_factor = (self.lock_color_factor + step).min(target_factor);
+        } else {
+            self.lock_color_factor = (self.lock_color_factor - step).max(target_factor);
+        }
+
+        let color = Self::CLOSED_LIGHT_COLOR.lerp(Self::LOCKED_LIGHT_COLOR, self.lock_color_factor);
+        self.set_lights_color(graph, color);
+    }
+
     fn set_lights_color(&self, graph: &mut Graph, color: Color) {
         for &light in self.lights.iter() {
             if let Some(light_ref) = graph[*light].query_component_mut::<BaseLight>() {
@@ -330,6 +393,10 @@ impl Door {
         self.initial_position
     }
 
+    pub fn state(&self) -> DoorState {
+        self.state
+    }
+
     pub fn actual_position(&self, graph: &Graph) -> Vector3<f32> {
         graph[self.self_handle].global_position()
     }
@@ -370,11 +437,85 @@ impl Door {
     pub fn try_open(&mut self, has_key: bool) {
         self.open_request = Some(OpenRequest { has_key });
     }
+
+    /// Checks whether the door can be forced into the given state. Broken doors can't be
+    /// repaired and only stable states can be requested.
+    pub fn can_force_state(&self, state: DoorState) -> bool {
+        self.state != DoorState::Broken && !matches!(state, DoorState::Opening | DoorState::Closing)
+    }
+
+    /// Requests the door to switch to the given state regardless of actors nearby. Returns
+    /// `false` if the transition is illegal.
+    pub fn force_state(&mut self, state: DoorState) -> bool {
+        if self.can_force_state(state) {
+            self.forced_state = Some(state);
+            true
+        } else {
+            false
+        }
+    }
+
+    fn apply_forced_state(
+        &mut self,
+        forced_state: DoorState,
+        graph: &mut Graph,
+        sound_manager: &SoundManager,
+        sender: &MessageSender,
+    ) {
+        let (new_state, sound) = match (forced_state, self.state) {
+            (DoorState::Opened, DoorState::Closed | DoorState::Locked | DoorState::Closing) => {
+                (DoorState::Opening, "data/sounds/door_open.ogg")
+            }
+            (DoorState::Closed, DoorState::Opened | DoorState::Opening) => {
+                (DoorState::Closing, "data/sounds/door_close.ogg")
+            }
+            (DoorState::Closed, DoorState::Locked) => {
+                (DoorState::Closed, "data/sounds/access_granted.ogg")
+            }
+            (DoorState::Locked, DoorState::Closed) => {
+                (DoorState::Locked, "data/sounds/door_deny.ogg")
+            }
+            (DoorState::Locked, DoorState::Opened | DoorState::Opening) => {
+                // Door must be closed first, it will be locked once closed.
+                self.forced_state = Some(DoorState::Locked);
+                (DoorState::Closing, "data/sounds/door_close.ogg")
+            }
+            (DoorState::Locked, DoorState::Closing) => {
+                self.forced_state = Some(DoorState::Locked);
+                return;
+            }
+            (DoorState::Broken, state) if state != DoorState::Broken => {
+                (DoorState::Broken, "data/sounds/bullet_impact_metal.ogg")
+            }
+            _ => return,
+        };
+
+        self.state = new_state;
+
+        if new_state == DoorState::Broken {
+            sender.send(Message::DoorBreached {
+                door: self.self_handle,
+            });
+        }
+
+        let position = self.actual_position(graph);
+        sound_manager.play_sound(graph, sound, position, 1.0, 1.0, 1.0);
+    }
 }
 
 #[derive(Default, Visit)]
 pub struct DoorContainer {
     pub doors: Vec<Handle<Node>>,
+    #[visit(skip)]
+    blocked_doorways: Vec<BlockedDoorway>,
+}
+
+/// Navmesh vertices around a locked door.
+#[derive(Default, Debug)]
+struct BlockedDoorway {
+    door: Handle<Node>,
+    /// Pairs of navmesh index and vertex index.
+    vertices: Vec<(usize, usize)>,
 }
 
 pub fn door_ref(handle: Handle<Node>, graph: &Graph) -> &Door {
@@ -392,9 +533,114 @@ pub fn door_mut(handle: Handle<Node>, graph: &mut Graph) -> &mut Door {
 }
 
 impl DoorContainer {
+    /// Navmesh vertices this close to a locked door are blocked.
+    const BLOCK_RADIUS: f32 = 1.25;
+    /// Path cost multiplier of blocked vertices, bots go around locked doors unless there is no
+    /// other way.
+    const BLOCKED_VERTEX_PENALTY: f32 = 1000.0;
+    /// Penalty of navmesh vertices by default.
+    const DEFAULT_VERTEX_PENALTY: f32 = 1.0;
+
     pub fn new() -> Self {
         Self {
             doors: Default::default(),
+            blocked_doorways: Default::default(),
+        }
+    }
+
+    /// Navmesh penalties are saved with the scene, but blocked doorways are not, so penalties
+    /// around every door are reset after loading. Doors that are still locked are blocked again
+    /// on the next update.
+    pub fn resolve(&mut self, scene: &mut Scene) {
+        self.blocked_doorways.clear();
+
+        let graph = &scene.graph;
+        let door_positions = self
+            .doors
+            .iter()
+            .filter_map(|&door| graph.try_get(door).and_then(|n| n.try_get_script::<Door>()))
+            .map(|door| door.initial_position())
+            .collect::<Vec<_>>();
+
+        for navmesh in scene.navmeshes.iter_mut() {
+            for vertex in navmesh.vertices_mut().iter_mut() {
+                if door_positions.iter().any(|door_position| {
+                    vertex.position().metric_distance(door_position) < Self::BLOCK_RADIUS
+                }) {
+                    vertex.set_penalty(Self::DEFAULT_VERTEX_PENALTY);
+                }
+            }
+        }
+    }
+
+    /// Makes bots path around locked doors by raising path cost of navmesh vertices near them.
+    /// Vertices are looked up only when a door gets locked.
+    pub fn update_navmesh_blocking(&mut self, scene: &mut Scene) {
+        let graph = &scene.graph;
+        let navmeshes = &mut scene.navmeshes;
+
+        let is_locked = |door: Handle<Node>| {
+            graph
+                .try_get(door)
+                .and_then(|n| n.try_get_script::<Door>())
+                .map_or(false, |door| door.state() == DoorState::Locked)
+        };
+
+        self.blocked_doorways.retain(|doorway| {
+            let locked = is_locked(doorway.door);
+            if !locked {
+                for &(navmesh_index, vertex_index) in doorway.vertices.iter() {
+                    if let Some(vertex) = navmeshes
+                        .iter_mut()
+                        .nth(navmesh_index)
+                        .and_then(|navmesh| navmesh.vertices_mut().get_mut(vertex_index))
+                    {
+                        vertex.set_penalty(Self::DEFAULT_VERTEX_PENALTY);
+                    }
+                }
+            }
+            locked
+        });
+
+        for &door in self.doors.iter() {
+            if !is_locked(door) || self.blocked_doorways.iter().any(|d| d.door == door) {
+                continue;
+            }
+
+            let door_position = door_ref(door, graph).initial_position();
+            let mut vertices = Vec::new();
+            for (navmesh_index, navmesh) in navmeshes.iter_mut().enumerate() {
+                for (vertex_index, vertex) in navmesh.vertices_mut().iter_mut().enumerate() {
+                    if vertex.position().metric_distance(&door_position) < Self::BLOCK_RADIUS {
+                        vertex.set_penalty(Self::BLOCKED_VERTEX_PENALTY);
+                        vertices.push((navmesh_index, vertex_index));
+                    }
+                }
+            }
+            self.blocked_doorways
+                .push(BlockedDoorway { door, vertices });
+        }
+    }
+
+    /// Forces the door into the given state. Returns `false` if there is no such door or the
+    /// transition is illegal (for example a broken door can't be repaired).
+    pub fn set_state(&self, door: Handle<Node>, state: DoorState, graph: &mut Graph) -> bool {
+        self.doors.contains(&door)
+            && graph
+                .try_get_mut(door)
+                .and_then(|node| node.try_get_script_mut::<Door>())
+                .map_or(false, |door| door.force_state(state))
+    }
+
+    /// Searches for a door that contains the given node (a collider, a screen, etc.).
+    pub fn find_door_by_node(&self, node: Handle<Node>, graph: &Graph) -> Option<Handle<Node>> {
+        let mut current = node;
+        while let Some(current_node) = graph.try_get(current) {
+            if self.doors.contains(&current) {
+                return Some(current);
+            }
+            current = current_node.parent();
         }
+        None
     }
 }
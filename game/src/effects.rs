@@ -31,6 +31,8 @@ pub enum EffectKind {
     BulletImpact,
     BloodSpray,
     Smoke,
+    /// Used when an actor is torn apart by a massive hit.
+    Gibs,
 }
 
 /// # Notes
@@ -47,6 +49,7 @@ pub fn create(
         EffectKind::BulletImpact => create_bullet_impact(graph, resource_manager, pos, orientation),
         EffectKind::BloodSpray => create_blood_spray(graph, resource_manager, pos, orientation),
         EffectKind::Smoke => create_smoke(graph, resource_manager, pos, orientation),
+        EffectKind::Gibs => create_gibs(graph, resource_manager, pos, orientation),
     }
 }
 
@@ -147,6 +150,46 @@ fn create_blood_spray(
     .build(graph)
 }
 
+fn create_gibs(
+    graph: &mut Graph,
+    resource_manager: &ResourceManager,
+    pos: Vector3<f32>,
+    orientation: UnitQuaternion<f32>,
+) -> Handle<Node> {
+    ParticleSystemBuilder::new(
+        BaseBuilder::new().with_lifetime(1.5).with_local_transform(
+            TransformBuilder::new()
+                .with_local_position(pos)
+                .with_local_rotation(orientation)
+                .build(),
+        ),
+    )
+    .with_acceleration(Vector3::new(0.0, -0.0015, 0.0))
+    .with_color_over_lifetime_gradient({
+        let mut gradient = ColorGradient::new();
+        gradient.add_point(GradientPoint::new(0.00, Color::from_rgba(160, 0, 0, 255)));
+        gradient.add_point(GradientPoint::new(0.80, Color::from_rgba(120, 0, 0, 255)));
+        gradient.add_point(GradientPoint::new(1.00, Color::from_rgba(120, 0, 0, 0)));
+        gradient
+    })
+    .with_emitters(vec![SphereEmitterBuilder::new(
+        BaseEmitterBuilder::new()
+            .with_max_particles(400)
+            .with_spawn_rate(4000)
+            .with_size_modifier_range(-0.005..-0.0075)
+            .with_lifetime_range(0.5..1.5)
+            .with_size_range(0.02..0.06)
+            .with_x_velocity_range(-0.03..0.03)
+            .with_y_velocity_range(0.0..0.05)
+            .with_z_velocity_range(-0.03..0.03)
+            .resurrect_particles(false),
+    )
+    .with_radius(0.3)
+    .build()])
+    .with_texture(resource_manager.request_texture(Path::new("data/particles/dirt_01.png")))
+    .build(graph)
+}
+
 fn create_smoke(
     graph: &mut Graph,
     resource_manager: &ResourceManager,
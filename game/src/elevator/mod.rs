@@ -1,13 +1,17 @@
-use crate::current_level_mut;
+use crate::{character::character_ref, current_level_mut, current_level_ref};
 use fyrox::{
     core::{
+        algebra::Vector3,
+        pool::Handle,
         reflect::prelude::*,
         uuid::{uuid, Uuid},
         visitor::prelude::*,
     },
     impl_component_provider,
     scene::{
-        node::{NodeHandle, TypeUuidProvider},
+        collider::Collider,
+        graph::Graph,
+        node::{Node, NodeHandle, TypeUuidProvider},
         rigidbody::RigidBody,
     },
     script::{ScriptContext, ScriptDeinitContext, ScriptTrait},
@@ -31,6 +35,22 @@ impl Elevator {
             self.dest_floor = floor;
         }
     }
+
+    /// Returns true if the given collider is touching any collider of the elevator.
+    fn is_standing_on(graph: &Graph, elevator: Handle<Node>, collider: Handle<Node>) -> bool {
+        if let Some(collider_ref) = graph.try_get(collider).and_then(|n| n.cast::<Collider>()) {
+            let elevator_colliders = graph[elevator].children();
+            for contact in collider_ref.contacts(&graph.physics) {
+                if contact.has_any_active_contact
+                    && (elevator_colliders.contains(&contact.collider1)
+                        || elevator_colliders.contains(&contact.collider2))
+                {
+                    return true;
+                }
+            }
+        }
+        false
+    }
 }
 
 impl_component_provider!(Elevator);
@@ -73,12 +93,34 @@ impl ScriptTrait for Elevator {
         ) {
             let current_pos = context.scene.graph[**current].global_position();
             let dest_pos = context.scene.graph[**dest].global_position();
+            let mut displacement = Vector3::default();
             if let Some(rigid_body_ref) =
                 context.scene.graph[context.handle].cast_mut::<RigidBody>()
             {
                 let position = current_pos.lerp(&dest_pos, self.k);
+                displacement = position - **rigid_body_ref.local_transform().position();
                 rigid_body_ref.local_transform_mut().set_position(position);
             }
+
+            // Carry actors standing on the elevator, otherwise they will fall through the floor
+            // (on the way up) or will be hanging in the air (on the way down).
+            if displacement.norm_squared() > 0.0 {
+                if let Some(level) = current_level_ref(context.plugins) {
+                    for &actor in level.actors.iter() {
+                        let character = character_ref(actor, &context.scene.graph);
+                        if Self::is_standing_on(
+                            &context.scene.graph,
+                            context.handle,
+                            character.capsule_collider,
+                        ) {
+                            let body = character.body;
+                            let body_transform = context.scene.graph[body].local_transform_mut();
+                            let new_position = **body_transform.position() + displacement;
+                            body_transform.set_position(new_position);
+                        }
+                    }
+                }
+            }
         }
     }
 
@@ -0,0 +1,89 @@
+//! Directional damage indicator. Hits taken by the player are collected into arcs around the
+//! crosshair that point to attackers, hits from about the same direction share a single arc.
+
+use fyrox::core::algebra::Vector3;
+
+/// Hits with directions closer than this angle (in degrees) are merged into one arc.
+const ARC_MERGE_ANGLE: f32 = 30.0;
+/// Time (in seconds) an arc is visible after the last hit.
+const ARC_LIFETIME: f32 = 1.5;
+/// Damage at which an arc reaches its full intensity.
+const FULL_INTENSITY_DAMAGE: f32 = 50.0;
+
+#[derive(Clone, Debug)]
+pub struct DamageArc {
+    /// World-space yaw (in radians) of the direction from the player to the attacker.
+    yaw: f32,
+    damage: f32,
+    timer: f32,
+}
+
+impl DamageArc {
+    /// Angle (in radians) of the arc relative to the view direction with the given yaw, zero
+    /// means that the attacker is in front of the player, positive angles go counterclockwise.
+    pub fn relative_angle(&self, view_yaw: f32) -> f32 {
+        wrap_angle(self.yaw - view_yaw)
+    }
+
+    /// Opacity of the arc in [0; 1] range, it depends on accumulated damage and fades out
+    /// over time.
+    pub fn intensity(&self) -> f32 {
+        (self.damage / FULL_INTENSITY_DAMAGE).min(1.0) * (self.timer / ARC_LIFETIME)
+    }
+}
+
+#[derive(Default, Debug)]
+pub struct DamageIndicator {
+    arcs: Vec<DamageArc>,
+}
+
+impl DamageIndicator {
+    /// Registers a hit, `direction` points from the player to the attacker.
+    pub fn add_hit(&mut self, direction: Vector3<f32>, amount: f32) {
+        if direction.x == 0.0 && direction.z == 0.0 {
+            return;
+        }
+
+        let yaw = direction.x.atan2(direction.z);
+
+        if let Some(arc) = self
+            .arcs
+            .iter_mut()
+            .find(|arc| wrap_angle(arc.yaw - yaw).abs() <= ARC_MERGE_ANGLE.to_radians())
+        {
+            // Shift the arc towards the stronger hit.
+            let total = arc.damage + amount;
+            if total > 0.0 {
+                arc.yaw += wrap_angle(yaw - arc.yaw) * amount / total;
+            }
+            arc.damage = total;
+            arc.timer = ARC_LIFETIME;
+        } else {
+            self.arcs.push(DamageArc {
+                yaw,
+                damage: amount,
+                timer: ARC_LIFETIME,
+            });
+        }
+    }
+
+    pub fn update(&mut self, dt: f32) {
+        for arc in self.arcs.iter_mut() {
+            arc.timer -= dt;
+        }
+        self.arcs.retain(|arc| arc.timer > 0.0);
+    }
+
+    pub fn arcs(&self) -> &[DamageArc] {
+        &self.arcs
+    }
+
+    pub fn clear(&mut self) {
+        self.arcs.clear();
+    }
+}
+
+/// Wraps the angle into `[-pi; pi]` range.
+fn wrap_angle(angle: f32) -> f32 {
+    angle.sin().atan2(angle.cos())
+}
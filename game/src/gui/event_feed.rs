@@ -0,0 +1,79 @@
+//! Feed of recent gameplay events in the top right corner of the screen, see
+//! [`crate::level::event_log::EventLog`].
+
+use crate::level::Level;
+use fyrox::{
+    core::{color::Color, pool::Handle},
+    gui::{
+        brush::Brush,
+        formatted_text::WrapMode,
+        message::MessageDirection,
+        text::{TextBuilder, TextMessage},
+        ttf::SharedFont,
+        widget::{WidgetBuilder, WidgetMessage},
+        HorizontalAlignment, Thickness, UiNode, UserInterface, VerticalAlignment,
+    },
+};
+use std::fmt::Write;
+
+/// Time (in seconds) an event stays in the feed.
+const EVENT_DISPLAY_TIME: f32 = 6.0;
+/// Max amount of lines in the feed, older events scroll out of it.
+const MAX_LINES: usize = 5;
+
+pub struct EventFeed {
+    text: Handle<UiNode>,
+    string: String,
+}
+
+impl EventFeed {
+    pub fn new(ui: &mut UserInterface, font: SharedFont) -> Self {
+        let text = TextBuilder::new(
+            WidgetBuilder::new()
+                .with_width(400.0)
+                .with_margin(Thickness::uniform(10.0))
+                .with_horizontal_alignment(HorizontalAlignment::Right)
+                .with_vertical_alignment(VerticalAlignment::Top)
+                .with_foreground(Brush::Solid(Color::opaque(220, 220, 220))),
+        )
+        .with_font(font)
+        .with_wrap(WrapMode::Word)
+        .with_horizontal_text_alignment(HorizontalAlignment::Right)
+        .build(&mut ui.build_ctx());
+
+        Self {
+            text,
+            string: Default::default(),
+        }
+    }
+
+    pub fn update(&mut self, ui: &UserInterface, level: Option<&Level>) {
+        let mut string = String::new();
+        if let Some(level) = level {
+            let mut events = level
+                .recent_events()
+                .rev()
+                .take_while(|event| event.age < EVENT_DISPLAY_TIME)
+                .take(MAX_LINES)
+                .collect::<Vec<_>>();
+            events.reverse();
+            for event in events {
+                let _ = writeln!(string, "{}", event.description);
+            }
+        }
+
+        if string != self.string {
+            ui.send_message(WidgetMessage::visibility(
+                self.text,
+                MessageDirection::ToWidget,
+                !string.is_empty(),
+            ));
+            ui.send_message(TextMessage::text(
+                self.text,
+                MessageDirection::ToWidget,
+                string.clone(),
+            ));
+            self.string = string;
+        }
+    }
+}
@@ -23,6 +23,8 @@ use fyrox::{
     },
 };
 
+pub mod damage_indicator;
+pub mod event_feed;
 pub mod inventory;
 pub mod item_display;
 pub mod journal;
@@ -108,25 +108,36 @@ impl WeaponDisplay {
 
     pub fn sync_to_model(&self, player: &Player, graph: &Graph) {
         let ammo = if player.current_weapon().is_some() {
-            let total_ammo = player.inventory().item_count(ItemKind::Ammo);
-            total_ammo
-                / weapon_ref(player.current_weapon(), graph)
-                    .definition
-                    .ammo_consumption_per_shot
+            let weapon = weapon_ref(player.current_weapon(), graph);
+            let definition = weapon.definition;
+            let total_ammo = player.inventory().item_count(definition.ammo_item);
+            let ammo = total_ammo / definition.ammo_consumption_per_shot;
+            if weapon.is_overheated() {
+                format!("{} HOT", ammo)
+            } else if definition.uses_heat() {
+                format!("{} {}%", ammo, (weapon.heat() * 100.0) as u32)
+            } else {
+                format!("{}", ammo)
+            }
         } else {
-            0
+            "0".to_owned()
         };
         self.ui.send_message(TextMessage::text(
             self.ammo,
             MessageDirection::ToWidget,
-            format!("{}", ammo),
+            ammo,
         ));
 
         let grenades = player.inventory().item_count(ItemKind::Grenade);
+        let grenades = if let Some(fuse_left) = player.grenade_fuse_left() {
+            format!("{} {:.1}s", grenades, fuse_left)
+        } else {
+            format!("{}", grenades)
+        };
         self.ui.send_message(TextMessage::text(
             self.grenades,
             MessageDirection::ToWidget,
-            format!("{}", grenades),
+            grenades,
         ));
     }
 
@@ -0,0 +1,113 @@
+//! Ambient lighting of a level. It is defined per map in `data/configs/ambient.ron`, maps without
+//! a definition keep the lighting embedded into their scene. Scripted events (for example an
+//! alarm) could change it at runtime using [`crate::message::Message::SetAmbientLighting`],
+//! power outages started by [`crate::message::Message::SetPower`] dim it temporarily.
+
+use fyrox::{
+    core::{color::Color, visitor::prelude::*},
+    lazy_static::lazy_static,
+    scene::Scene,
+};
+use serde::Deserialize;
+use std::{collections::HashMap, fs::File};
+
+#[derive(Deserialize, Clone, Debug)]
+pub struct AmbientDefinition {
+    pub ambient_color: (u8, u8, u8),
+}
+
+#[derive(Deserialize, Default)]
+pub struct AmbientDefinitionsContainer {
+    map: HashMap<String, AmbientDefinition>,
+}
+
+impl AmbientDefinitionsContainer {
+    pub fn new() -> Self {
+        let file = File::open("data/configs/ambient.ron").unwrap();
+        ron::de::from_reader(file).unwrap()
+    }
+}
+
+/// Fraction of the ambient lighting left when power is cut.
+const POWER_OUTAGE_BRIGHTNESS: f32 = 0.15;
+const POWER_OFF_FADE_TIME: f32 = 0.5;
+const POWER_ON_FADE_TIME: f32 = 2.0;
+
+lazy_static! {
+    static ref DEFINITIONS: AmbientDefinitionsContainer = AmbientDefinitionsContainer::new();
+}
+
+#[derive(Visit, Default, Clone, Debug)]
+pub struct AmbientSettings {
+    /// Target ambient lighting color, `None` leaves the color of the scene untouched.
+    color: Option<Color>,
+    /// Color at the beginning of the current transition.
+    from: Color,
+    /// Duration (in seconds) of the current transition.
+    fade_time: f32,
+    elapsed: f32,
+    /// Ambient color to restore when power is back, `Some` only during power outage.
+    #[visit(optional)]
+    powered_color: Option<Color>,
+}
+
+impl AmbientSettings {
+    pub fn for_map(map_path: &str) -> Self {
+        Self {
+            color: DEFINITIONS.map.get(map_path).map(|definition| {
+                let (r, g, b) = definition.ambient_color;
+                Color::opaque(r, g, b)
+            }),
+            ..Default::default()
+        }
+    }
+
+    pub fn color(&self) -> Option<Color> {
+        self.color
+    }
+
+    /// Starts a smooth transition from the current ambient color of the scene to the given one,
+    /// zero `fade_time` changes the color immediately.
+    pub fn set_color(&mut self, scene: &mut Scene, color: Color, fade_time: f32) {
+        self.from = scene.ambient_lighting_color;
+        self.color = Some(color);
+        self.fade_time = fade_time.max(0.0);
+        self.elapsed = 0.0;
+        self.apply(scene);
+    }
+
+    /// Dims ambient lighting when power is cut and restores it back when power is on.
+    pub fn set_power(&mut self, scene: &mut Scene, powered: bool) {
+        if powered {
+            if let Some(color) = self.powered_color.take() {
+                self.set_color(scene, color, POWER_ON_FADE_TIME);
+            }
+        } else if self.powered_color.is_none() {
+            let color = self.color.unwrap_or(scene.ambient_lighting_color);
+            self.powered_color = Some(color);
+            let dim = |c: u8| (c as f32 * POWER_OUTAGE_BRIGHTNESS) as u8;
+            self.set_color(
+                scene,
+                Color::opaque(dim(color.r), dim(color.g), dim(color.b)),
+                POWER_OFF_FADE_TIME,
+            );
+        }
+    }
+
+    pub fn update(&mut self, scene: &mut Scene, dt: f32) {
+        if self.elapsed < self.fade_time {
+            self.elapsed = (self.elapsed + dt).min(self.fade_time);
+            self.apply(scene);
+        }
+    }
+
+    pub fn apply(&self, scene: &mut Scene) {
+        if let Some(color) = self.color {
+            scene.ambient_lighting_color = if self.elapsed < self.fade_time {
+                self.from.lerp(color, self.elapsed / self.fade_time)
+            } else {
+                color
+            };
+        }
+    }
+}
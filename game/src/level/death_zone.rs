@@ -1,6 +1,7 @@
 use crate::{
     character::{try_get_character_mut, CharacterCommand},
-    current_level_ref,
+    current_level_ref, game_ref,
+    message::Message,
 };
 use fyrox::{
     core::{
@@ -24,19 +25,42 @@ impl TypeUuidProvider for DeathZone {
     }
 }
 
+impl DeathZone {
+    const VOID_OUT_SOUND: &'static str = "data/sounds/void_out.wav";
+}
+
 impl ScriptTrait for DeathZone {
     fn on_update(&mut self, context: &mut ScriptContext) {
+        let level = current_level_ref(context.plugins).unwrap();
+        let sender = &game_ref(context.plugins).message_sender;
         let self_bounds = context.scene.graph[context.handle].world_bounding_box();
-        for &actor in current_level_ref(context.plugins).unwrap().actors.iter() {
+        for &actor in level.actors.iter() {
             let character_position = context.scene.graph[actor].global_position();
             if let Some(character) = try_get_character_mut(actor, &mut context.scene.graph) {
                 if self_bounds.is_contains_point(character_position) {
+                    let was_alive = !character.is_dead();
+
                     character.push_command(CharacterCommand::Damage {
                         who: Default::default(),
                         hitbox: None,
                         amount: 99999.0,
                         critical_shot_probability: 0.0,
                     });
+
+                    if was_alive {
+                        sender.send(Message::ActorVoidedOut {
+                            actor,
+                            position: character_position,
+                        });
+                        level.sound_manager.play_sound(
+                            &mut context.scene.graph,
+                            Self::VOID_OUT_SOUND,
+                            character_position,
+                            1.0,
+                            4.0,
+                            3.0,
+                        );
+                    }
                 }
             }
         }
@@ -16,10 +16,12 @@ use fyrox::{
         decal::DecalBuilder,
         graph::Graph,
         node::{Node, TypeUuidProvider},
+        rigidbody::RigidBody,
         transform::TransformBuilder,
     },
     script::{Script, ScriptContext, ScriptTrait},
 };
+use std::cell::RefCell;
 
 #[derive(Visit, Reflect, Debug, Clone)]
 pub struct Decal {
@@ -71,6 +73,11 @@ impl ScriptTrait for Decal {
 }
 
 impl Decal {
+    /// Makes decal to start fading out immediately.
+    pub fn fade_out(&mut self) {
+        self.lifetime = self.lifetime.min(0.0);
+    }
+
     pub fn add_to_graph(
         graph: &mut Graph,
         position: Vector3<f32>,
@@ -149,4 +156,113 @@ impl Decal {
             resource_manager.request_texture("data/textures/decals/BulletImpact_BaseColor.png"),
         )
     }
+
+    /// Creates a decal at a hit point, the decal is attached to a rigid body of the hit collider
+    /// (if any), so it will move together with the body. Actors are marked with blood.
+    pub fn new_impact(
+        resource_manager: &ResourceManager,
+        graph: &mut Graph,
+        position: Vector3<f32>,
+        normal: Vector3<f32>,
+        collider: Handle<Node>,
+        is_actor: bool,
+    ) -> Handle<Node> {
+        let parent = graph
+            .try_get(collider)
+            .map(|c| c.parent())
+            .filter(|&body| {
+                graph
+                    .try_get(body)
+                    .map_or(false, |b| b.cast::<RigidBody>().is_some())
+            })
+            .unwrap_or_default();
+
+        Self::new_bullet_hole(
+            resource_manager,
+            graph,
+            position,
+            normal,
+            parent,
+            if is_actor {
+                Color::opaque(160, 0, 0)
+            } else {
+                Color::opaque(20, 20, 20)
+            },
+        )
+    }
+}
+
+/// Marks left by a hit of a surface or an actor.
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub struct HitMarks {
+    /// Bullet hole, holes in actors are blood decals.
+    pub impact_decal: bool,
+    /// Blood spray and blood splatter on surfaces behind an actor.
+    pub blood: bool,
+}
+
+impl HitMarks {
+    /// Blood is skipped entirely when gore is disabled, including holes in actors.
+    pub fn of_hit(is_actor: bool, gore: bool) -> Self {
+        Self {
+            impact_decal: !is_actor || gore,
+            blood: is_actor && gore,
+        }
+    }
+}
+
+/// Tracks live decals of a level and fades out the oldest ones when there are too many of them.
+#[derive(Default, Visit, Debug)]
+pub struct DecalContainer {
+    decals: RefCell<Vec<Handle<Node>>>,
+}
+
+impl DecalContainer {
+    const MAX_DECALS: usize = 128;
+
+    pub fn register(&self, decal: Handle<Node>, graph: &mut Graph) {
+        let mut decals = self.decals.borrow_mut();
+
+        // Forget decals that were already removed.
+        decals.retain(|&d| {
+            graph
+                .try_get(d)
+                .map_or(false, |n| n.try_get_script::<Decal>().is_some())
+        });
+
+        decals.push(decal);
+
+        while decals.len() > Self::MAX_DECALS {
+            let oldest = decals.remove(0);
+            if let Some(decal) = graph[oldest].try_get_script_mut::<Decal>() {
+                decal.fade_out();
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_no_blood_marks_without_gore() {
+        let marks = HitMarks::of_hit(true, false);
+        assert!(!marks.impact_decal);
+        assert!(!marks.blood);
+
+        // Surfaces still get bullet holes.
+        let marks = HitMarks::of_hit(false, false);
+        assert!(marks.impact_decal);
+        assert!(!marks.blood);
+    }
+
+    #[test]
+    fn test_blood_marks_with_gore() {
+        let marks = HitMarks::of_hit(true, true);
+        assert!(marks.impact_decal);
+        assert!(marks.blood);
+
+        assert!(!HitMarks::of_hit(false, true).blood);
+    }
 }
@@ -0,0 +1,62 @@
+//! Distress of the player, it rises when the player is low on health or takes a lot of damage in
+//! a short time. It is just a value in [0; 1] range, the HUD uses it to drive a blood vignette and
+//! a heartbeat sound.
+
+use fyrox::core::visitor::prelude::*;
+
+#[derive(Visit, Clone, Debug)]
+pub struct PlayerDistress {
+    /// Health fraction below which the player starts to feel distress.
+    pub health_threshold: f32,
+    /// Amount of damage taken recently that alone causes full distress, zero disables it.
+    pub full_distress_damage: f32,
+    /// Time (in seconds) over which recent damage fades out.
+    pub damage_decay_time: f32,
+    /// Damage taken recently, decays over time.
+    recent_damage: f32,
+    /// Health of the player in [0; 1] range.
+    player_health: f32,
+}
+
+impl Default for PlayerDistress {
+    fn default() -> Self {
+        Self {
+            health_threshold: 0.4,
+            full_distress_damage: 60.0,
+            damage_decay_time: 1.5,
+            recent_damage: 0.0,
+            player_health: 1.0,
+        }
+    }
+}
+
+impl PlayerDistress {
+    pub fn register_damage(&mut self, amount: f32) {
+        self.recent_damage += amount.max(0.0);
+    }
+
+    pub fn update(&mut self, player_health: f32, dt: f32) {
+        self.player_health = player_health.clamp(0.0, 1.0);
+        if self.damage_decay_time > 0.0 {
+            self.recent_damage *= (-dt / self.damage_decay_time).exp();
+        } else {
+            self.recent_damage = 0.0;
+        }
+    }
+
+    /// Normalized distress, zero means that the player is fine and one - that the player is
+    /// about to die.
+    pub fn value(&self) -> f32 {
+        let health = if self.health_threshold > 0.0 {
+            (1.0 - self.player_health / self.health_threshold).max(0.0)
+        } else {
+            0.0
+        };
+        let damage = if self.full_distress_damage > 0.0 {
+            self.recent_damage / self.full_distress_damage
+        } else {
+            0.0
+        };
+        (health + damage).clamp(0.0, 1.0)
+    }
+}
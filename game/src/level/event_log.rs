@@ -0,0 +1,46 @@
+//! Log of notable gameplay events - kills, pickups, completed objectives, breached doors. The log
+//! keeps only a few most recent events, the HUD shows them as a feed.
+
+use std::collections::VecDeque;
+
+#[derive(Debug, Clone)]
+pub struct GameEvent {
+    pub description: String,
+    /// Time (in seconds) passed since the event.
+    pub age: f32,
+}
+
+#[derive(Default, Debug, Clone)]
+pub struct EventLog {
+    events: VecDeque<GameEvent>,
+}
+
+impl EventLog {
+    /// Max amount of stored events, the oldest ones are dropped when it is exceeded.
+    pub const CAPACITY: usize = 32;
+
+    pub fn add(&mut self, description: String) {
+        if self.events.len() >= Self::CAPACITY {
+            self.events.pop_front();
+        }
+        self.events.push_back(GameEvent {
+            description,
+            age: 0.0,
+        });
+    }
+
+    pub fn update(&mut self, dt: f32) {
+        for event in self.events.iter_mut() {
+            event.age += dt;
+        }
+    }
+
+    /// Returns stored events, from the oldest to the newest.
+    pub fn events(&self) -> impl DoubleEndedIterator<Item = &GameEvent> {
+        self.events.iter()
+    }
+
+    pub fn clear(&mut self) {
+        self.events.clear();
+    }
+}
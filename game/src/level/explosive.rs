@@ -0,0 +1,183 @@
+//! Explosive props (barrels, gas tanks, etc.) that detonate when destroyed by splash damage,
+//! which allows chain reactions of explosions.
+
+use crate::{
+    current_level_mut, current_level_ref,
+    effects::{self, EffectKind},
+    game_ref,
+    message::Message,
+};
+use fyrox::{
+    core::{
+        pool::Handle,
+        reflect::prelude::*,
+        uuid::{uuid, Uuid},
+        variable::InheritableVariable,
+        visitor::prelude::*,
+    },
+    impl_component_provider,
+    scene::node::{Node, TypeUuidProvider},
+    script::{ScriptContext, ScriptDeinitContext, ScriptTrait},
+};
+
+#[derive(Visit, Reflect, Debug, Clone)]
+pub struct Explosive {
+    #[reflect(
+        description = "Amount of splash damage the explosive can take before detonation.",
+        min_value = "0.0"
+    )]
+    #[visit(optional)]
+    health: InheritableVariable<f32>,
+
+    #[reflect(
+        description = "Radius (in meters) of the explosion.",
+        min_value = "0.0"
+    )]
+    #[visit(optional)]
+    radius: InheritableVariable<f32>,
+
+    #[reflect(
+        description = "Amount of damage dealt by the explosion.",
+        min_value = "0.0"
+    )]
+    #[visit(optional)]
+    damage: InheritableVariable<f32>,
+
+    #[reflect(
+        description = "Delay (in seconds) between destruction and detonation.",
+        min_value = "0.0"
+    )]
+    #[visit(optional)]
+    fuse: InheritableVariable<f32>,
+
+    #[reflect(hidden)]
+    #[visit(optional)]
+    damage_taken: f32,
+
+    #[reflect(hidden)]
+    #[visit(optional)]
+    ignited: bool,
+
+    #[reflect(hidden)]
+    #[visit(optional)]
+    fuse_timer: f32,
+
+    // Initiator of the explosion that destroyed this explosive, so the kills will be counted.
+    #[reflect(hidden)]
+    #[visit(skip)]
+    who: Handle<Node>,
+}
+
+impl Default for Explosive {
+    fn default() -> Self {
+        Self {
+            health: 30.0.into(),
+            radius: 3.0.into(),
+            damage: 100.0.into(),
+            fuse: 0.25.into(),
+            damage_taken: 0.0,
+            ignited: false,
+            fuse_timer: 0.0,
+            who: Default::default(),
+        }
+    }
+}
+
+impl_component_provider!(Explosive);
+
+impl TypeUuidProvider for Explosive {
+    fn type_uuid() -> Uuid {
+        uuid!("d4b2a0c6-3c4f-4a8e-9a2b-7f0b5e1c8d93")
+    }
+}
+
+impl Explosive {
+    const EXPLOSION_SOUND: &'static str = "data/sounds/explosion.wav";
+
+    /// Damages the explosive, it ignites when damage exceeds its health. Ignited explosives
+    /// ignore any further damage, so an explosive detonates only once.
+    pub fn damage(&mut self, amount: f32, who: Handle<Node>) {
+        if self.ignited {
+            return;
+        }
+
+        self.damage_taken += amount;
+        if self.damage_taken >= *self.health {
+            self.ignited = true;
+            self.fuse_timer = *self.fuse;
+            self.who = who;
+        }
+    }
+
+    pub fn is_ignited(&self) -> bool {
+        self.ignited
+    }
+}
+
+impl ScriptTrait for Explosive {
+    fn on_init(&mut self, ctx: &mut ScriptContext) {
+        current_level_mut(ctx.plugins)
+            .expect("Level must exist!")
+            .explosives
+            .push(ctx.handle);
+    }
+
+    fn on_deinit(&mut self, ctx: &mut ScriptDeinitContext) {
+        if let Some(level) = current_level_mut(ctx.plugins) {
+            if let Some(position) = level.explosives.iter().position(|e| *e == ctx.node_handle) {
+                level.explosives.remove(position);
+            }
+        }
+    }
+
+    fn on_update(&mut self, ctx: &mut ScriptContext) {
+        if !self.ignited {
+            return;
+        }
+
+        self.fuse_timer -= ctx.dt;
+        if self.fuse_timer > 0.0 {
+            return;
+        }
+
+        let position = ctx.scene.graph[ctx.handle].global_position();
+
+        effects::create(
+            EffectKind::Smoke,
+            &mut ctx.scene.graph,
+            ctx.resource_manager,
+            position,
+            Default::default(),
+        );
+
+        if let Some(level) = current_level_ref(ctx.plugins) {
+            level.sound_manager.play_sound(
+                &mut ctx.scene.graph,
+                Self::EXPLOSION_SOUND,
+                position,
+                1.0,
+                4.0,
+                3.0,
+            );
+        }
+
+        // Splash damage is applied through the message queue, so explosives nearby that are
+        // destroyed by this explosion will ignite on the next frame and detonate after their own
+        // fuse. This spreads chain reactions over time and prevents recursion.
+        game_ref(ctx.plugins)
+            .message_sender
+            .send(Message::ApplySplashDamage {
+                amount: *self.damage,
+                radius: *self.radius,
+                center: position,
+                who: self.who,
+                critical_shot_probability: 0.0,
+            });
+
+        ctx.scene.graph.remove_node(ctx.handle);
+    }
+
+    fn id(&self) -> Uuid {
+        Self::type_uuid()
+    }
+}
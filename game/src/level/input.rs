@@ -0,0 +1,94 @@
+//! Routing of input devices between local players. With a single player every device belongs
+//! to them, in co-op each player gets the first keyboard and the first mouse that are pressed
+//! after they joined, so two players could share a machine with a keyboard and a mouse each.
+
+use fyrox::{
+    core::pool::Handle,
+    event::{DeviceEvent, DeviceId, ElementState, Event, WindowEvent},
+    scene::node::Node,
+};
+
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum InputDeviceKind {
+    Keyboard,
+    Mouse,
+}
+
+impl InputDeviceKind {
+    /// Returns the device that produced the given event, its kind and whether the event is a
+    /// press that allows to claim the device.
+    pub fn of_event(event: &Event<()>) -> Option<(DeviceId, Self, bool)> {
+        match event {
+            Event::WindowEvent {
+                event:
+                    WindowEvent::KeyboardInput {
+                        device_id, input, ..
+                    },
+                ..
+            } => Some((
+                *device_id,
+                Self::Keyboard,
+                input.state == ElementState::Pressed,
+            )),
+            Event::DeviceEvent { device_id, event } => match event {
+                DeviceEvent::Button { state, .. } => {
+                    Some((*device_id, Self::Mouse, *state == ElementState::Pressed))
+                }
+                DeviceEvent::MouseWheel { .. } => Some((*device_id, Self::Mouse, true)),
+                DeviceEvent::MouseMotion { .. } => Some((*device_id, Self::Mouse, false)),
+                _ => None,
+            },
+            _ => None,
+        }
+    }
+}
+
+#[derive(Default, Debug, Clone)]
+pub struct InputDevices {
+    owners: Vec<(DeviceId, InputDeviceKind, Handle<Node>)>,
+}
+
+impl InputDevices {
+    /// Returns the player that must handle input from the given device or `Handle::NONE` if
+    /// the input must be ignored. A device that has no owner yet is claimed by the first player
+    /// without a device of the same kind, but only when `claim` is set.
+    pub fn owner(
+        &mut self,
+        device: DeviceId,
+        kind: InputDeviceKind,
+        claim: bool,
+        players: &[Handle<Node>],
+    ) -> Handle<Node> {
+        if let [player] = players {
+            return *player;
+        }
+
+        if let Some((_, _, owner)) = self.owners.iter().find(|(id, _, _)| *id == device) {
+            return *owner;
+        }
+
+        if !claim {
+            return Handle::NONE;
+        }
+
+        let owner = players
+            .iter()
+            .copied()
+            .find(|&player| {
+                !self
+                    .owners
+                    .iter()
+                    .any(|(_, owner_kind, owner)| *owner == player && *owner_kind == kind)
+            })
+            .unwrap_or_default();
+        if owner.is_some() {
+            self.owners.push((device, kind, owner));
+        }
+        owner
+    }
+
+    /// Frees devices of the given player, so other players could claim them.
+    pub fn release(&mut self, player: Handle<Node>) {
+        self.owners.retain(|(_, _, owner)| *owner != player);
+    }
+}
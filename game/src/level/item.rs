@@ -1,4 +1,10 @@
-use crate::{block_on, current_level_mut, weapon::definition::WeaponKind};
+use crate::{
+    block_on,
+    character::try_get_character_ref,
+    current_level_mut,
+    weapon::{balance::WeaponBalance, definition::WeaponKind, try_weapon_ref, Weapon},
+    CollisionGroups,
+};
 use fyrox::{
     core::{
         algebra::{Point3, Vector3},
@@ -7,6 +13,7 @@ use fyrox::{
         pool::Handle,
         reflect::prelude::*,
         uuid::{uuid, Uuid},
+        variable::InheritableVariable,
         visitor::prelude::*,
     },
     engine::resource_manager::ResourceManager,
@@ -14,11 +21,13 @@ use fyrox::{
     lazy_static::lazy_static,
     scene::{
         base::BaseBuilder,
-        collider::ColliderShape,
+        collider::{BitMask, ColliderBuilder, ColliderShape, InteractionGroups},
         graph::physics::RayCastOptions,
         graph::Graph,
         node::{Node, TypeUuidProvider},
+        rigidbody::{RigidBody, RigidBodyBuilder, RigidBodyType},
         sprite::SpriteBuilder,
+        transform::TransformBuilder,
         Scene,
     },
     script::{ScriptContext, ScriptDeinitContext, ScriptTrait},
@@ -58,6 +67,11 @@ pub enum ItemKind {
 
     // Keys
     MasterKey,
+
+    // Kinds are saved by index, so new ones must be added only at the end.
+    ShotgunShells,
+    Shotgun,
+    Armor,
 }
 
 impl Default for ItemKind {
@@ -74,13 +88,20 @@ impl ItemKind {
             ItemKind::M4 => Some(WeaponKind::M4),
             ItemKind::Glock => Some(WeaponKind::Glock),
             ItemKind::RailGun => Some(WeaponKind::RailGun),
+            ItemKind::Shotgun => Some(WeaponKind::Shotgun),
             ItemKind::Medkit
             | ItemKind::Medpack
+            | ItemKind::Armor
             | ItemKind::Ammo
+            | ItemKind::ShotgunShells
             | ItemKind::Grenade
             | ItemKind::MasterKey => None,
         }
     }
+
+    pub fn is_medical(&self) -> bool {
+        matches!(self, ItemKind::Medkit | ItemKind::Medpack)
+    }
 }
 
 #[derive(Visit, Reflect, Debug, Clone)]
@@ -89,6 +110,29 @@ pub struct Item {
     model: Handle<Node>,
     pub stack_size: u32,
 
+    #[reflect(
+        description = "Time (in seconds) after which picked up item will appear again. \
+        Zero means that the item won't respawn.",
+        min_value = "0.0"
+    )]
+    #[visit(optional)]
+    respawn_time: InheritableVariable<f32>,
+
+    #[reflect(hidden)]
+    #[visit(optional)]
+    respawn_timer: f32,
+
+    // Dynamic body, that carries the item, if it was tossed.
+    #[reflect(hidden)]
+    #[visit(optional)]
+    physics_body: Handle<Node>,
+
+    // Time (in seconds) left before a dropped item disappears, zero means that the item stays
+    // forever.
+    #[reflect(hidden)]
+    #[visit(optional)]
+    lifetime: f32,
+
     #[reflect(hidden)]
     spark: Handle<Node>,
 
@@ -108,6 +152,10 @@ impl Default for Item {
             spark: Default::default(),
             spark_size_change_dir: 1.0,
             stack_size: 1,
+            respawn_time: InheritableVariable::new(0.0),
+            respawn_timer: 0.0,
+            physics_body: Default::default(),
+            lifetime: 0.0,
             definition: Self::get_definition(ItemKind::Medkit),
         }
     }
@@ -158,6 +206,19 @@ impl ScriptTrait for Item {
     }
 
     fn on_update(&mut self, ctx: &mut ScriptContext) {
+        if self.respawn_timer > 0.0 {
+            self.respawn_timer -= ctx.dt;
+        }
+
+        if self.lifetime > 0.0 {
+            self.lifetime -= ctx.dt;
+            if self.lifetime <= 0.0 {
+                ctx.scene.graph.remove_node(self.root(ctx.handle));
+                return;
+            }
+        }
+        ctx.scene.graph[ctx.handle].set_visibility(self.is_available());
+
         let spark = ctx.scene.graph[self.spark].as_sprite_mut();
         let new_size = spark.size() + 0.02 * self.spark_size_change_dir * ctx.dt;
         spark.set_size(new_size);
@@ -201,6 +262,9 @@ lazy_static! {
 }
 
 impl Item {
+    /// Time (in seconds) after which dropped items disappear.
+    pub const DROPPED_ITEM_LIFETIME: f32 = 20.0;
+
     pub fn get_definition(kind: ItemKind) -> &'static ItemDefinition {
         DEFINITIONS
             .map
@@ -214,7 +278,7 @@ impl Item {
         kind: ItemKind,
         position: Vector3<f32>,
         adjust_height: bool,
-    ) {
+    ) -> Handle<Node> {
         let position = if adjust_height {
             let mut intersections = Vec::new();
             let ray = Ray::from_two_points(position, position - Vector3::new(0.0, 1000.0, 0.0));
@@ -250,14 +314,97 @@ impl Item {
 
         let item_ref = &mut scene.graph[item];
 
-        assert!(item_ref.has_script::<Item>());
+        // Some kinds share a model with other kinds, so the kind stored in the model is
+        // overridden.
+        item_ref
+            .try_get_script_mut::<Item>()
+            .expect("Root node of an item model must have Item script!")
+            .kind = kind;
 
         item_ref.local_transform_mut().set_position(position);
+
+        item
+    }
+
+    /// Adds an item carried by a small dynamic body, that is thrown with the given velocity.
+    pub fn add_to_scene_with_velocity(
+        scene: &mut Scene,
+        resource_manager: ResourceManager,
+        kind: ItemKind,
+        position: Vector3<f32>,
+        velocity: Vector3<f32>,
+        stack_size: u32,
+    ) -> Handle<Node> {
+        let item = Self::add_to_scene(scene, resource_manager, kind, Vector3::default(), false);
+
+        let collider = ColliderBuilder::new(BaseBuilder::new())
+            .with_shape(ColliderShape::ball(0.1))
+            // Items must not push actors.
+            .with_collision_groups(InteractionGroups::new(
+                BitMask(0xFFFF),
+                BitMask(!(CollisionGroups::ActorCapsule as u32)),
+            ))
+            .build(&mut scene.graph);
+
+        let body = RigidBodyBuilder::new(
+            BaseBuilder::new()
+                .with_local_transform(
+                    TransformBuilder::new()
+                        .with_local_position(position)
+                        .build(),
+                )
+                .with_children(&[collider]),
+        )
+        .with_body_type(RigidBodyType::Dynamic)
+        .with_lin_vel(velocity)
+        .with_can_sleep(true)
+        .build(&mut scene.graph);
+
+        scene.graph.link_nodes(item, body);
+
+        let item_script = item_mut(item, &mut scene.graph);
+        item_script.physics_body = body;
+        item_script.stack_size = stack_size;
+        item_script.lifetime = Self::DROPPED_ITEM_LIFETIME;
+
+        item
+    }
+
+    /// Returns a handle of the topmost node that belongs to the item, removing this node will
+    /// remove the entire item.
+    pub fn root(&self, self_handle: Handle<Node>) -> Handle<Node> {
+        if self.physics_body.is_some() {
+            self.physics_body
+        } else {
+            self_handle
+        }
     }
 
     pub fn get_kind(&self) -> ItemKind {
         self.kind
     }
+
+    /// Returns true if the item was dropped by an actor, such items hold exact amount of stuff
+    /// in their stack.
+    pub fn is_dropped(&self) -> bool {
+        self.physics_body.is_some()
+    }
+
+    /// Returns true if the item can be picked up (it is not waiting for respawn).
+    pub fn is_available(&self) -> bool {
+        self.respawn_timer <= 0.0
+    }
+
+    /// Marks the item as picked up. Returns `true` if the item will respawn after some time
+    /// and must stay in the scene, `false` - the item must be removed.
+    pub fn pick_up(&mut self) -> bool {
+        if *self.respawn_time > 0.0 {
+            self.respawn_timer = *self.respawn_time;
+            true
+        } else {
+            false
+        }
+    }
 }
 
 #[derive(Visit)]
@@ -272,6 +419,11 @@ impl Default for ItemContainer {
 }
 
 impl ItemContainer {
+    /// Speed (in m/s) at which attracted items move towards the player.
+    const MAGNET_SPEED: f32 = 3.0;
+    /// The player is considered wounded below this amount of health.
+    const MAGNET_HEALTH_THRESHOLD: f32 = 50.0;
+
     pub fn new() -> Self {
         Self {
             container: Default::default(),
@@ -285,6 +437,93 @@ impl ItemContainer {
     pub fn iter(&self) -> impl Iterator<Item = &Handle<Node>> {
         self.container.iter()
     }
+
+    /// Pulls ammo for the current weapon of the player towards them when they're running low
+    /// on it, the same is done for medical items when the player is wounded. Only items that
+    /// are closer to the player than to any other actor are attracted.
+    pub fn update_magnet(
+        &self,
+        graph: &mut Graph,
+        player: Handle<Node>,
+        actors: &[Handle<Node>],
+        radius: f32,
+        dt: f32,
+    ) {
+        let (player_position, ammo_item, wounded) =
+            match try_get_character_ref(player, graph).filter(|p| !p.is_dead()) {
+                Some(character) => {
+                    // Ammo is low when the player has less than a single ammo pickup.
+                    let ammo_item = try_weapon_ref(character.current_weapon(), graph)
+                        .map(|weapon| Weapon::definition(weapon.kind()).ammo_item)
+                        .filter(|&ammo_item| {
+                            character.inventory().item_count(ammo_item)
+                                < WeaponBalance::ammo_pickup_amount(ammo_item)
+                        });
+
+                    (
+                        character.position(graph),
+                        ammo_item,
+                        character.get_health() < Self::MAGNET_HEALTH_THRESHOLD,
+                    )
+                }
+                None => return,
+            };
+
+        if ammo_item.is_none() && !wounded {
+            return;
+        }
+
+        for &item_handle in self.container.iter() {
+            let item = item_ref(item_handle, graph);
+            let kind = item.get_kind();
+
+            // Items that respawn must stay where they were placed.
+            if !item.is_available()
+                || *item.respawn_time > 0.0
+                || !(Some(kind) == ammo_item || (wounded && kind.is_medical()))
+            {
+                continue;
+            }
+
+            let root = item.root(item_handle);
+            let item_position = graph[item_handle].global_position();
+            let distance = item_position.metric_distance(&player_position);
+            if distance > radius {
+                continue;
+            }
+
+            let is_player_closest = actors.iter().all(|&actor| {
+                actor == player
+                    || try_get_character_ref(actor, graph).map_or(true, |character| {
+                        character.is_dead()
+                            || character.position(graph).metric_distance(&item_position) >= distance
+                    })
+            });
+            if !is_player_closest {
+                continue;
+            }
+
+            let mut direction = player_position - item_position;
+            direction.y = 0.0;
+            let direction = match direction.try_normalize(f32::EPSILON) {
+                Some(direction) => direction,
+                None => continue,
+            };
+
+            let root_node = &mut graph[root];
+            if let Some(rigid_body) = root_node.cast_mut::<RigidBody>() {
+                // Tossed items are moved by physics, keep their vertical velocity so they can
+                // still fall.
+                let mut velocity = direction.scale(Self::MAGNET_SPEED);
+                velocity.y = rigid_body.lin_vel().y;
+                rigid_body.set_lin_vel(velocity);
+            } else {
+                root_node
+                    .local_transform_mut()
+                    .offset(direction.scale(Self::MAGNET_SPEED * dt));
+            }
+        }
+    }
 }
 
 pub fn item_ref(handle: Handle<Node>, graph: &Graph) -> &Item {
@@ -0,0 +1,132 @@
+//! Top-down overview of a level that can be rendered by a minimap widget without accessing the
+//! scene directly.
+
+use crate::{
+    bot::try_get_bot_ref,
+    character::try_get_character_ref,
+    door::{door_ref, DoorContainer, DoorState},
+    level::item::{Item, ItemContainer, ItemKind},
+};
+use fyrox::{
+    core::{
+        algebra::{Vector2, Vector3},
+        pool::Handle,
+    },
+    scene::{graph::Graph, node::Node},
+};
+
+/// Bots closer than this distance (in meters) to the player are always visible on the minimap.
+const PROXIMITY_DETECTION_RADIUS: f32 = 8.0;
+
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum MinimapActorKind {
+    Player,
+    Friend,
+    Enemy,
+}
+
+#[derive(Clone, Debug)]
+pub struct MinimapActor {
+    pub handle: Handle<Node>,
+    pub kind: MinimapActorKind,
+    pub position: Vector2<f32>,
+}
+
+#[derive(Clone, Debug)]
+pub struct MinimapItem {
+    pub kind: ItemKind,
+    pub position: Vector2<f32>,
+}
+
+#[derive(Clone, Debug)]
+pub struct MinimapDoor {
+    pub state: DoorState,
+    pub position: Vector2<f32>,
+}
+
+/// Positions are projected onto XZ plane and are relative to the player, so the player is always
+/// at the origin.
+#[derive(Default, Clone, Debug)]
+pub struct MinimapData {
+    pub actors: Vec<MinimapActor>,
+    pub items: Vec<MinimapItem>,
+    pub doors: Vec<MinimapDoor>,
+}
+
+fn project(position: Vector3<f32>, origin: Vector3<f32>) -> Vector2<f32> {
+    Vector2::new(position.x - origin.x, position.z - origin.z)
+}
+
+impl MinimapData {
+    pub fn update(
+        &mut self,
+        graph: &Graph,
+        player: Handle<Node>,
+        actors: &[Handle<Node>],
+        items: &ItemContainer,
+        doors: &DoorContainer,
+    ) {
+        self.actors.clear();
+        self.items.clear();
+        self.doors.clear();
+
+        let player_team;
+        let origin = match try_get_character_ref(player, graph) {
+            Some(player) => {
+                player_team = player.team;
+                player.position(graph)
+            }
+            None => return,
+        };
+
+        for &handle in actors {
+            let character = match try_get_character_ref(handle, graph) {
+                Some(character) if !character.is_dead() => character,
+                _ => continue,
+            };
+
+            let position = character.position(graph);
+
+            let kind = if handle == player {
+                MinimapActorKind::Player
+            } else if character.team == player_team {
+                MinimapActorKind::Friend
+            } else {
+                // Show only bots that the player is aware of: either the ones that hunt the
+                // player or the ones that are close enough to be heard.
+                let detected = try_get_bot_ref(handle, graph)
+                    .map_or(true, |bot| bot.is_engaging(player))
+                    || position.metric_distance(&origin) <= PROXIMITY_DETECTION_RADIUS;
+                if !detected {
+                    continue;
+                }
+                MinimapActorKind::Enemy
+            };
+
+            self.actors.push(MinimapActor {
+                handle,
+                kind,
+                position: project(position, origin),
+            });
+        }
+
+        for &handle in items.iter() {
+            if let Some(item) = graph[handle].try_get_script::<Item>() {
+                if item.is_available() {
+                    self.items.push(MinimapItem {
+                        kind: item.get_kind(),
+                        position: project(graph[handle].global_position(), origin),
+                    });
+                }
+            }
+        }
+
+        for &handle in doors.doors.iter() {
+            let door = door_ref(handle, graph);
+            self.doors.push(MinimapDoor {
+                state: door.state(),
+                position: project(door.actual_position(graph), origin),
+            });
+        }
+    }
+}
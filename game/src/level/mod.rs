@@ -1,26 +1,61 @@
 use crate::{
-    bot::Bot,
-    character::{character_ref, try_get_character_mut, CharacterCommand},
+    bot::{is_line_of_sight_blocked, squad::SquadManager, try_get_bot_ref, Bot, BotKind},
+    character::{character_ref, try_get_character_mut, try_get_character_ref, CharacterCommand},
     config::SoundConfig,
     door::DoorContainer,
-    level::item::ItemContainer,
+    effects::{self, EffectKind},
+    level::{
+        ambient::AmbientSettings,
+        decal::DecalContainer,
+        distress::PlayerDistress,
+        event_log::{EventLog, GameEvent},
+        explosive::Explosive,
+        input::{InputDeviceKind, InputDevices},
+        item::ItemContainer,
+        minimap::MinimapData,
+        objective::ObjectiveList,
+        physics::LevelPhysics,
+        pressure::SpawnPressure,
+        stats::LevelStats,
+        trail::ShotTrail,
+    },
     message::Message,
-    sound::SoundManager,
+    player::camera::CameraController,
+    sound::{music::MusicPlayer, Mixer, SoundManager},
     utils::use_hrtf,
-    MessageSender,
+    weapon::{definition::WeaponKind, projectile::ProjectilePool, try_weapon_ref},
+    MessageSender, Player,
 };
 use fyrox::{
-    core::{algebra::Vector3, math::PositionProvider, pool::Handle, visitor::prelude::*},
+    core::{
+        algebra::Vector3,
+        color::Color,
+        math::{PositionProvider, Rect},
+        pool::Handle,
+        visitor::prelude::*,
+    },
     engine::resource_manager::ResourceManager,
+    event::Event,
     plugin::PluginContext,
-    scene::{self, node::Node, Scene},
+    scene::{self, camera::Camera, graph::Graph, node::Node, rigidbody::RigidBody, Scene},
+    script::Script,
 };
 use std::path::Path;
 
+pub mod ambient;
 pub mod death_zone;
 pub mod decal;
+pub mod distress;
+pub mod event_log;
+pub mod explosive;
+pub mod input;
 pub mod item;
+pub mod minimap;
+pub mod objective;
+pub mod physics;
+pub mod pressure;
 pub mod spawn;
+pub mod stats;
 pub mod trail;
 pub mod trigger;
 pub mod turret;
@@ -29,22 +64,148 @@ pub mod turret;
 pub struct Level {
     pub map_path: String,
     pub scene: Handle<Scene>,
+    /// The first of local players, HUD shows state of this one.
     pub player: Handle<Node>,
+    /// All local players, including the first one.
+    #[visit(optional)]
+    pub players: Vec<Handle<Node>>,
     pub actors: Vec<Handle<Node>>,
     pub items: ItemContainer,
     pub doors_container: DoorContainer,
     pub elevators: Vec<Handle<Node>>,
+    #[visit(optional)]
+    pub explosives: Vec<Handle<Node>>,
 
     #[visit(skip)]
     pub sound_manager: SoundManager,
+    #[visit(optional)]
+    pub projectile_pool: ProjectilePool,
+    #[visit(optional)]
+    pub decals: DecalContainer,
+    #[visit(optional)]
+    pub stats: LevelStats,
+    /// Whether actors of the same team can damage each other.
+    #[visit(optional)]
+    pub friendly_fire: bool,
+    #[visit(optional)]
+    pub physics: LevelPhysics,
+    #[visit(optional)]
+    pub objectives: ObjectiveList,
+    /// Max distance (in meters) between the player and an item at which the item can be picked.
+    #[visit(optional)]
+    pub pickup_radius: f32,
+    /// Max distance (in meters) at which ammo and health items are attracted by the player
+    /// who is running low on them.
+    #[visit(optional)]
+    pub magnet_radius: f32,
+    /// Limits amount of alive bots and scales spawn pace to the state of the player.
+    #[visit(optional)]
+    pub pressure: SpawnPressure,
+    /// Distress of the primary player, see [`Level::player_distress`].
+    #[visit(optional)]
+    pub distress: PlayerDistress,
+    #[visit(optional)]
+    pub ambient: AmbientSettings,
+    /// Whether power of the level is cut. Doors do not open and their lights are off, ambient
+    /// lighting is dimmed.
+    #[visit(optional)]
+    pub power_outage: bool,
+    /// Recent gameplay events shown in the HUD feed.
+    #[visit(skip)]
+    events: EventLog,
     #[visit(skip)]
     sender: Option<MessageSender>,
+    #[visit(skip)]
+    minimap: MinimapData,
+    /// Points bots hide at when under fire, see [`find_cover_points`].
+    #[visit(skip)]
+    pub cover_points: Vec<Vector3<f32>>,
+    /// Cover points are searched on the first update, once the physics world is built.
+    #[visit(skip)]
+    cover_points_resolved: bool,
+    /// Groups of nearby bots, rebuilt every frame.
+    #[visit(skip)]
+    pub squads: SquadManager,
+    /// Input devices claimed by local players, they're claimed again after loading.
+    #[visit(skip)]
+    input_devices: InputDevices,
+    /// Actors that fell into a death zone and haven't received its damage yet, their deaths
+    /// are already in the feed.
+    #[visit(skip)]
+    voided_out: Vec<Handle<Node>>,
+}
+
+/// Height (in meters) above the floor at which an obstacle must be to hide a bot.
+const COVER_PROBE_HEIGHT: f32 = 0.5;
+/// Max distance (in meters) from a cover point to an obstacle.
+const COVER_PROBE_DISTANCE: f32 = 0.8;
+/// Min distance (in meters) between derived cover points.
+const COVER_POINT_SPACING: f32 = 1.5;
+
+/// Returns positions of nodes tagged as "Cover". If a level has no such nodes, cover points are
+/// derived from geometry - navmesh vertices that have an obstacle next to them at waist height.
+/// Whether a point hides a bot from a particular threat is checked by the bot itself.
+fn find_cover_points(scene: &Scene) -> Vec<Vector3<f32>> {
+    let tagged = scene
+        .graph
+        .linear_iter()
+        .filter(|node| node.tag() == "Cover")
+        .map(|node| node.global_position())
+        .collect::<Vec<_>>();
+    if !tagged.is_empty() {
+        return tagged;
+    }
+
+    let mut points = Vec::<Vector3<f32>>::new();
+    for navmesh in scene.navmeshes.iter() {
+        for vertex in navmesh.vertices() {
+            let position = vertex.position();
+            if points
+                .iter()
+                .any(|point| point.metric_distance(&position) < COVER_POINT_SPACING)
+            {
+                continue;
+            }
+
+            let origin = position + Vector3::new(0.0, COVER_PROBE_HEIGHT, 0.0);
+            let has_obstacle_nearby = (0..8).any(|i| {
+                let angle = i as f32 * std::f32::consts::FRAC_PI_4;
+                let direction = Vector3::new(angle.cos(), 0.0, angle.sin());
+                is_line_of_sight_blocked(
+                    &scene.graph,
+                    origin,
+                    origin + direction.scale(COVER_PROBE_DISTANCE),
+                )
+            });
+            if has_obstacle_nearby {
+                points.push(position);
+            }
+        }
+    }
+    points
+}
+
+fn parse_vector(args: &[&str]) -> Result<Vector3<f32>, String> {
+    if args.len() != 3 {
+        return Err("Expected three coordinates".to_owned());
+    }
+    let mut coords = [0.0; 3];
+    for (coord, arg) in coords.iter_mut().zip(args) {
+        *coord = arg
+            .parse::<f32>()
+            .map_err(|_| format!("Invalid coordinate {}", arg))?;
+    }
+    Ok(Vector3::from(coords))
 }
 
 impl Level {
     pub const ARRIVAL_PATH: &'static str = "data/levels/loading_bay.rgs";
     pub const TESTBED_PATH: &'static str = "data/levels/testbed.rgs";
     pub const LAB_PATH: &'static str = "data/levels/lab.rgs";
+    pub const DEFAULT_PICKUP_RADIUS: f32 = 0.75;
+    pub const DEFAULT_MAGNET_RADIUS: f32 = 4.0;
+    /// Distance (in meters) in front of the player at which console spawns bots.
+    const CONSOLE_SPAWN_DISTANCE: f32 = 3.0;
 
     pub fn from_existing_scene(
         scene: &mut Scene,
@@ -68,14 +229,39 @@ impl Level {
 
         Self {
             player: Default::default(),
+            players: Default::default(),
             actors: Default::default(),
             items: Default::default(),
             scene: scene_handle,
             sender: Some(sender),
-            sound_manager: SoundManager::new(scene, resource_manager),
+            sound_manager: SoundManager::new(
+                scene,
+                resource_manager,
+                Mixer::from_config(&sound_config),
+            ),
             doors_container: Default::default(),
             map_path: Default::default(),
             elevators: Default::default(),
+            explosives: Default::default(),
+            projectile_pool: Default::default(),
+            decals: Default::default(),
+            stats: Default::default(),
+            friendly_fire: false,
+            physics: Default::default(),
+            objectives: Default::default(),
+            pickup_radius: Self::DEFAULT_PICKUP_RADIUS,
+            magnet_radius: Self::DEFAULT_MAGNET_RADIUS,
+            pressure: Default::default(),
+            distress: Default::default(),
+            ambient: Default::default(),
+            power_outage: false,
+            events: Default::default(),
+            minimap: Default::default(),
+            cover_points: Default::default(),
+            cover_points_resolved: false,
+            input_devices: Default::default(),
+            voided_out: Default::default(),
+            squads: Default::default(),
         }
     }
 
@@ -108,16 +294,46 @@ impl Level {
             .graph
             .update(Default::default(), 0.0, Default::default());
 
+        let physics = LevelPhysics::for_map(&map);
+        let objectives = ObjectiveList::for_map(&map);
+        let ambient = AmbientSettings::for_map(&map);
+        ambient.apply(&mut scene);
+
         let level = Self {
             player: Default::default(),
+            players: Default::default(),
             actors: Default::default(),
             items: Default::default(),
             scene: Handle::NONE, // Filled when scene will be moved to engine.
             sender: Some(sender),
-            sound_manager: SoundManager::new(&mut scene, resource_manager),
+            sound_manager: SoundManager::new(
+                &mut scene,
+                resource_manager,
+                Mixer::from_config(&sound_config),
+            ),
             doors_container: Default::default(),
             map_path: map,
             elevators: Default::default(),
+            explosives: Default::default(),
+            projectile_pool: Default::default(),
+            decals: Default::default(),
+            stats: Default::default(),
+            friendly_fire: false,
+            physics,
+            objectives,
+            pickup_radius: Self::DEFAULT_PICKUP_RADIUS,
+            magnet_radius: Self::DEFAULT_MAGNET_RADIUS,
+            pressure: Default::default(),
+            distress: Default::default(),
+            ambient,
+            power_outage: false,
+            events: Default::default(),
+            minimap: Default::default(),
+            cover_points: Default::default(),
+            cover_points_resolved: false,
+            input_devices: Default::default(),
+            voided_out: Default::default(),
+            squads: Default::default(),
         };
 
         (level, scene)
@@ -131,6 +347,84 @@ impl Level {
         self.player
     }
 
+    pub fn is_player(&self, actor: Handle<Node>) -> bool {
+        self.players.contains(&actor)
+    }
+
+    pub fn add_player(&mut self, player: Handle<Node>) {
+        if !self.players.contains(&player) {
+            self.players.push(player);
+        }
+        if self.player.is_none() {
+            self.player = player;
+        }
+    }
+
+    pub fn remove_player(&mut self, player: Handle<Node>) {
+        self.players.retain(|&p| p != player);
+        if self.player == player {
+            self.player = self.players.first().copied().unwrap_or_default();
+        }
+        self.input_devices.release(player);
+    }
+
+    /// Index of the given player among local players, zero for the first one.
+    pub fn player_index(&self, player: Handle<Node>) -> usize {
+        self.players
+            .iter()
+            .position(|&p| p == player)
+            .unwrap_or_default()
+    }
+
+    /// Checks whether the given OS event comes from an input device of the given player, see
+    /// [`InputDevices`].
+    pub fn is_input_of(&mut self, player: Handle<Node>, event: &Event<()>) -> bool {
+        match InputDeviceKind::of_event(event) {
+            Some((device, kind, claim)) => {
+                self.input_devices.owner(device, kind, claim, &self.players) == player
+            }
+            None => true,
+        }
+    }
+
+    /// Splits the screen between local players, each player sees the level through their own
+    /// camera in a horizontal strip of the screen.
+    fn update_split_screen(&self, graph: &mut Graph) {
+        let count = self.players.len().max(1) as f32;
+        for (i, &player) in self.players.iter().enumerate() {
+            let camera = graph
+                .try_get(player)
+                .and_then(|p| p.try_get_script::<Player>())
+                .and_then(|p| graph.try_get(p.camera_controller()))
+                .and_then(|c| c.try_get_script::<CameraController>())
+                .map(|c| c.camera())
+                .unwrap_or_default();
+            if let Some(camera) = graph
+                .try_get_mut(camera)
+                .and_then(|c| c.cast_mut::<Camera>())
+            {
+                camera.set_viewport(Rect::new(0.0, i as f32 / count, 1.0, 1.0 / count));
+            }
+        }
+    }
+
+    pub fn alive_bot_count(&self, graph: &Graph) -> usize {
+        self.actors
+            .iter()
+            .filter(|&&actor| try_get_bot_ref(actor, graph).map_or(false, |bot| !bot.is_dead()))
+            .count()
+    }
+
+    /// Checks whether one more bot can be spawned without exceeding the alive bot cap.
+    pub fn can_spawn_bot(&self, graph: &Graph) -> bool {
+        self.pressure.can_spawn(self.alive_bot_count(graph))
+    }
+
+    /// Top-down overview of the level around the player, refreshed every frame.
+    pub fn minimap_data(&self) -> &MinimapData {
+        &self.minimap
+    }
+
     fn apply_splash_damage(
         &mut self,
         engine: &mut PluginContext,
@@ -146,7 +440,8 @@ impl Level {
             let character = character_ref(actor_handle, &scene.graph);
             // TODO: Add occlusion test. This will hit actors through walls.
             let position = character.position(&scene.graph);
-            if position.metric_distance(&center) <= radius {
+            let distance = position.metric_distance(&center);
+            if distance <= radius {
                 if let Some(character) = try_get_character_mut(actor_handle, &mut scene.graph) {
                     character.push_command(CharacterCommand::Damage {
                         who,
@@ -155,35 +450,493 @@ impl Level {
                         amount,
                         critical_shot_probability,
                     });
+                    // Explosions push actors away from the center, the closer the stronger.
+                    character.push_command(CharacterCommand::Knockback {
+                        who,
+                        direction: position - center,
+                        amount: amount * (2.0 - distance / radius),
+                    });
+                }
+            }
+        }
+
+        // Explosives caught in the blast ignite and detonate after their fuse.
+        for &explosive_handle in self.explosives.iter() {
+            let distance = scene.graph[explosive_handle]
+                .global_position()
+                .metric_distance(&center);
+            if distance <= radius {
+                if let Some(explosive) = scene
+                    .graph
+                    .try_get_mut(explosive_handle)
+                    .and_then(|e| e.try_get_script_mut::<Explosive>())
+                {
+                    explosive.damage(amount, who);
                 }
             }
         }
+
+        // Nearby explosions shake player's camera, the closer the stronger.
+        let shake_radius = radius * 2.0;
+        let shake = scene
+            .graph
+            .try_get(self.player)
+            .and_then(|p| p.try_get_script::<Player>())
+            .and_then(|player| {
+                let distance = player.position(&scene.graph).metric_distance(&center);
+                (distance <= shake_radius).then(|| {
+                    (
+                        player.camera_controller(),
+                        3.0 * (1.0 - distance / shake_radius),
+                    )
+                })
+            });
+        if let Some((camera_controller, amplitude)) = shake {
+            if let Some(camera_controller) = scene
+                .graph
+                .try_get_mut(camera_controller)
+                .and_then(|c| c.try_get_script_mut::<CameraController>())
+            {
+                camera_controller.shake(amplitude, 0.6);
+            }
+        }
     }
 
-    pub async fn handle_message(&mut self, engine: &mut PluginContext<'_, '_>, message: &Message) {
-        if let &Message::ApplySplashDamage {
-            amount,
-            radius,
-            center,
-            who,
-            critical_shot_probability,
-        } = message
+    fn spawn_player(&mut self, engine: &mut PluginContext, player: Handle<Node>) {
+        let scene = &mut engine.scenes[self.scene];
+
+        // Script is moved out of the graph for a while, because respawn needs the whole scene.
+        let mut player_script = match scene
+            .graph
+            .try_get_mut(player)
+            .and_then(|n| n.try_get_script_mut::<Player>())
         {
-            self.apply_splash_damage(
+            Some(player_script) => std::mem::take(player_script),
+            None => return,
+        };
+
+        player_script.respawn(scene);
+
+        if let Some(script) = scene.graph[player].try_get_script_mut::<Player>() {
+            *script = player_script;
+        }
+    }
+
+    fn teleport_actor(
+        &mut self,
+        engine: &mut PluginContext,
+        actor: Handle<Node>,
+        position: Vector3<f32>,
+        with_effect: bool,
+    ) {
+        // Actor could be already destroyed.
+        if !self.actors.contains(&actor) {
+            return;
+        }
+
+        let scene = &mut engine.scenes[self.scene];
+
+        if let Some(character) = try_get_character_ref(actor, &scene.graph) {
+            let body = character.body;
+
+            if let Some(rigid_body) = scene.graph[body].cast_mut::<RigidBody>() {
+                rigid_body.set_lin_vel(Default::default());
+                rigid_body.set_ang_vel(Default::default());
+                rigid_body.local_transform_mut().set_position(position);
+            }
+
+            if with_effect {
+                let effect = effects::create(
+                    EffectKind::Smoke,
+                    &mut scene.graph,
+                    engine.resource_manager,
+                    position,
+                    Default::default(),
+                );
+                scene.graph[effect].set_script(Some(Script::new(ShotTrail::new(5.0))));
+            }
+        }
+    }
+
+    fn register_damage(
+        &mut self,
+        engine: &mut PluginContext,
+        actor: Handle<Node>,
+        who: Handle<Node>,
+        amount: f32,
+        killed: bool,
+    ) {
+        let graph = &engine.scenes[self.scene].graph;
+        // Damage could be dealt by a weapon, count it for its owner.
+        let attacker = try_weapon_ref(who, graph).map_or(who, |w| w.owner());
+        if attacker.is_some() {
+            self.stats.register_damage(attacker, amount, killed);
+        }
+        if actor == self.player {
+            self.distress.register_damage(amount);
+        }
+        if killed && self.is_player(attacker) {
+            self.pressure.register_kill();
+        }
+        // Death zones deal damage on behalf of nobody, their kills are already in the feed.
+        let voided_out = attacker.is_none()
+            && self
+                .voided_out
+                .iter()
+                .position(|&a| a == actor)
+                .map(|i| self.voided_out.remove(i))
+                .is_some();
+        if killed && !voided_out {
+            let victim = self.actor_name(actor, graph);
+            let description = if attacker.is_some() && attacker != actor {
+                format!("{} killed {}", self.actor_name(attacker, graph), victim)
+            } else {
+                format!("{} died", victim)
+            };
+            self.events.add(description);
+        }
+    }
+
+    fn actor_name(&self, actor: Handle<Node>, graph: &Graph) -> String {
+        if self.is_player(actor) {
+            "Player".to_owned()
+        } else if let Some(bot) = try_get_bot_ref(actor, graph) {
+            bot.kind.description().to_owned()
+        } else if graph.is_valid_handle(actor) {
+            graph[actor].name_owned()
+        } else {
+            "Unknown".to_owned()
+        }
+    }
+
+    /// Normalized (in [0; 1] range) distress of the primary player, it depends on health and
+    /// recent damage and goes back to zero as health recovers. The HUD uses it to show a blood
+    /// vignette and to play a heartbeat sound.
+    pub fn player_distress(&self) -> f32 {
+        self.distress.value()
+    }
+
+    /// Recent gameplay events (kills, pickups, completed objectives, breached doors), from the
+    /// oldest to the newest. Only a limited amount of events is kept.
+    pub fn recent_events(&self) -> impl DoubleEndedIterator<Item = &GameEvent> {
+        self.events.events()
+    }
+
+    pub async fn handle_message(&mut self, engine: &mut PluginContext<'_, '_>, message: &Message) {
+        match *message {
+            Message::ApplySplashDamage {
+                amount,
+                radius,
+                center,
+                who,
+                critical_shot_probability,
+            } => self.apply_splash_damage(
                 engine,
                 amount,
                 radius,
                 center,
                 who,
                 critical_shot_probability,
-            )
+            ),
+            Message::TeleportActor {
+                actor,
+                position,
+                with_effect,
+            } => self.teleport_actor(engine, actor, position, with_effect),
+            Message::SpawnPlayer { player } => self.spawn_player(engine, player),
+            Message::DamageActor { actor, who, amount } => {
+                let graph = &mut engine.scenes[self.scene].graph;
+                if let Some(character) = try_get_character_mut(actor, graph) {
+                    character.push_command(CharacterCommand::Damage {
+                        who,
+                        hitbox: None,
+                        amount,
+                        critical_shot_probability: 0.0,
+                    });
+                }
+            }
+            Message::ActorDamaged {
+                actor,
+                who,
+                amount,
+                killed,
+            } => self.register_damage(engine, actor, who, amount, killed),
+            Message::ActorVoidedOut { actor, .. } => {
+                self.stats.register_void_out(actor);
+                let graph = &engine.scenes[self.scene].graph;
+                let victim = self.actor_name(actor, graph);
+                self.events.add(format!("{} fell into the void", victim));
+                self.voided_out.push(actor);
+            }
+            Message::ItemPickedUp { actor, kind } => {
+                if self.is_player(actor) {
+                    self.events.add(format!("Picked up {}", kind.as_ref()));
+                }
+            }
+            Message::ObjectiveCompleted { ref description } => {
+                self.events
+                    .add(format!("Objective completed: {}", description));
+            }
+            Message::DoorBreached { .. } => self.events.add("Door breached".to_owned()),
+            Message::SetPower(powered) => {
+                self.power_outage = !powered;
+                self.ambient
+                    .set_power(&mut engine.scenes[self.scene], powered);
+            }
+            Message::SetAmbientLighting { color, fade_time } => {
+                self.ambient
+                    .set_color(&mut engine.scenes[self.scene], color, fade_time)
+            }
+            _ => (),
         }
     }
 
-    pub fn resolve(&mut self, ctx: &mut PluginContext, sender: MessageSender) {
+    /// Executes a text command of the debug console and returns a message describing the
+    /// result. Supported commands:
+    ///
+    /// - `spawn_bot <Mutant|Parasite|Zombie|ShieldedMutant> [x y z]` - spawns a bot at the given
+    /// position or in front of the player.
+    /// - `give_weapon <kind>` - gives a weapon (`M4`, `Ak47`, `PlasmaRifle`, etc.) to the player.
+    /// - `teleport <x> <y> <z>` - moves the player to the given position.
+    /// - `set_health <amount>` - sets health of the player.
+    /// - `god` - toggles god mode of the player.
+    /// - `noclip` - toggles no-clip mode of the player.
+    /// - `ambient <r> <g> <b> [fade_time]` - changes ambient lighting of the level.
+    pub fn execute_command(&mut self, ctx: &mut PluginContext, command: &str) -> String {
+        match self.try_execute_command(ctx, command) {
+            Ok(result) => result,
+            Err(error) => error,
+        }
+    }
+
+    fn try_execute_command(
+        &mut self,
+        ctx: &mut PluginContext,
+        command: &str,
+    ) -> Result<String, String> {
+        let mut args = command.split_whitespace();
+        let name = match args.next() {
+            Some(name) => name,
+            None => return Err("Empty command".to_owned()),
+        };
+        let args = args.collect::<Vec<_>>();
+
+        let scene = &mut ctx.scenes[self.scene];
+
+        match name {
+            "spawn_bot" => {
+                let kind = args
+                    .first()
+                    .ok_or_else(|| "Usage: spawn_bot <kind> [x y z]".to_owned())?;
+                let kind = kind
+                    .parse::<BotKind>()
+                    .map_err(|_| format!("Unknown bot kind {}", kind))?;
+                if !self.can_spawn_bot(&scene.graph) {
+                    return Err(format!(
+                        "Unable to spawn a bot, there are already {} bots alive",
+                        self.pressure.max_alive_bots
+                    ));
+                }
+                let position = if args.len() > 1 {
+                    parse_vector(&args[1..])?
+                } else {
+                    let player = scene
+                        .graph
+                        .try_get(self.player)
+                        .and_then(|p| p.try_get_script::<Player>())
+                        .ok_or_else(|| "There is no player".to_owned())?;
+                    let camera = scene
+                        .graph
+                        .try_get(player.camera_controller())
+                        .and_then(|c| c.try_get_script::<CameraController>())
+                        .map(|c| c.camera())
+                        .unwrap_or_default();
+                    let mut forward = scene
+                        .graph
+                        .try_get(camera)
+                        .map_or_else(Vector3::z, |c| c.look_vector());
+                    forward.y = 0.0;
+                    player.position(&scene.graph)
+                        + forward
+                            .try_normalize(f32::EPSILON)
+                            .unwrap_or_else(Vector3::z)
+                            .scale(Self::CONSOLE_SPAWN_DISTANCE)
+                };
+                Bot::add_to_scene(
+                    scene,
+                    kind,
+                    ctx.resource_manager,
+                    position,
+                    Default::default(),
+                );
+                Ok(format!("Spawned {:?} at {:?}", kind, position))
+            }
+            "give_weapon" => {
+                let kind = args
+                    .first()
+                    .ok_or_else(|| "Usage: give_weapon <kind>".to_owned())?;
+                let kind = kind
+                    .parse::<WeaponKind>()
+                    .map_err(|_| format!("Unknown weapon kind {}", kind))?;
+                let player = try_get_character_mut(self.player, &mut scene.graph)
+                    .ok_or_else(|| "There is no player".to_owned())?;
+                player.push_command(CharacterCommand::AddWeapon(kind));
+                if let Some(sender) = self.sender.as_ref() {
+                    sender.send(Message::SyncInventory);
+                }
+                Ok(format!("Added {:?}", kind))
+            }
+            "teleport" => {
+                let position = parse_vector(&args)?;
+                let sender = self
+                    .sender
+                    .as_ref()
+                    .ok_or_else(|| "Level has no message sender".to_owned())?;
+                sender.send(Message::TeleportActor {
+                    actor: self.player,
+                    position,
+                    with_effect: true,
+                });
+                Ok(format!("Teleported to {:?}", position))
+            }
+            "set_health" => {
+                let amount = args
+                    .first()
+                    .ok_or_else(|| "Usage: set_health <amount>".to_owned())?;
+                let amount = amount
+                    .parse::<f32>()
+                    .map_err(|_| format!("Invalid amount {}", amount))?;
+                let player = try_get_character_mut(self.player, &mut scene.graph)
+                    .ok_or_else(|| "There is no player".to_owned())?;
+                player.health = amount;
+                Ok(format!("Health set to {}", amount))
+            }
+            "god" => {
+                let player = try_get_character_mut(self.player, &mut scene.graph)
+                    .ok_or_else(|| "There is no player".to_owned())?;
+                let invulnerable = !player.is_invulnerable();
+                player.set_invulnerable(invulnerable);
+                Ok(format!(
+                    "God mode {}",
+                    if invulnerable { "enabled" } else { "disabled" }
+                ))
+            }
+            "noclip" => {
+                let player = scene
+                    .graph
+                    .try_get_mut(self.player)
+                    .and_then(|p| p.try_get_script_mut::<Player>())
+                    .ok_or_else(|| "There is no player".to_owned())?;
+                let no_clip = !player.is_no_clip();
+                player.set_no_clip(no_clip);
+                Ok(format!(
+                    "No-clip {}",
+                    if no_clip { "enabled" } else { "disabled" }
+                ))
+            }
+            "ambient" => {
+                if args.len() < 3 {
+                    return Err("Usage: ambient <r> <g> <b> [fade_time]".to_owned());
+                }
+                let mut rgb = [0u8; 3];
+                for (component, arg) in rgb.iter_mut().zip(args.iter()) {
+                    *component = arg
+                        .parse::<u8>()
+                        .map_err(|_| format!("Invalid color component {}", arg))?;
+                }
+                let fade_time = match args.get(3) {
+                    Some(arg) => arg
+                        .parse::<f32>()
+                        .map_err(|_| format!("Invalid fade time {}", arg))?,
+                    None => 0.0,
+                };
+                let color = Color::opaque(rgb[0], rgb[1], rgb[2]);
+                self.ambient.set_color(scene, color, fade_time);
+                Ok(format!("Ambient lighting set to {:?}", color))
+            }
+            _ => Err(format!("Unknown command {}", name)),
+        }
+    }
+
+    pub fn update(&mut self, ctx: &mut PluginContext, item_magnet: bool) {
+        let scene = &mut ctx.scenes[self.scene];
+
+        let in_combat = self.actors.iter().any(|&actor| {
+            try_get_bot_ref(actor, &scene.graph).map_or(false, |bot| {
+                self.players.iter().any(|&player| bot.is_engaging(player))
+            })
+        });
+
+        if let Some(player) = try_get_character_ref(self.player, &scene.graph) {
+            if !player.is_dead() {
+                self.stats.add_survival_time(ctx.dt);
+            }
+            self.pressure.update(player.get_health() / 100.0, ctx.dt);
+            self.distress.update(player.get_health() / 100.0, ctx.dt);
+        }
+
+        self.sound_manager
+            .update(&mut scene.graph, in_combat, ctx.dt);
+
+        self.ambient.update(scene, ctx.dt);
+
+        self.events.update(ctx.dt);
+
+        self.squads.update(scene, &self.actors);
+
+        if !self.cover_points_resolved {
+            self.cover_points = find_cover_points(scene);
+            self.cover_points_resolved = true;
+        }
+
+        self.doors_container.update_navmesh_blocking(scene);
+
+        self.update_split_screen(&mut scene.graph);
+
+        if item_magnet {
+            self.items.update_magnet(
+                &mut scene.graph,
+                self.player,
+                &self.actors,
+                self.magnet_radius,
+                ctx.dt,
+            );
+        }
+
+        self.objectives
+            .update(&scene.graph, self.player, self.sender.as_ref(), ctx.dt);
+
+        self.minimap.update(
+            &scene.graph,
+            self.player,
+            &self.actors,
+            &self.items,
+            &self.doors_container,
+        );
+    }
+
+    pub fn resolve(
+        &mut self,
+        ctx: &mut PluginContext,
+        sender: MessageSender,
+        sound_config: &SoundConfig,
+    ) {
         self.set_message_sender(sender);
-        self.sound_manager =
-            SoundManager::new(&mut ctx.scenes[self.scene], ctx.resource_manager.clone());
+        // Saves made before the pickup radius was added do not have it.
+        if self.pickup_radius <= 0.0 {
+            self.pickup_radius = Self::DEFAULT_PICKUP_RADIUS;
+        }
+        if self.magnet_radius <= 0.0 {
+            self.magnet_radius = Self::DEFAULT_MAGNET_RADIUS;
+        }
+        self.ambient.apply(&mut ctx.scenes[self.scene]);
+        MusicPlayer::remove_saved_sources(&mut ctx.scenes[self.scene].graph);
+        self.doors_container.resolve(&mut ctx.scenes[self.scene]);
+        self.sound_manager = SoundManager::new(
+            &mut ctx.scenes[self.scene],
+            ctx.resource_manager.clone(),
+            Mixer::from_config(sound_config),
+        );
     }
 
     pub fn set_message_sender(&mut self, sender: MessageSender) {
@@ -216,5 +969,7 @@ impl Level {
                 }
             }
         }
+
+        self.squads.debug_draw(drawing_context);
     }
 }
@@ -0,0 +1,210 @@
+//! Objectives of a level, they're defined per map in `data/configs/objectives.ron` and their
+//! progress is stored in saves.
+
+use crate::{character::try_get_character_ref, message::Message, MessageSender};
+use fyrox::{
+    core::{algebra::Vector3, pool::Handle, visitor::prelude::*},
+    lazy_static::lazy_static,
+    scene::{graph::Graph, node::Node},
+};
+use serde::Deserialize;
+use std::{collections::HashMap, fs::File};
+
+#[derive(Deserialize, Clone, Debug)]
+pub enum ObjectiveDefinitionKind {
+    ReachLocation {
+        position: (f32, f32, f32),
+        radius: f32,
+    },
+    /// Kill an actor with the given node name.
+    KillTarget {
+        name: String,
+    },
+    SurviveTime {
+        duration: f32,
+    },
+}
+
+#[derive(Deserialize, Clone, Debug)]
+pub struct ObjectiveDefinition {
+    pub description: String,
+    pub kind: ObjectiveDefinitionKind,
+}
+
+#[derive(Deserialize, Default)]
+pub struct ObjectiveDefinitionsContainer {
+    map: HashMap<String, Vec<ObjectiveDefinition>>,
+}
+
+impl ObjectiveDefinitionsContainer {
+    pub fn new() -> Self {
+        let file = File::open("data/configs/objectives.ron").unwrap();
+        ron::de::from_reader(file).unwrap()
+    }
+}
+
+lazy_static! {
+    static ref DEFINITIONS: ObjectiveDefinitionsContainer = ObjectiveDefinitionsContainer::new();
+}
+
+#[derive(Visit, Clone, Debug)]
+pub enum ObjectiveKind {
+    ReachLocation { position: Vector3<f32>, radius: f32 },
+    KillTarget { name: String },
+    SurviveTime { duration: f32 },
+}
+
+impl Default for ObjectiveKind {
+    fn default() -> Self {
+        Self::SurviveTime { duration: 0.0 }
+    }
+}
+
+#[derive(Visit, Default, Clone, Debug)]
+pub struct Objective {
+    pub description: String,
+    pub kind: ObjectiveKind,
+    completed: bool,
+    /// Time (in seconds) the player has survived, used only by survival objectives.
+    elapsed: f32,
+    // Actor to kill, found by name on first update.
+    #[visit(skip)]
+    target: Handle<Node>,
+    // Whether the actor to kill was found alive, only such actor counts as killed when it
+    // disappears from the scene.
+    #[visit(skip)]
+    target_seen_alive: bool,
+}
+
+impl Objective {
+    fn from_definition(definition: &ObjectiveDefinition) -> Self {
+        let kind = match definition.kind {
+            ObjectiveDefinitionKind::ReachLocation {
+                position: (x, y, z),
+                radius,
+            } => ObjectiveKind::ReachLocation {
+                position: Vector3::new(x, y, z),
+                radius,
+            },
+            ObjectiveDefinitionKind::KillTarget { ref name } => {
+                ObjectiveKind::KillTarget { name: name.clone() }
+            }
+            ObjectiveDefinitionKind::SurviveTime { duration } => {
+                ObjectiveKind::SurviveTime { duration }
+            }
+        };
+
+        Self {
+            description: definition.description.clone(),
+            kind,
+            ..Default::default()
+        }
+    }
+
+    pub fn is_completed(&self) -> bool {
+        self.completed
+    }
+
+    /// Position of a waypoint marker in the world, `None` if the objective is not bound to any
+    /// place.
+    pub fn waypoint(&self, graph: &Graph) -> Option<Vector3<f32>> {
+        match self.kind {
+            ObjectiveKind::ReachLocation { position, .. } => Some(position),
+            ObjectiveKind::KillTarget { .. } => graph
+                .try_get(self.target)
+                .map(|target| target.global_position()),
+            ObjectiveKind::SurviveTime { .. } => None,
+        }
+    }
+
+    fn check_completion(&mut self, graph: &Graph, player: Handle<Node>, dt: f32) -> bool {
+        let player = try_get_character_ref(player, graph).filter(|p| !p.is_dead());
+
+        match self.kind {
+            ObjectiveKind::ReachLocation { position, radius } => player.map_or(false, |p| {
+                p.position(graph).metric_distance(&position) <= radius
+            }),
+            ObjectiveKind::KillTarget { ref name } => {
+                if self.target.is_none() {
+                    // Other nodes could have the same name, only characters can be killed.
+                    self.target = graph
+                        .pair_iter()
+                        .find(|(handle, node)| {
+                            node.name() == name && try_get_character_ref(*handle, graph).is_some()
+                        })
+                        .map(|(handle, _)| handle)
+                        .unwrap_or_default();
+                    // Target could be spawned later.
+                    false
+                } else {
+                    match try_get_character_ref(self.target, graph) {
+                        Some(target) => {
+                            self.target_seen_alive |= !target.is_dead();
+                            target.is_dead()
+                        }
+                        None => {
+                            if !self.target_seen_alive {
+                                self.target = Handle::NONE;
+                            }
+                            self.target_seen_alive
+                        }
+                    }
+                }
+            }
+            ObjectiveKind::SurviveTime { duration } => {
+                if player.is_some() {
+                    self.elapsed += dt;
+                }
+                self.elapsed >= duration
+            }
+        }
+    }
+}
+
+#[derive(Visit, Default, Clone, Debug)]
+pub struct ObjectiveList {
+    objectives: Vec<Objective>,
+}
+
+impl ObjectiveList {
+    pub fn for_map(map_path: &str) -> Self {
+        Self {
+            objectives: DEFINITIONS
+                .map
+                .get(map_path)
+                .map(|definitions| definitions.iter().map(Objective::from_definition).collect())
+                .unwrap_or_default(),
+        }
+    }
+
+    pub fn update(
+        &mut self,
+        graph: &Graph,
+        player: Handle<Node>,
+        sender: Option<&MessageSender>,
+        dt: f32,
+    ) {
+        for objective in self.objectives.iter_mut().filter(|o| !o.completed) {
+            if objective.check_completion(graph, player, dt) {
+                objective.completed = true;
+                if let Some(sender) = sender {
+                    sender.send(Message::ObjectiveCompleted {
+                        description: objective.description.clone(),
+                    });
+                }
+            }
+        }
+    }
+
+    pub fn objectives(&self) -> &[Objective] {
+        &self.objectives
+    }
+
+    pub fn active(&self) -> impl Iterator<Item = &Objective> {
+        self.objectives.iter().filter(|o| !o.completed)
+    }
+
+    pub fn is_all_completed(&self) -> bool {
+        self.objectives.iter().all(|o| o.completed)
+    }
+}
@@ -0,0 +1,73 @@
+use fyrox::{core::visitor::prelude::*, lazy_static::lazy_static};
+use serde::Deserialize;
+use std::{collections::HashMap, fs::File};
+
+/// Physical properties of a level, they allow to make low-gravity sections of the station.
+#[derive(Copy, Clone, Debug, Deserialize, Visit)]
+pub struct LevelPhysics {
+    /// Multiplier of the standard gravity applied to the player.
+    #[serde(default = "default_scale")]
+    pub gravity_scale: f32,
+    /// Multiplier of the initial vertical speed of a jump.
+    #[serde(default = "default_scale")]
+    pub jump_scale: f32,
+    /// Downward speed (in m/s) at landing above which actors take fall damage.
+    #[serde(default = "default_fall_damage_threshold")]
+    pub fall_damage_threshold: f32,
+    /// Damage per each m/s of landing speed above the threshold.
+    #[serde(default = "default_fall_damage_scale")]
+    pub fall_damage_scale: f32,
+}
+
+fn default_scale() -> f32 {
+    1.0
+}
+
+fn default_fall_damage_threshold() -> f32 {
+    8.0
+}
+
+fn default_fall_damage_scale() -> f32 {
+    10.0
+}
+
+impl Default for LevelPhysics {
+    fn default() -> Self {
+        Self {
+            gravity_scale: 1.0,
+            jump_scale: 1.0,
+            fall_damage_threshold: default_fall_damage_threshold(),
+            fall_damage_scale: default_fall_damage_scale(),
+        }
+    }
+}
+
+impl LevelPhysics {
+    /// Returns physical properties of the given map or the standard ones if the map has no
+    /// special settings.
+    pub fn for_map(map_path: &str) -> Self {
+        DEFINITIONS.map.get(map_path).cloned().unwrap_or_default()
+    }
+
+    /// Time (in seconds) of a fall from the same height scales as inverse square root of gravity,
+    /// this factor allows to scale time-based thresholds accordingly.
+    pub fn fall_time_factor(&self) -> f32 {
+        1.0 / self.gravity_scale.max(0.01).sqrt()
+    }
+}
+
+#[derive(Deserialize, Default)]
+pub struct LevelPhysicsContainer {
+    map: HashMap<String, LevelPhysics>,
+}
+
+impl LevelPhysicsContainer {
+    pub fn new() -> Self {
+        let file = File::open("data/configs/levels.ron").unwrap();
+        ron::de::from_reader(file).unwrap()
+    }
+}
+
+lazy_static! {
+    static ref DEFINITIONS: LevelPhysicsContainer = LevelPhysicsContainer::new();
+}
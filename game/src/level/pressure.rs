@@ -0,0 +1,97 @@
+//! Dynamic difficulty. Pressure on the player defines how fast spawn points release new bots,
+//! wounded players get more time between spawns, while players who kill fast get less. The cap
+//! and scaling factors are defined in `data/configs/pressure.ron`.
+
+use fyrox::{core::visitor::prelude::*, lazy_static::lazy_static};
+use serde::Deserialize;
+use std::fs::File;
+
+/// Time (in seconds) over which kills are averaged.
+const KILL_RATE_WINDOW: f32 = 60.0;
+const MIN_SPAWN_INTERVAL_SCALE: f32 = 0.5;
+const MAX_SPAWN_INTERVAL_SCALE: f32 = 3.0;
+
+#[derive(Deserialize, Clone, Debug)]
+pub struct SpawnPressureDefinition {
+    #[serde(default = "default_max_alive_bots")]
+    pub max_alive_bots: u32,
+    #[serde(default = "default_health_factor")]
+    pub health_factor: f32,
+    #[serde(default = "default_kill_rate_factor")]
+    pub kill_rate_factor: f32,
+}
+
+fn default_max_alive_bots() -> u32 {
+    12
+}
+
+fn default_health_factor() -> f32 {
+    1.0
+}
+
+fn default_kill_rate_factor() -> f32 {
+    0.1
+}
+
+impl SpawnPressureDefinition {
+    fn new() -> Self {
+        let file = File::open("data/configs/pressure.ron").unwrap();
+        ron::de::from_reader(file).unwrap()
+    }
+}
+
+lazy_static! {
+    static ref DEFINITION: SpawnPressureDefinition = SpawnPressureDefinition::new();
+}
+
+#[derive(Visit, Clone, Debug)]
+pub struct SpawnPressure {
+    /// Max amount of bots alive at the same time, spawners wait until some of them die.
+    pub max_alive_bots: u32,
+    /// How much low health of the player stretches spawn intervals, zero disables it.
+    pub health_factor: f32,
+    /// How much each recent kill of the player shortens spawn intervals, zero disables it.
+    pub kill_rate_factor: f32,
+    /// Amount of kills made by the player recently, decays over time.
+    recent_kills: f32,
+    /// Health of the player in [0; 1] range.
+    player_health: f32,
+}
+
+impl Default for SpawnPressure {
+    fn default() -> Self {
+        Self::from_definition(&DEFINITION)
+    }
+}
+
+impl SpawnPressure {
+    pub fn from_definition(definition: &SpawnPressureDefinition) -> Self {
+        Self {
+            max_alive_bots: definition.max_alive_bots,
+            health_factor: definition.health_factor,
+            kill_rate_factor: definition.kill_rate_factor,
+            recent_kills: 0.0,
+            player_health: 1.0,
+        }
+    }
+
+    pub fn register_kill(&mut self) {
+        self.recent_kills += 1.0;
+    }
+
+    pub fn update(&mut self, player_health: f32, dt: f32) {
+        self.player_health = player_health.clamp(0.0, 1.0);
+        self.recent_kills *= (-dt / KILL_RATE_WINDOW).exp();
+    }
+
+    /// Multiplier for intervals between spawns, values above one slow spawns down.
+    pub fn spawn_interval_scale(&self) -> f32 {
+        let health = 1.0 + self.health_factor * (1.0 - self.player_health);
+        let kills = 1.0 + self.kill_rate_factor * self.recent_kills;
+        (health / kills).clamp(MIN_SPAWN_INTERVAL_SCALE, MAX_SPAWN_INTERVAL_SCALE)
+    }
+
+    pub fn can_spawn(&self, alive_bots: usize) -> bool {
+        alive_bots < self.max_alive_bots as usize
+    }
+}
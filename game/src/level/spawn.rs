@@ -1,5 +1,7 @@
 use crate::{
+    bot::Bot,
     character::{Character, CharacterCommand},
+    current_level_ref,
     weapon::definition::WeaponKind,
 };
 use fyrox::{
@@ -19,10 +21,43 @@ use fyrox::{
 #[derive(Visit, Reflect, Default, Debug, Clone)]
 pub struct DefaultWeapon(WeaponKind);
 
-#[derive(Visit, Reflect, Default, Debug, Clone)]
+#[derive(Visit, Reflect, Debug, Clone)]
 pub struct CharacterSpawnPoint {
     default_weapons: Vec<DefaultWeapon>,
     prefab: Option<Model>,
+    #[visit(optional)]
+    #[reflect(
+        description = "Time (in seconds) during which spawned character can't be damaged by others.",
+        min_value = 0.0
+    )]
+    spawn_protection: f32,
+    #[visit(optional)]
+    #[reflect(
+        description = "Time (in seconds) between spawns of bots. Zero means that the character \
+        is spawned only once when the level starts.",
+        min_value = 0.0
+    )]
+    respawn_interval: f32,
+    #[visit(optional)]
+    #[reflect(hidden)]
+    respawn_timer: f32,
+    // The first spawn was blocked by the bot cap, it's retried until it succeeds.
+    #[visit(optional)]
+    #[reflect(hidden)]
+    initial_spawn_pending: bool,
+}
+
+impl Default for CharacterSpawnPoint {
+    fn default() -> Self {
+        Self {
+            default_weapons: Default::default(),
+            prefab: None,
+            spawn_protection: 2.0,
+            respawn_interval: 0.0,
+            respawn_timer: 0.0,
+            initial_spawn_pending: false,
+        }
+    }
 }
 
 impl_component_provider!(CharacterSpawnPoint);
@@ -33,9 +68,24 @@ impl TypeUuidProvider for CharacterSpawnPoint {
     }
 }
 
-impl ScriptTrait for CharacterSpawnPoint {
-    fn on_init(&mut self, ctx: &mut ScriptContext) {
+fn is_bot_prefab(model: &Model) -> bool {
+    let data = model.data_ref();
+    let graph = &data.get_scene().graph;
+    graph[graph.get_root()].try_get_script::<Bot>().is_some()
+}
+
+impl CharacterSpawnPoint {
+    /// Spawns a character, returns `false` if it is a bot and the alive bot cap of the level is
+    /// reached, such spawn should be retried later.
+    fn spawn(&self, ctx: &mut ScriptContext) -> bool {
         if let Some(model) = self.prefab.as_ref() {
+            if is_bot_prefab(model)
+                && current_level_ref(ctx.plugins)
+                    .map_or(false, |level| !level.can_spawn_bot(&ctx.scene.graph))
+            {
+                return false;
+            }
+
             // Take rotation and position for the point.
             let (rotation, position) = ctx
                 .scene
@@ -60,11 +110,48 @@ impl ScriptTrait for CharacterSpawnPoint {
                 for weapon in self.default_weapons.iter() {
                     character.push_command(CharacterCommand::AddWeapon(weapon.0))
                 }
+
+                character.protect(self.spawn_protection);
             } else {
                 Log::err("Unable to find character in a prefab!")
             }
+
+            true
         } else {
-            Log::warn("Prefab is not set, nothing to spawn!")
+            Log::warn("Prefab is not set, nothing to spawn!");
+
+            true
+        }
+    }
+}
+
+impl ScriptTrait for CharacterSpawnPoint {
+    fn on_init(&mut self, ctx: &mut ScriptContext) {
+        self.initial_spawn_pending = !self.spawn(ctx);
+        self.respawn_timer = self.respawn_interval;
+    }
+
+    fn on_update(&mut self, ctx: &mut ScriptContext) {
+        if self.initial_spawn_pending {
+            self.initial_spawn_pending = !self.spawn(ctx);
+            return;
+        }
+
+        if self.respawn_interval <= 0.0 {
+            return;
+        }
+
+        self.respawn_timer -= ctx.dt;
+        if self.respawn_timer > 0.0 {
+            return;
+        }
+
+        // Wait until some bots die if the cap is reached, the pace of further spawns depends on
+        // pressure on the player.
+        if self.spawn(ctx) {
+            let scale = current_level_ref(ctx.plugins)
+                .map_or(1.0, |level| level.pressure.spawn_interval_scale());
+            self.respawn_timer = self.respawn_interval * scale;
         }
     }
 
@@ -0,0 +1,49 @@
+use fyrox::{
+    core::{pool::Handle, visitor::prelude::*},
+    scene::node::Node,
+};
+use std::collections::HashMap;
+
+#[derive(Default, Visit, Debug, Clone)]
+pub struct ActorStats {
+    pub kills: u32,
+    pub damage_dealt: f32,
+    /// How many times the actor fell into a death zone.
+    #[visit(optional)]
+    pub void_outs: u32,
+}
+
+/// Per-level statistics, saved together with the level.
+#[derive(Default, Visit, Debug, Clone)]
+pub struct LevelStats {
+    actors: HashMap<Handle<Node>, ActorStats>,
+    time_survived: f32,
+}
+
+impl LevelStats {
+    pub fn register_damage(&mut self, attacker: Handle<Node>, amount: f32, killed: bool) {
+        let stats = self.actors.entry(attacker).or_default();
+        stats.damage_dealt += amount;
+        if killed {
+            stats.kills += 1;
+        }
+    }
+
+    /// Void-outs are not kills of anyone, they're just counted for the fallen actor.
+    pub fn register_void_out(&mut self, actor: Handle<Node>) {
+        self.actors.entry(actor).or_default().void_outs += 1;
+    }
+
+    pub fn actor_stats(&self, actor: Handle<Node>) -> Option<&ActorStats> {
+        self.actors.get(&actor)
+    }
+
+    pub fn add_survival_time(&mut self, dt: f32) {
+        self.time_survived += dt;
+    }
+
+    /// Time (in seconds) the player has been alive on the level.
+    pub fn time_survived(&self) -> f32 {
+        self.time_survived
+    }
+}
@@ -26,6 +26,16 @@ impl Default for TriggerKind {
 #[derive(Visit, Reflect, Debug, Default, Clone)]
 pub struct Trigger {
     kind: TriggerKind,
+
+    #[visit(optional)]
+    #[reflect(
+        description = "Whether the trigger fires only once or each time an actor is inside."
+    )]
+    one_shot: bool,
+
+    #[visit(optional)]
+    #[reflect(hidden)]
+    fired: bool,
 }
 
 impl_component_provider!(Trigger);
@@ -38,6 +48,10 @@ impl TypeUuidProvider for Trigger {
 
 impl ScriptTrait for Trigger {
     fn on_update(&mut self, context: &mut ScriptContext) {
+        if self.one_shot && self.fired {
+            return;
+        }
+
         let game = game_ref(context.plugins);
 
         let position = context.scene.graph[context.handle].global_position();
@@ -52,6 +66,12 @@ impl ScriptTrait for Trigger {
                         TriggerKind::NextLevel => game.message_sender.send(Message::LoadNextLevel),
                         TriggerKind::EndGame => game.message_sender.send(Message::EndGame),
                     }
+
+                    self.fired = true;
+
+                    if self.one_shot {
+                        break;
+                    }
                 }
             }
         }
@@ -1,6 +1,7 @@
 use crate::{
     character::{character_ref, try_get_character_ref},
-    current_level_ref,
+    current_level_ref, game_ref,
+    level::decal::DecalContainer,
     sound::SoundManager,
     weapon::{definition::ShotEffect, projectile::Damage},
     Player, Weapon,
@@ -169,6 +170,7 @@ impl TypeUuidProvider for Turret {
 impl ScriptTrait for Turret {
     fn on_update(&mut self, ctx: &mut ScriptContext) {
         let level_ref = current_level_ref(ctx.plugins).expect("Level must exist!");
+        let gore = game_ref(ctx.plugins).accessibility.gore;
 
         self.update_frustum(ctx.scene);
 
@@ -219,6 +221,8 @@ impl ScriptTrait for Turret {
                                 &level_ref.actors,
                                 ctx.resource_manager,
                                 &level_ref.sound_manager,
+                                &level_ref.decals,
+                                gore,
                             );
                             self.barrel_index += 1;
                             if self.barrel_index >= self.barrels.len() as u32 {
@@ -235,6 +239,8 @@ impl ScriptTrait for Turret {
                                 &level_ref.actors,
                                 ctx.resource_manager,
                                 &level_ref.sound_manager,
+                                &level_ref.decals,
+                                gore,
                             );
                         }
                     }
@@ -307,6 +313,8 @@ impl Barrel {
         actors: &[Handle<Node>],
         resource_manager: &ResourceManager,
         sound_manager: &SoundManager,
+        decals: &DecalContainer,
+        gore: bool,
     ) {
         self.offset = Vector3::new(-20.0, 0.0, 0.0);
 
@@ -320,9 +328,14 @@ impl Barrel {
             shot_position,
             target_position,
             Damage::Point(10.0),
+            0,
+            1.0,
+            1.0,
             ShotEffect::Smoke,
             sound_manager,
             0.01,
+            decals,
+            gore,
         );
 
         let sounds = [
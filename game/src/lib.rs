@@ -24,23 +24,25 @@ pub mod weapon;
 use crate::weapon::projectile::Projectile;
 use crate::{
     bot::Bot,
-    config::{Config, SoundConfig},
+    config::{AccessibilityConfig, Config, SoundConfig},
     control_scheme::ControlScheme,
     door::{ui::DoorUiContainer, Door},
     elevator::{call_button::CallButton, ui::CallButtonUiContainer, Elevator},
     gui::{
-        inventory::InventoryInterface, item_display::ItemDisplay, journal::JournalDisplay,
-        weapon_display::WeaponDisplay, DeathScreen, FinalScreen,
+        damage_indicator::DamageIndicator, event_feed::EventFeed, inventory::InventoryInterface,
+        item_display::ItemDisplay, journal::JournalDisplay, weapon_display::WeaponDisplay,
+        DeathScreen, FinalScreen,
     },
     level::{
-        death_zone::DeathZone, decal::Decal, item::Item, spawn::CharacterSpawnPoint,
-        turret::Turret, Level,
+        death_zone::DeathZone, decal::Decal, explosive::Explosive, item::Item,
+        spawn::CharacterSpawnPoint, turret::Turret, Level,
     },
     light::AnimatedLight,
     loading_screen::LoadingScreen,
     menu::Menu,
     message::Message,
     player::{camera::CameraController, Player},
+    sound::{Mixer, SoundBus},
     utils::use_hrtf,
     weapon::Weapon,
 };
@@ -69,6 +71,7 @@ use fyrox::{
     resource::texture::Texture,
     scene::{
         base::BaseBuilder,
+        node::Node,
         sound::{SoundBuilder, Status},
         Scene, SceneLoader,
     },
@@ -96,6 +99,8 @@ pub struct Game {
     debug_string: String,
     running: bool,
     control_scheme: ControlScheme,
+    /// Control schemes of local co-op players except the first one, who uses `control_scheme`.
+    co_op_control_schemes: Vec<ControlScheme>,
     message_receiver: Receiver<Message>,
     message_sender: MessageSender,
     load_context: Option<Arc<Mutex<LoadContext>>>,
@@ -108,13 +113,19 @@ pub struct Game {
     journal_display: JournalDisplay,
     door_ui_container: DoorUiContainer,
     call_button_ui_container: CallButtonUiContainer,
+    damage_indicator: DamageIndicator,
+    event_feed: EventFeed,
     // We're storing sound config separately because we can adjust sound
     // setting in the options but don't have a level loaded. This field
     // is data-model for options menu.
     sound_config: SoundConfig,
     update_duration: Duration,
     show_debug_info: bool,
+    accessibility: AccessibilityConfig,
     smaller_font: SharedFont,
+    // Lives left of the player on the previous level, they're carried to the player of the
+    // next level.
+    player_lives: Option<u32>,
 }
 
 pub fn game_ref(plugins: &[Box<dyn Plugin>]) -> &Game {
@@ -204,11 +215,13 @@ impl Game {
         let mut control_scheme = ControlScheme::default();
         let mut sound_config = SoundConfig::default();
         let mut show_debug_info = false;
+        let mut accessibility = AccessibilityConfig::default();
 
         match Config::load() {
             Ok(config) => {
                 show_debug_info = config.show_debug_info;
                 sound_config = config.sound;
+                accessibility = config.accessibility;
 
                 match context
                     .renderer
@@ -220,7 +233,9 @@ impl Game {
                     Err(e) => Log::err(format!("Failed to set graphics settings. Reason: {:?}", e)),
                 }
 
-                control_scheme = config.controls;
+                if let Some(controls) = config.controls {
+                    control_scheme = controls;
+                }
             }
             Err(e) => {
                 Log::writeln(
@@ -233,17 +248,32 @@ impl Game {
             }
         }
 
-        let (tx, rx) = mpsc::channel();
+        // Controls saved in their own file take precedence over ones migrated from settings.
+        match ControlScheme::load(ControlScheme::PATH) {
+            Ok(controls) => control_scheme = controls,
+            Err(e) => Log::warn(format!(
+                "Failed to load controls, using defaults. Reason: {:?}",
+                e
+            )),
+        }
 
+        // Additional players use the controls of the first one, unless they have their own.
+        let co_op_control_schemes =
+            ControlScheme::load_list(ControlScheme::CO_OP_PATH).unwrap_or_default();
+
+        // Master volume is applied by the sound engine, so it affects every sound.
         context
             .sound_engine
             .set_sound_gain(sound_config.master_volume);
 
+        let (tx, rx) = mpsc::channel();
+
         let message_sender = MessageSender { sender: tx };
         let weapon_display = WeaponDisplay::new(font.clone(), context.resource_manager.clone());
         let inventory_interface = InventoryInterface::new(message_sender.clone());
         let item_display = ItemDisplay::new(smaller_font.clone());
         let journal_display = JournalDisplay::new();
+        let event_feed = EventFeed::new(context.user_interface, smaller_font.clone());
 
         let level = if override_scene.is_some() {
             let sound_config = sound_config.clone();
@@ -276,6 +306,7 @@ impl Game {
                 font.clone(),
                 show_debug_info,
                 &sound_config,
+                &accessibility,
             )),
             death_screen: DeathScreen::new(
                 context.user_interface,
@@ -284,6 +315,7 @@ impl Game {
             ),
             final_screen: FinalScreen::new(context.user_interface, font, message_sender.clone()),
             control_scheme,
+            co_op_control_schemes,
             debug_text: Handle::NONE,
             weapon_display,
             item_display,
@@ -296,9 +328,13 @@ impl Game {
             message_receiver: rx,
             message_sender,
             sound_config,
+            accessibility,
             update_duration: Default::default(),
             door_ui_container: Default::default(),
             call_button_ui_container: Default::default(),
+            damage_indicator: Default::default(),
+            event_feed,
+            player_lives: None,
         };
 
         game.create_debug_ui(&mut context);
@@ -307,6 +343,16 @@ impl Game {
         game
     }
 
+    /// Returns control scheme of the given local player.
+    pub fn player_control_scheme(&self, player: Handle<Node>) -> &ControlScheme {
+        self.level
+            .as_ref()
+            .map(|level| level.player_index(player))
+            .and_then(|index| index.checked_sub(1))
+            .and_then(|index| self.co_op_control_schemes.get(index))
+            .unwrap_or(&self.control_scheme)
+    }
+
     fn handle_ui_message(&mut self, context: &mut PluginContext, message: &UiMessage) {
         self.menu.handle_ui_message(
             context,
@@ -314,6 +360,7 @@ impl Game {
             &mut self.control_scheme,
             &mut self.show_debug_info,
             &self.sound_config,
+            &mut self.accessibility,
         );
 
         self.death_screen.handle_ui_message(message);
@@ -423,7 +470,7 @@ impl Game {
 
         // Set control scheme for player.
         if let Some(level) = &mut self.level {
-            level.resolve(context, self.message_sender.clone());
+            level.resolve(context, self.message_sender.clone(), &self.sound_config);
         }
 
         self.menu.sync_to_model(context, true);
@@ -435,6 +482,7 @@ impl Game {
         if let Some(ref mut level) = self.level.take() {
             self.door_ui_container.clear();
             self.call_button_ui_container.clear();
+            self.damage_indicator.clear();
             level.destroy(context);
             Log::info("Current level destroyed!");
         }
@@ -492,6 +540,11 @@ impl Game {
             || self.final_screen.is_visible(context.user_interface)
     }
 
+    /// Directions of recent hits taken by the player.
+    pub fn damage_indicator(&self) -> &DamageIndicator {
+        &self.damage_indicator
+    }
+
     pub fn update(&mut self, ctx: &mut PluginContext) {
         let last_time = std::time::Instant::now();
 
@@ -534,6 +587,7 @@ impl Game {
 
         if let Some(ref mut level) = self.level {
             ctx.scenes[level.scene].enabled = !self.menu.is_visible(ctx.user_interface);
+            level.update(ctx, self.control_scheme.item_magnet);
         }
 
         self.menu.scene.update(ctx, ctx.dt);
@@ -542,6 +596,9 @@ impl Game {
         self.item_display.update(ctx.dt);
         self.door_ui_container.update(ctx.dt);
         self.call_button_ui_container.update(ctx.dt);
+        self.damage_indicator.update(ctx.dt);
+        self.event_feed
+            .update(ctx.user_interface, self.level.as_ref());
 
         self.handle_messages(ctx);
 
@@ -558,9 +615,11 @@ impl Game {
         while let Ok(message) = self.message_receiver.try_recv() {
             match &message {
                 Message::StartNewGame => {
+                    self.player_lives = None;
                     self.load_level(Level::ARRIVAL_PATH, context);
                 }
                 Message::LoadTestbed => {
+                    self.player_lives = None;
                     self.load_level(Level::TESTBED_PATH, context);
                 }
                 Message::SaveGame => match self.save_game(context) {
@@ -568,6 +627,8 @@ impl Game {
                     Err(e) => Log::err(format!("Failed to make a save, reason: {}", e)),
                 },
                 Message::LoadGame => {
+                    // Saved player already has its lives.
+                    self.player_lives = None;
                     if let Err(e) = self.load_game(context) {
                         Log::err(format!("Failed to load saved game. Reason: {:?}", e));
                     }
@@ -580,6 +641,13 @@ impl Game {
                         };
 
                         if let Some(kind) = kind {
+                            self.player_lives = context
+                                .scenes
+                                .try_get(level.scene)
+                                .and_then(|scene| scene.graph.try_get(level.get_player()))
+                                .and_then(|player| player.try_get_script::<Player>())
+                                .map(|player| player.lives());
+
                             self.load_level(kind, context)
                         }
                     }
@@ -598,12 +666,31 @@ impl Game {
                     self.final_screen.set_visible(context.user_interface, true);
                     self.menu.sync_to_model(context, false);
                 }
+                Message::SetDifficulty(difficulty) => {
+                    if let Some(level) = self.level.as_ref() {
+                        let graph = &mut context.scenes[level.scene].graph;
+                        for &player in level.players.iter() {
+                            if let Some(player_ref) = graph
+                                .try_get_mut(player)
+                                .and_then(|p| p.try_get_script_mut::<Player>())
+                            {
+                                player_ref.set_health_mode(difficulty.health_mode());
+                            }
+                        }
+                    }
+                }
                 Message::SetMusicVolume(volume) => {
                     self.sound_config.music_volume = *volume;
-                    // TODO: Apply to sound manager of level when it will handle music!
-                    context.scenes[self.menu.scene.scene].graph[self.menu.scene.music]
-                        .as_sound_mut()
-                        .set_gain(*volume);
+                    if let Some(level) = self.level.as_mut() {
+                        level.sound_manager.mixer_mut().set_music_gain(*volume);
+                    }
+                    self.sync_menu_music_gain(context);
+                }
+                Message::SetSfxVolume(volume) => {
+                    self.sound_config.sfx_volume = *volume;
+                    if let Some(level) = self.level.as_mut() {
+                        level.sound_manager.mixer_mut().set_sfx_gain(*volume);
+                    }
                 }
                 Message::SetUseHrtf(state) => {
                     self.sound_config.use_hrtf = *state;
@@ -625,11 +712,18 @@ impl Game {
                     context.sound_engine.set_sound_gain(*volume);
                 }
                 Message::SaveConfig => {
+                    if let Err(e) = self.control_scheme.save(ControlScheme::PATH) {
+                        Log::writeln(
+                            MessageKind::Error,
+                            format!("Failed to save controls. Reason: {:?}", e),
+                        );
+                    }
+
                     match Config::save(
                         context,
-                        self.control_scheme.clone(),
                         self.sound_config.clone(),
                         self.show_debug_info,
+                        self.accessibility.clone(),
                     ) {
                         Ok(_) => {
                             Log::info("Settings saved!");
@@ -662,6 +756,15 @@ impl Game {
                         self.journal_display.sync_to_model(&player_ref.journal);
                     }
                 }
+                Message::DamageDirection {
+                    actor,
+                    direction,
+                    amount,
+                } => {
+                    if self.level.as_ref().map_or(false, |l| l.player == *actor) {
+                        self.damage_indicator.add_hit(*direction, *amount);
+                    }
+                }
                 Message::Play2DSound { path, gain } => {
                     if let Ok(buffer) = fyrox::core::futures::executor::block_on(
                         context.resource_manager.request_sound_buffer(path),
@@ -671,7 +774,11 @@ impl Game {
                             .with_buffer(buffer.into())
                             .with_status(Status::Playing)
                             .with_play_once(true)
-                            .with_gain(*gain)
+                            .with_gain(
+                                *gain
+                                    * Mixer::from_config(&self.sound_config)
+                                        .bus_gain(SoundBus::Sfx),
+                            )
                             .build(&mut menu_scene.graph);
                     }
                 }
@@ -684,6 +791,13 @@ impl Game {
         }
     }
 
+    fn sync_menu_music_gain(&self, context: &mut PluginContext) {
+        let gain = Mixer::from_config(&self.sound_config).bus_gain(SoundBus::Music);
+        context.scenes[self.menu.scene.scene].graph[self.menu.scene.music]
+            .as_sound_mut()
+            .set_gain(gain);
+    }
+
     pub fn update_statistics(&mut self, elapsed: f64, context: &mut PluginContext) {
         if self.show_debug_info {
             self.debug_string.clear();
@@ -775,6 +889,7 @@ impl PluginConstructor for GameConstructor {
             .script_constructors
             .add::<Door>("Door")
             .add::<Turret>("Turret")
+            .add::<Explosive>("Explosive")
             .add::<Weapon>("Weapon")
             .add::<Item>("Item")
             .add::<Decal>("Decal")
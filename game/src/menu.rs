@@ -1,6 +1,11 @@
 use crate::{
-    config::SoundConfig, control_scheme::ControlScheme, message::Message,
-    options_menu::OptionsMenu, utils::create_camera, MessageSender,
+    config::{AccessibilityConfig, SoundConfig},
+    control_scheme::ControlScheme,
+    message::Message,
+    options_menu::OptionsMenu,
+    sound::{Mixer, SoundBus},
+    utils::create_camera,
+    MessageSender,
 };
 use fyrox::{
     core::{
@@ -70,7 +75,7 @@ impl MenuScene {
             .with_buffer(buffer.into())
             .with_looping(true)
             .with_status(Status::Playing)
-            .with_gain(sound_config.music_volume)
+            .with_gain(Mixer::from_config(sound_config).bus_gain(SoundBus::Music))
             .build(&mut scene.graph);
 
         let position = scene
@@ -122,6 +127,7 @@ impl Menu {
         font: SharedFont,
         show_debug_info: bool,
         sound_config: &SoundConfig,
+        accessibility: &AccessibilityConfig,
     ) -> Self {
         let frame_size = context.renderer.get_frame_size();
 
@@ -253,6 +259,7 @@ impl Menu {
                 sender,
                 show_debug_info,
                 sound_config,
+                accessibility,
             ),
         }
     }
@@ -321,6 +328,7 @@ impl Menu {
         control_scheme: &mut ControlScheme,
         show_debug_info: &mut bool,
         sound_config: &SoundConfig,
+        accessibility: &mut AccessibilityConfig,
     ) {
         if let Some(ButtonMessage::Click) = message.data() {
             if message.destination() == self.btn_new_game {
@@ -360,6 +368,7 @@ impl Menu {
             control_scheme,
             show_debug_info,
             sound_config,
+            accessibility,
         );
     }
 }
@@ -3,8 +3,9 @@
 //! required entity. This is very effective decoupling mechanism that works perfectly with
 //! strict ownership rules of Rust.
 
+use crate::{control_scheme::Difficulty, level::item::ItemKind};
 use fyrox::{
-    core::{algebra::Vector3, pool::Handle},
+    core::{algebra::Vector3, color::Color, pool::Handle},
     scene::node::Node,
 };
 use std::path::PathBuf;
@@ -23,6 +24,76 @@ pub enum Message {
         who: Handle<Node>,
         critical_shot_probability: f32,
     },
+    /// Instantly moves an actor to the given position, velocity of the actor will be reset.
+    TeleportActor {
+        actor: Handle<Node>,
+        position: Vector3<f32>,
+        /// Whether to show teleportation effect at the destination or not.
+        with_effect: bool,
+    },
+    /// Brings the dead player back to life at the point where the player has entered the level.
+    SpawnPlayer {
+        player: Handle<Node>,
+    },
+    /// Damages an actor without a hit, for example when it lands after a long fall.
+    DamageActor {
+        actor: Handle<Node>,
+        /// Damage initiator, `Handle::NONE` if the damage is not attributed to anyone.
+        who: Handle<Node>,
+        amount: f32,
+    },
+    /// Sent by a character each time it receives damage, used to collect level statistics.
+    ActorDamaged {
+        actor: Handle<Node>,
+        /// Damage initiator, could be a character or a weapon.
+        who: Handle<Node>,
+        amount: f32,
+        /// Whether the damage killed the actor or not.
+        killed: bool,
+    },
+    /// Purely informational message, sent when damage lands on a character. Could be used to
+    /// show floating damage numbers or hit markers.
+    HitConfirmed {
+        /// Damage initiator, could be a character or a weapon.
+        who: Handle<Node>,
+        position: Vector3<f32>,
+        amount: f32,
+        killed: bool,
+    },
+    /// Sent when an actor is hit by an attacker with known position, used to show where the
+    /// damage came from.
+    DamageDirection {
+        actor: Handle<Node>,
+        /// Direction from the actor to the attacker.
+        direction: Vector3<f32>,
+        amount: f32,
+    },
+    /// Sent when an actor falls into a death zone. Such deaths are not attributed to anyone.
+    ActorVoidedOut {
+        actor: Handle<Node>,
+        position: Vector3<f32>,
+    },
+    /// Purely informational message, sent when an actor picks up an item.
+    ItemPickedUp {
+        actor: Handle<Node>,
+        kind: ItemKind,
+    },
+    /// Purely informational message, sent when a door is broken.
+    DoorBreached {
+        door: Handle<Node>,
+    },
+    /// Cuts (`false`) or restores (`true`) power of the current level.
+    SetPower(bool),
+    /// Smoothly changes ambient lighting of the current level, used by scripted events.
+    SetAmbientLighting {
+        color: Color,
+        /// Duration (in seconds) of the transition, zero changes lighting immediately.
+        fade_time: f32,
+    },
+    /// Sent when the player completes one of the objectives of a level.
+    ObjectiveCompleted {
+        description: String,
+    },
     /// Save game state to a file. TODO: Add filename field.
     SaveGame,
     /// Loads game state from a file. TODO: Add filename field.
@@ -37,8 +108,10 @@ pub enum Message {
     SyncInventory,
     SyncJournal,
     SaveConfig,
+    SetDifficulty(Difficulty),
     // Sound-related messages.
     SetMusicVolume(f32),
+    SetSfxVolume(f32),
     SetUseHrtf(bool),
     SetMasterVolume(f32),
 }
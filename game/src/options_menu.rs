@@ -1,6 +1,6 @@
 use crate::{
-    config::SoundConfig,
-    control_scheme::{ControlButton, ControlScheme},
+    config::{AccessibilityConfig, SoundConfig},
+    control_scheme::{ControlButton, ControlScheme, Difficulty},
     gui::{create_check_box, create_scroll_bar, ScrollBarData},
     message::Message,
     MessageSender,
@@ -36,6 +36,7 @@ pub struct OptionsMenu {
     sender: MessageSender,
     sound_volume: Handle<UiNode>,
     pub music_volume: Handle<UiNode>,
+    sfx_volume: Handle<UiNode>,
     video_mode: Handle<UiNode>,
     spot_shadows: Handle<UiNode>,
     soft_spot_shadows: Handle<UiNode>,
@@ -51,12 +52,21 @@ pub struct OptionsMenu {
     active_control_button: Option<usize>,
     mouse_sens: Handle<UiNode>,
     mouse_y_inverse: Handle<UiNode>,
+    weapon_sway: Handle<UiNode>,
+    field_of_view: Handle<UiNode>,
+    auto_pickup: Handle<UiNode>,
+    item_magnet: Handle<UiNode>,
+    weapon_switch_speed: Handle<UiNode>,
+    difficulty: Handle<UiNode>,
     reset_control_scheme: Handle<UiNode>,
     use_hrtf: Handle<UiNode>,
     reset_audio_settings: Handle<UiNode>,
     point_shadows_quality: Handle<UiNode>,
     spot_shadows_quality: Handle<UiNode>,
     show_debug_info: Handle<UiNode>,
+    gore: Handle<UiNode>,
+    camera_shake_scale: Handle<UiNode>,
+    flashing: Handle<UiNode>,
 }
 
 fn make_text_mark(text: &str, row: usize, ctx: &mut BuildContext) -> Handle<UiNode> {
@@ -137,6 +147,36 @@ fn make_shadows_quality_drop_down(
     .build(ctx)
 }
 
+fn make_difficulty_drop_down(
+    ctx: &mut BuildContext,
+    row: usize,
+    current: Difficulty,
+) -> Handle<UiNode> {
+    DropdownListBuilder::new(
+        WidgetBuilder::new()
+            .on_row(row)
+            .on_column(1)
+            .with_margin(Thickness::uniform(1.0)),
+    )
+    .with_items({
+        Difficulty::ALL
+            .iter()
+            .map(|d| {
+                DecoratorBuilder::new(BorderBuilder::new(
+                    WidgetBuilder::new().with_child(
+                        TextBuilder::new(WidgetBuilder::new())
+                            .with_text(d.name())
+                            .build(ctx),
+                    ),
+                ))
+                .build(ctx)
+            })
+            .collect::<Vec<_>>()
+    })
+    .with_selected(current.index())
+    .build(ctx)
+}
+
 fn shadows_quality(size: usize) -> usize {
     if size < 256 {
         0
@@ -165,6 +205,7 @@ impl OptionsMenu {
         sender: MessageSender,
         show_debug_info_value: bool,
         sound_config: &SoundConfig,
+        accessibility: &AccessibilityConfig,
     ) -> Self {
         let video_modes: Vec<VideoMode> = if let Some(monitor) = engine.window.current_monitor() {
             monitor
@@ -187,6 +228,7 @@ impl OptionsMenu {
 
         let sound_volume;
         let music_volume;
+        let sfx_volume;
         let video_mode;
         let spot_shadows;
         let soft_spot_shadows;
@@ -196,6 +238,15 @@ impl OptionsMenu {
         let spot_shadow_distance;
         let mouse_sens;
         let mouse_y_inverse;
+        let weapon_sway;
+        let field_of_view;
+        let auto_pickup;
+        let item_magnet;
+        let weapon_switch_speed;
+        let difficulty;
+        let gore;
+        let camera_shake_scale;
+        let flashing;
         let reset_control_scheme;
         let mut control_scheme_buttons = Vec::new();
         let use_hrtf;
@@ -413,14 +464,32 @@ impl OptionsMenu {
                                     );
                                     music_volume
                                 })
-                                .with_child(make_text_mark("Use HRTF", 2, ctx))
+                                .with_child(make_text_mark("SFX Volume", 2, ctx))
+                                .with_child({
+                                    sfx_volume = create_scroll_bar(
+                                        ctx,
+                                        ScrollBarData {
+                                            min: 0.0,
+                                            max: 1.0,
+                                            value: sound_config.sfx_volume,
+                                            step: 0.025,
+                                            row: 2,
+                                            column: 1,
+                                            margin,
+                                            show_value: true,
+                                            orientation: Orientation::Horizontal,
+                                        },
+                                    );
+                                    sfx_volume
+                                })
+                                .with_child(make_text_mark("Use HRTF", 3, ctx))
                                 .with_child({
-                                    use_hrtf = create_check_box(ctx, 2, 1, sound_config.use_hrtf);
+                                    use_hrtf = create_check_box(ctx, 3, 1, sound_config.use_hrtf);
                                     use_hrtf
                                 })
                                 .with_child({
                                     reset_audio_settings = ButtonBuilder::new(
-                                        WidgetBuilder::new().on_row(4).with_margin(margin),
+                                        WidgetBuilder::new().on_row(5).with_margin(margin),
                                     )
                                     .with_text("Reset")
                                     .build(ctx);
@@ -430,6 +499,7 @@ impl OptionsMenu {
                         .add_row(common_row)
                         .add_row(common_row)
                         .add_row(common_row)
+                        .add_row(common_row)
                         .add_row(Row::stretch())
                         .add_row(common_row)
                         .add_column(Column::strict(250.0))
@@ -440,6 +510,53 @@ impl OptionsMenu {
             },
         };
 
+        let accessibility_tab = TabDefinition {
+            header: make_tab_header("Accessibility", ctx),
+            content: {
+                ScrollViewerBuilder::new(WidgetBuilder::new())
+                    .with_content(
+                        GridBuilder::new(
+                            WidgetBuilder::new()
+                                .with_child(make_text_mark("Gore", 0, ctx))
+                                .with_child({
+                                    gore = create_check_box(ctx, 0, 1, accessibility.gore);
+                                    gore
+                                })
+                                .with_child(make_text_mark("Camera Shake", 1, ctx))
+                                .with_child({
+                                    camera_shake_scale = create_scroll_bar(
+                                        ctx,
+                                        ScrollBarData {
+                                            min: 0.0,
+                                            max: 1.0,
+                                            value: accessibility.camera_shake_scale,
+                                            step: 0.1,
+                                            row: 1,
+                                            column: 1,
+                                            margin,
+                                            show_value: true,
+                                            orientation: Orientation::Horizontal,
+                                        },
+                                    );
+                                    camera_shake_scale
+                                })
+                                .with_child(make_text_mark("Flashing Effects", 2, ctx))
+                                .with_child({
+                                    flashing = create_check_box(ctx, 2, 1, accessibility.flashing);
+                                    flashing
+                                }),
+                        )
+                        .add_row(common_row)
+                        .add_row(common_row)
+                        .add_row(common_row)
+                        .add_column(Column::strict(250.0))
+                        .add_column(Column::stretch())
+                        .build(ctx),
+                    )
+                    .build(ctx)
+            },
+        };
+
         let controls_tab = TabDefinition {
             header: make_tab_header("Controls", ctx),
             content: {
@@ -449,7 +566,7 @@ impl OptionsMenu {
 
                         for (row, button) in control_scheme.buttons().iter().enumerate() {
                             // Offset by total amount of rows that goes before
-                            let row = row + 2;
+                            let row = row + 8;
 
                             children.push(make_text_mark(button.description.as_str(), row, ctx));
 
@@ -491,10 +608,85 @@ impl OptionsMenu {
                                         create_check_box(ctx, 1, 1, control_scheme.mouse_y_inverse);
                                     mouse_y_inverse
                                 })
+                                .with_child(make_text_mark("Weapon Sway", 2, ctx))
+                                .with_child({
+                                    weapon_sway = create_scroll_bar(
+                                        ctx,
+                                        ScrollBarData {
+                                            min: 0.0,
+                                            max: 2.0,
+                                            value: control_scheme.weapon_sway,
+                                            step: 0.1,
+                                            row: 2,
+                                            column: 1,
+                                            margin,
+                                            show_value: true,
+                                            orientation: Orientation::Horizontal,
+                                        },
+                                    );
+                                    weapon_sway
+                                })
+                                .with_child(make_text_mark("Field of View", 3, ctx))
+                                .with_child({
+                                    field_of_view = create_scroll_bar(
+                                        ctx,
+                                        ScrollBarData {
+                                            min: 50.0,
+                                            max: 110.0,
+                                            value: control_scheme.field_of_view,
+                                            step: 1.0,
+                                            row: 3,
+                                            column: 1,
+                                            margin,
+                                            show_value: true,
+                                            orientation: Orientation::Horizontal,
+                                        },
+                                    );
+                                    field_of_view
+                                })
+                                .with_child(make_text_mark("Auto Pickup", 4, ctx))
+                                .with_child({
+                                    auto_pickup =
+                                        create_check_box(ctx, 4, 1, control_scheme.auto_pickup);
+                                    auto_pickup
+                                })
+                                .with_child(make_text_mark("Item Magnet", 5, ctx))
+                                .with_child({
+                                    item_magnet =
+                                        create_check_box(ctx, 5, 1, control_scheme.item_magnet);
+                                    item_magnet
+                                })
+                                .with_child(make_text_mark("Weapon Switch Speed", 6, ctx))
+                                .with_child({
+                                    weapon_switch_speed = create_scroll_bar(
+                                        ctx,
+                                        ScrollBarData {
+                                            min: 0.5,
+                                            max: 2.0,
+                                            value: control_scheme.weapon_switch_speed,
+                                            step: 0.1,
+                                            row: 6,
+                                            column: 1,
+                                            margin,
+                                            show_value: true,
+                                            orientation: Orientation::Horizontal,
+                                        },
+                                    );
+                                    weapon_switch_speed
+                                })
+                                .with_child(make_text_mark("Difficulty", 7, ctx))
+                                .with_child({
+                                    difficulty = make_difficulty_drop_down(
+                                        ctx,
+                                        7,
+                                        control_scheme.difficulty,
+                                    );
+                                    difficulty
+                                })
                                 .with_child({
                                     reset_control_scheme = ButtonBuilder::new(
                                         WidgetBuilder::new()
-                                            .on_row(2 + control_scheme.buttons().len())
+                                            .on_row(8 + control_scheme.buttons().len())
                                             .with_margin(margin),
                                     )
                                     .with_text("Reset")
@@ -507,6 +699,12 @@ impl OptionsMenu {
                         .add_column(Column::stretch())
                         .add_row(common_row)
                         .add_row(common_row)
+                        .add_row(common_row)
+                        .add_row(common_row)
+                        .add_row(common_row)
+                        .add_row(common_row)
+                        .add_row(common_row)
+                        .add_row(common_row)
                         .add_rows(
                             (0..control_scheme.buttons().len())
                                 .map(|_| common_row)
@@ -523,6 +721,7 @@ impl OptionsMenu {
             .with_tab(graphics_tab)
             .with_tab(sound_tab)
             .with_tab(controls_tab)
+            .with_tab(accessibility_tab)
             .build(ctx);
 
         let options_window: Handle<UiNode> = WindowBuilder::new(
@@ -541,6 +740,7 @@ impl OptionsMenu {
             window: options_window,
             sound_volume,
             music_volume,
+            sfx_volume,
             video_mode,
             spot_shadows,
             soft_spot_shadows,
@@ -553,6 +753,15 @@ impl OptionsMenu {
             active_control_button: None,
             mouse_sens,
             mouse_y_inverse,
+            weapon_sway,
+            field_of_view,
+            auto_pickup,
+            item_magnet,
+            weapon_switch_speed,
+            difficulty,
+            gore,
+            camera_shake_scale,
+            flashing,
             reset_control_scheme,
             use_hrtf,
             reset_audio_settings,
@@ -571,6 +780,7 @@ impl OptionsMenu {
         control_scheme: &ControlScheme,
         show_debug_info: bool,
         sound_config: &SoundConfig,
+        accessibility: &AccessibilityConfig,
     ) {
         let ui = &mut engine.user_interface;
         let settings = engine.renderer.get_quality_settings();
@@ -590,8 +800,12 @@ impl OptionsMenu {
         sync_check_box(self.ssao, settings.use_ssao);
         sync_check_box(self.fxaa, settings.fxaa);
         sync_check_box(self.mouse_y_inverse, control_scheme.mouse_y_inverse);
+        sync_check_box(self.auto_pickup, control_scheme.auto_pickup);
+        sync_check_box(self.item_magnet, control_scheme.item_magnet);
         sync_check_box(self.use_hrtf, sound_config.use_hrtf);
         sync_check_box(self.show_debug_info, show_debug_info);
+        sync_check_box(self.gore, accessibility.gore);
+        sync_check_box(self.flashing, accessibility.flashing);
 
         let sync_scroll_bar = |handle: Handle<UiNode>, value: f32| {
             ui.send_message(ScrollBarMessage::value(
@@ -603,8 +817,19 @@ impl OptionsMenu {
         sync_scroll_bar(self.point_shadow_distance, settings.point_shadows_distance);
         sync_scroll_bar(self.spot_shadow_distance, settings.spot_shadows_distance);
         sync_scroll_bar(self.mouse_sens, control_scheme.mouse_sens);
+        sync_scroll_bar(self.weapon_sway, control_scheme.weapon_sway);
+        sync_scroll_bar(self.field_of_view, control_scheme.field_of_view);
+        sync_scroll_bar(self.weapon_switch_speed, control_scheme.weapon_switch_speed);
         sync_scroll_bar(self.sound_volume, sound_config.master_volume);
         sync_scroll_bar(self.music_volume, sound_config.music_volume);
+        sync_scroll_bar(self.sfx_volume, sound_config.sfx_volume);
+        sync_scroll_bar(self.camera_shake_scale, accessibility.camera_shake_scale);
+
+        ui.send_message(DropdownListMessage::selection(
+            self.difficulty,
+            MessageDirection::ToWidget,
+            Some(control_scheme.difficulty.index()),
+        ));
 
         for (btn, def) in self
             .control_scheme_buttons
@@ -678,6 +903,9 @@ impl OptionsMenu {
                     control_scheme.buttons_mut()[active_control_button].button = control_button;
 
                     self.active_control_button = None;
+
+                    // Remapped keys must survive restart.
+                    self.sender.send(Message::SaveConfig);
                 }
             }
         }
@@ -691,6 +919,7 @@ impl OptionsMenu {
         control_scheme: &mut ControlScheme,
         show_debug_info: &mut bool,
         sound_config: &SoundConfig,
+        accessibility: &mut AccessibilityConfig,
     ) {
         let old_settings = context.renderer.get_quality_settings();
         let mut settings = old_settings;
@@ -711,9 +940,24 @@ impl OptionsMenu {
                 } else if message.destination() == self.mouse_sens {
                     control_scheme.mouse_sens = *new_value;
                     changed = true;
+                } else if message.destination() == self.weapon_sway {
+                    control_scheme.weapon_sway = *new_value;
+                    changed = true;
+                } else if message.destination() == self.field_of_view {
+                    control_scheme.field_of_view = *new_value;
+                    changed = true;
+                } else if message.destination() == self.weapon_switch_speed {
+                    control_scheme.weapon_switch_speed = *new_value;
+                    changed = true;
                 } else if message.destination() == self.music_volume {
                     self.sender.send(Message::SetMusicVolume(*new_value));
                     changed = true;
+                } else if message.destination() == self.sfx_volume {
+                    self.sender.send(Message::SetSfxVolume(*new_value));
+                    changed = true;
+                } else if message.destination() == self.camera_shake_scale {
+                    accessibility.camera_shake_scale = *new_value;
+                    changed = true;
                 }
             }
         } else if let Some(DropdownListMessage::SelectionChanged(Some(index))) = message.data() {
@@ -736,6 +980,12 @@ impl OptionsMenu {
                     settings.spot_shadow_map_precision = ShadowMapPrecision::Half;
                 }
                 changed = true;
+            } else if message.destination() == self.difficulty {
+                if let Some(difficulty) = Difficulty::ALL.get(*index) {
+                    control_scheme.difficulty = *difficulty;
+                    self.sender.send(Message::SetDifficulty(*difficulty));
+                    changed = true;
+                }
             } else if message.destination() == self.point_shadows_quality {
                 settings.point_shadow_map_size = index_to_shadow_map_size(*index);
                 if *index > 0 {
@@ -762,6 +1012,12 @@ impl OptionsMenu {
             } else if message.destination() == self.mouse_y_inverse {
                 control_scheme.mouse_y_inverse = value;
                 changed = true;
+            } else if message.destination() == self.auto_pickup {
+                control_scheme.auto_pickup = value;
+                changed = true;
+            } else if message.destination() == self.item_magnet {
+                control_scheme.item_magnet = value;
+                changed = true;
             } else if message.destination() == self.use_light_scatter {
                 settings.light_scatter_enabled = value;
                 changed = true;
@@ -777,15 +1033,45 @@ impl OptionsMenu {
             } else if message.destination() == self.show_debug_info {
                 changed = true;
                 *show_debug_info = value;
+            } else if message.destination() == self.gore {
+                accessibility.gore = value;
+                changed = true;
+            } else if message.destination() == self.flashing {
+                accessibility.flashing = value;
+                changed = true;
             }
         } else if let Some(ButtonMessage::Click) = message.data() {
             if message.destination() == self.reset_control_scheme {
                 control_scheme.reset();
-                self.sync_to_model(context, control_scheme, *show_debug_info, sound_config);
+                self.sync_to_model(
+                    context,
+                    control_scheme,
+                    *show_debug_info,
+                    sound_config,
+                    accessibility,
+                );
                 changed = true;
             } else if message.destination() == self.reset_audio_settings {
-                context.sound_engine.set_sound_gain(1.0);
-                self.sync_to_model(context, control_scheme, *show_debug_info, sound_config);
+                let default_config = SoundConfig::default();
+                let sound_config = &SoundConfig {
+                    master_volume: default_config.master_volume,
+                    sfx_volume: default_config.sfx_volume,
+                    music_volume: default_config.music_volume,
+                    ..sound_config.clone()
+                };
+                self.sender
+                    .send(Message::SetMasterVolume(sound_config.master_volume));
+                self.sender
+                    .send(Message::SetSfxVolume(sound_config.sfx_volume));
+                self.sender
+                    .send(Message::SetMusicVolume(sound_config.music_volume));
+                self.sync_to_model(
+                    context,
+                    control_scheme,
+                    *show_debug_info,
+                    sound_config,
+                    accessibility,
+                );
                 changed = true;
             }
 
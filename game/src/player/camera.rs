@@ -1,4 +1,4 @@
-use crate::Player;
+use crate::{control_scheme::ControlButton, current_level_mut, game_ref, Player};
 use fyrox::{
     core::{
         algebra::{Point3, UnitQuaternion, Vector3},
@@ -9,9 +9,14 @@ use fyrox::{
         uuid::{uuid, Uuid},
         visitor::prelude::*,
     },
+    event::{DeviceEvent, ElementState, Event, WindowEvent},
     impl_component_provider, rand,
     scene::{
-        graph::physics::{Intersection, RayCastOptions},
+        camera::{Camera, PerspectiveProjection, Projection},
+        graph::{
+            physics::{Intersection, RayCastOptions},
+            Graph,
+        },
         node::{Node, TypeUuidProvider},
         Scene,
     },
@@ -29,18 +34,175 @@ pub struct CameraController {
     shake_offset: Vector3<f32>,
     target_shake_offset: Vector3<f32>,
     shake_timer: f32,
+    #[visit(optional)]
+    shake_amplitude: f32,
     #[visit(skip)]
     #[reflect(hidden)]
     query_buffer: Vec<Intersection>,
+    // Saved, because the camera stays unlinked from the hinge in saved scene while flying.
+    #[visit(optional)]
+    #[reflect(hidden)]
+    free_fly: Option<FreeFly>,
+    // Progress of aim-down-sights zoom, 0.0 - no zoom, 1.0 - full zoom of current weapon.
+    #[visit(skip)]
+    #[reflect(hidden)]
+    aim_zoom_factor: f32,
+}
+
+/// State of the camera detached from the player, it flies freely around the level while the
+/// simulation continues.
+#[derive(Default, Debug, Clone, Visit)]
+struct FreeFly {
+    position: Vector3<f32>,
+    yaw: f32,
+    pitch: f32,
+    #[visit(skip)]
+    move_forward: bool,
+    #[visit(skip)]
+    move_backward: bool,
+    #[visit(skip)]
+    move_left: bool,
+    #[visit(skip)]
+    move_right: bool,
+    #[visit(skip)]
+    fast: bool,
+    // Local rotation of the camera before it was detached from the hinge.
+    original_rotation: UnitQuaternion<f32>,
+}
+
+impl FreeFly {
+    const SPEED: f32 = 4.0;
+    const FAST_SPEED_MULTIPLIER: f32 = 3.0;
+
+    fn rotation(&self) -> UnitQuaternion<f32> {
+        UnitQuaternion::from_axis_angle(&Vector3::y_axis(), self.yaw)
+            * UnitQuaternion::from_axis_angle(&Vector3::x_axis(), self.pitch)
+    }
+
+    fn update(&mut self, camera: &mut Node, dt: f32) {
+        let rotation = self.rotation();
+        let look = rotation * Vector3::z();
+        let side = rotation * Vector3::x();
+
+        let mut velocity = Vector3::default();
+        if self.move_forward {
+            velocity += look;
+        }
+        if self.move_backward {
+            velocity -= look;
+        }
+        if self.move_left {
+            velocity += side;
+        }
+        if self.move_right {
+            velocity -= side;
+        }
+
+        if let Some(direction) = velocity.try_normalize(f32::EPSILON) {
+            let speed = if self.fast {
+                Self::SPEED * Self::FAST_SPEED_MULTIPLIER
+            } else {
+                Self::SPEED
+            };
+            self.position += direction.scale(speed * dt);
+        }
+
+        // Camera is detached, so its local transform is its global transform.
+        camera
+            .local_transform_mut()
+            .set_position(self.position)
+            .set_rotation(rotation);
+    }
 }
 
 impl CameraController {
+    /// Used when the player's state machine has no transition to the aim pose.
+    const DEFAULT_AIM_TRANSITION_TIME: f32 = 0.2;
+
     pub fn camera(&self) -> Handle<Node> {
         self.camera
     }
 
+    fn update_field_of_view(
+        &mut self,
+        graph: &mut Graph,
+        base_fov: f32,
+        aim_zoom: f32,
+        aim_transition_time: f32,
+        is_aiming: bool,
+        dt: f32,
+    ) {
+        let target = if is_aiming { 1.0 } else { 0.0 };
+        let step = if aim_transition_time > 0.0 {
+            dt / aim_transition_time
+        } else {
+            1.0
+        };
+        if self.aim_zoom_factor < target {
+            self.aim_zoom_factor = (self.aim_zoom_factor + step).min(target);
+        } else {
+            self.aim_zoom_factor = (self.aim_zoom_factor - step).max(target);
+        }
+
+        let zoom = 1.0 + (aim_zoom - 1.0) * self.aim_zoom_factor;
+        let fov = 2.0 * ((base_fov.to_radians() * 0.5).tan() / zoom).atan();
+
+        if let Some(camera) = graph[self.camera].cast_mut::<Camera>() {
+            if let Projection::Perspective(perspective) = camera.projection().clone() {
+                camera.set_projection(Projection::Perspective(PerspectiveProjection {
+                    fov,
+                    ..perspective
+                }));
+            }
+        }
+    }
+
+    pub fn is_free_fly(&self) -> bool {
+        self.free_fly.is_some()
+    }
+
+    /// Detaches camera from the player (or attaches it back), so it can fly freely around the
+    /// level.
+    pub fn set_free_fly(&mut self, graph: &mut Graph, enabled: bool) {
+        if enabled == self.is_free_fly() || graph.try_get(self.camera).is_none() {
+            return;
+        }
+
+        if enabled {
+            let camera = &graph[self.camera];
+            let look = camera.look_vector();
+            self.free_fly = Some(FreeFly {
+                position: camera.global_position(),
+                yaw: look.x.atan2(look.z),
+                pitch: (-look.y).clamp(-1.0, 1.0).asin(),
+                original_rotation: **camera.local_transform().rotation(),
+                ..Default::default()
+            });
+            graph.unlink_node(self.camera);
+        } else if let Some(free_fly) = self.free_fly.take() {
+            graph.link_nodes(self.camera, self.camera_hinge);
+            graph[self.camera]
+                .local_transform_mut()
+                .set_rotation(free_fly.original_rotation);
+        }
+    }
+
+    const MAX_SHAKE_AMPLITUDE: f32 = 3.0;
+    /// Amount of amplitude lost per second by shakes stronger than weapon recoil.
+    const SHAKE_DECAY: f32 = 4.0;
+
     pub fn request_shake_camera(&mut self) {
-        self.shake_timer = 0.24;
+        self.shake(1.0, 0.24);
+    }
+
+    /// Shakes camera for given amount of time. Amplitude is relative, `1.0` is a recoil of a
+    /// weapon. Overlapping shakes do not sum up, the strongest one wins.
+    pub fn shake(&mut self, amplitude: f32, duration: f32) {
+        self.shake_amplitude = self
+            .shake_amplitude
+            .max(amplitude)
+            .min(Self::MAX_SHAKE_AMPLITUDE);
+        self.shake_timer = self.shake_timer.max(duration);
     }
 
     fn check_occlusion(&mut self, owner_collider: Handle<Node>, scene: &mut Scene) {
@@ -76,19 +238,25 @@ impl CameraController {
         }
     }
 
-    fn update_shake(&mut self, dt: f32) {
+    fn update_shake(&mut self, scale: f32, dt: f32) {
         let xy_range = -0.027..0.027;
         let z_range = 0.01..0.05;
-        if self.shake_timer > 0.0 {
+        if self.shake_timer > 0.0 && self.shake_amplitude > 0.0 && scale > 0.0 {
             self.shake_timer -= dt;
             let mut rnd = rand::thread_rng();
             self.target_shake_offset = Vector3::new(
                 rnd.gen_range(xy_range.clone()),
                 rnd.gen_range(xy_range),
                 rnd.gen_range(z_range),
-            );
+            )
+            .scale(self.shake_amplitude * scale);
+            // Strong shakes fade out smoothly, weak ones are just cut by the timer.
+            if self.shake_amplitude > 1.0 {
+                self.shake_amplitude = (self.shake_amplitude - Self::SHAKE_DECAY * dt).max(1.0);
+            }
         } else {
             self.shake_timer = 0.0;
+            self.shake_amplitude = 0.0;
             self.target_shake_offset = Vector3::new(0.0, 0.0, 0.0);
         }
         self.shake_offset.follow(&self.target_shake_offset, 0.5);
@@ -104,22 +272,99 @@ impl TypeUuidProvider for CameraController {
 }
 
 impl ScriptTrait for CameraController {
+    fn on_os_event(&mut self, event: &Event<()>, context: &mut ScriptContext) {
+        if !current_level_mut(context.plugins)
+            .map_or(true, |level| level.is_input_of(self.player, event))
+        {
+            return;
+        }
+
+        let control_scheme = game_ref(context.plugins).player_control_scheme(self.player);
+
+        match event {
+            Event::WindowEvent {
+                event: WindowEvent::KeyboardInput { input, .. },
+                ..
+            } => {
+                let button = match input.virtual_keycode {
+                    Some(key) => ControlButton::Key(key),
+                    None => return,
+                };
+                let pressed = input.state == ElementState::Pressed;
+
+                if button == control_scheme.free_fly_camera.button {
+                    if pressed {
+                        let enabled = !self.is_free_fly();
+                        self.set_free_fly(&mut context.scene.graph, enabled);
+                    }
+                } else if let Some(free_fly) = self.free_fly.as_mut() {
+                    if button == control_scheme.move_forward.button {
+                        free_fly.move_forward = pressed;
+                    } else if button == control_scheme.move_backward.button {
+                        free_fly.move_backward = pressed;
+                    } else if button == control_scheme.move_left.button {
+                        free_fly.move_left = pressed;
+                    } else if button == control_scheme.move_right.button {
+                        free_fly.move_right = pressed;
+                    } else if button == control_scheme.run.button {
+                        free_fly.fast = pressed;
+                    }
+                }
+            }
+            Event::DeviceEvent {
+                event: DeviceEvent::MouseMotion { delta },
+                ..
+            } => {
+                if let Some(free_fly) = self.free_fly.as_mut() {
+                    let mouse_sens = control_scheme.mouse_sens * context.dt;
+                    let pitch_direction = if control_scheme.mouse_y_inverse {
+                        -1.0
+                    } else {
+                        1.0
+                    };
+                    free_fly.yaw -= (delta.0 as f32) * mouse_sens;
+                    free_fly.pitch = (free_fly.pitch
+                        + pitch_direction * (delta.1 as f32) * mouse_sens)
+                        .clamp(-90.0f32.to_radians(), 90.0f32.to_radians());
+                }
+            }
+            _ => (),
+        }
+    }
+
     fn on_update(&mut self, context: &mut ScriptContext) {
-        let (is_walking, is_running, is_aiming, yaw, pitch) = context
-            .scene
-            .graph
-            .try_get(self.player)
-            .and_then(|p| p.try_get_script::<Player>())
-            .map(|p| {
-                (
-                    p.is_walking(),
-                    p.is_running(context.scene),
-                    p.is_aiming(),
-                    p.controller.yaw,
-                    p.controller.pitch,
-                )
-            })
-            .unwrap_or_default();
+        if let Some(free_fly) = self.free_fly.as_mut() {
+            free_fly.update(&mut context.scene.graph[self.camera], context.dt);
+            return;
+        }
+
+        let (is_walking, is_running, is_aiming, yaw, pitch, aim_zoom, aim_transition_time) =
+            context
+                .scene
+                .graph
+                .try_get(self.player)
+                .and_then(|p| p.try_get_script::<Player>())
+                .map(|p| {
+                    (
+                        p.is_walking(),
+                        p.is_running(context.scene),
+                        p.is_aiming(),
+                        p.controller.yaw,
+                        p.controller.pitch,
+                        p.aim_zoom(&context.scene.graph),
+                        p.aim_transition_time(&context.scene.graph)
+                            .unwrap_or(Self::DEFAULT_AIM_TRANSITION_TIME),
+                    )
+                })
+                .unwrap_or((
+                    false,
+                    false,
+                    false,
+                    0.0,
+                    0.0,
+                    1.0,
+                    Self::DEFAULT_AIM_TRANSITION_TIME,
+                ));
 
         if is_walking {
             let (kx, ky) = if is_running { (8.0, 13.0) } else { (5.0, 10.0) };
@@ -133,7 +378,18 @@ impl ScriptTrait for CameraController {
 
         self.target_camera_offset.z = if is_aiming { 0.2 } else { 0.8 };
 
-        self.update_shake(context.dt);
+        let game = game_ref(context.plugins);
+        let control_scheme = game.player_control_scheme(self.player);
+        let shake_scale = game.accessibility.camera_shake_scale;
+        self.update_field_of_view(
+            &mut context.scene.graph,
+            control_scheme.field_of_view,
+            aim_zoom,
+            aim_transition_time,
+            is_aiming,
+            context.dt,
+        );
+        self.update_shake(shake_scale, context.dt);
         self.check_occlusion(self.ignorable_collider, context.scene);
 
         self.target_camera_offset += self.shake_offset;
@@ -1,5 +1,8 @@
 use crate::{
-    character::{Character, CharacterCommand},
+    bot::is_line_of_sight_blocked,
+    character::{
+        try_get_character_mut, try_get_character_ref, Character, CharacterCommand, HitBox, Team,
+    },
     control_scheme::ControlButton,
     current_level_mut, current_level_ref,
     door::{door_mut, DoorContainer},
@@ -7,21 +10,25 @@ use crate::{
     game_mut, game_ref,
     gui::journal::Journal,
     inventory::Inventory,
-    level::item::ItemKind,
+    level::item::{ItemContainer, ItemKind},
     message::Message,
-    player::state_machine::{CombatWeaponKind, StateMachine, StateMachineInput},
+    player::{
+        scope_sway::ScopeSway,
+        state_machine::{CombatWeaponKind, StateMachine, StateMachineInput},
+    },
     sound::SoundManager,
     utils,
     weapon::{
         definition::WeaponKind,
-        projectile::{Projectile, ProjectileKind},
-        try_weapon_ref, weapon_mut, weapon_ref,
+        projectile::{Projectile, ProjectileKind, ProjectilePool},
+        try_weapon_mut, try_weapon_ref, weapon_mut, weapon_ref, Weapon,
     },
-    CameraController, Elevator, Game, Item,
+    CameraController, Elevator, Game, Item, MessageSender,
 };
 use fyrox::{
     core::{
-        algebra::{UnitQuaternion, Vector3},
+        algebra::{Point3, UnitQuaternion, Vector3},
+        arrayvec::ArrayVec,
         color::Color,
         color_gradient::{ColorGradient, ColorGradientBuilder, GradientPoint},
         math::{self, SmoothAngle, Vector3Ext},
@@ -39,7 +46,8 @@ use fyrox::{
     scene::{
         animation::absm::AnimationBlendingStateMachine,
         base::BaseBuilder,
-        graph::Graph,
+        collider::{Collider, InteractionGroups},
+        graph::{physics::RayCastOptions, Graph},
         light::BaseLight,
         node::{Node, TypeUuidProvider},
         sprite::SpriteBuilder,
@@ -48,9 +56,14 @@ use fyrox::{
     script::{ScriptContext, ScriptDeinitContext, ScriptTrait},
     utils::log::Log,
 };
-use std::ops::{Deref, DerefMut};
+use std::{
+    f32::consts::PI,
+    ops::{Deref, DerefMut},
+};
+use strum_macros::{AsRefStr, EnumString, EnumVariantNames};
 
 pub mod camera;
+pub mod scope_sway;
 mod state_machine;
 
 #[derive(Default, Debug)]
@@ -63,9 +76,13 @@ pub struct InputController {
     yaw: f32,
     pitch: f32,
     aim: bool,
+    hold_breath: bool,
     toss_grenade: bool,
+    melee: bool,
     shoot: bool,
     run: bool,
+    lean_left: bool,
+    lean_right: bool,
     action: bool,
     cursor_up: bool,
     cursor_down: bool,
@@ -91,6 +108,7 @@ pub enum RequiredWeapon {
     Next,
     Previous,
     Specific(WeaponKind),
+    Slot(u32),
 }
 
 impl RequiredWeapon {
@@ -105,10 +123,30 @@ impl Default for RequiredWeapon {
     }
 }
 
+/// Defines how health of the player changes over time.
+#[derive(
+    Copy, Clone, PartialEq, Eq, Debug, Visit, Reflect, AsRefStr, EnumString, EnumVariantNames,
+)]
+pub enum HealthMode {
+    /// Health changes only by damage and medkits.
+    Normal,
+    /// Health slowly restores after some time without taking damage.
+    Regeneration,
+    /// Health slowly decreases when it is below a threshold.
+    BleedOut,
+}
+
+impl Default for HealthMode {
+    fn default() -> Self {
+        Self::Normal
+    }
+}
+
 #[derive(Clone)]
 pub struct PlayerPersistentData {
     pub inventory: Inventory,
     pub health: f32,
+    pub armor: f32,
     pub current_weapon: u32,
     pub weapons: Vec<WeaponKind>,
 }
@@ -131,6 +169,10 @@ pub struct Player {
     weapon_origin: Handle<Node>,
     run_factor: f32,
     target_run_factor: f32,
+    #[visit(optional)]
+    stamina: f32,
+    #[visit(optional)]
+    exhausted: bool,
     in_air_time: f32,
     velocity: Vector3<f32>, // Horizontal velocity, Y is ignored.
     target_velocity: Vector3<f32>,
@@ -148,6 +190,115 @@ pub struct Player {
     #[visit(optional)]
     animation_player: Handle<Node>,
 
+    #[visit(optional)]
+    left_arm: Handle<Node>,
+
+    #[visit(optional)]
+    left_fore_arm: Handle<Node>,
+
+    #[visit(optional)]
+    left_hand: Handle<Node>,
+
+    #[reflect(hidden)]
+    #[visit(skip)]
+    left_hand_ik_weight: f32,
+
+    /// Current lean amount in [-1; 1] range, negative values lean to the left.
+    #[reflect(hidden)]
+    #[visit(skip)]
+    lean: f32,
+
+    /// Time left until next melee attack is allowed.
+    #[reflect(hidden)]
+    #[visit(skip)]
+    melee_cooldown: f32,
+
+    /// Amount of respawns left, when it reaches zero, death of the player ends the match.
+    #[visit(optional)]
+    lives: u32,
+
+    #[reflect(hidden)]
+    #[visit(optional)]
+    spawn_position: Option<Vector3<f32>>,
+
+    #[visit(optional)]
+    health_mode: HealthMode,
+
+    #[visit(optional)]
+    #[reflect(
+        description = "Amount of health restored per second in Regeneration mode.",
+        min_value = 0.0
+    )]
+    regeneration_rate: f32,
+
+    #[visit(optional)]
+    #[reflect(
+        description = "Time (in seconds) without damage before regeneration starts.",
+        min_value = 0.0
+    )]
+    regeneration_delay: f32,
+
+    #[visit(optional)]
+    #[reflect(hidden)]
+    time_since_damage: f32,
+
+    #[reflect(hidden)]
+    #[visit(skip)]
+    respawn_timer: f32,
+
+    /// Time the fuse of a grenade in hand has been burning, `None` if there's no grenade in hand.
+    #[reflect(hidden)]
+    #[visit(skip)]
+    grenade_cook_time: Option<f32>,
+
+    /// Flash light state is kept by the player, so it won't be lost on weapon change.
+    #[visit(optional)]
+    flash_light_enabled: bool,
+
+    #[visit(optional)]
+    #[reflect(hidden)]
+    flash_light_battery: f32,
+
+    #[visit(optional)]
+    #[reflect(
+        description = "Time (in seconds) during which respawned player can't be damaged by others.",
+        min_value = 0.0
+    )]
+    respawn_protection: f32,
+
+    /// Yaw of the legs while turning in place, the upper body follows the camera meanwhile.
+    #[reflect(hidden)]
+    #[visit(skip)]
+    legs_yaw: f32,
+
+    #[reflect(hidden)]
+    #[visit(skip)]
+    turning_in_place: bool,
+
+    /// Debug mode, the player flies through level geometry. It is not stored in saves.
+    #[reflect(hidden)]
+    #[visit(skip)]
+    no_clip: bool,
+
+    /// Speed multiplier of weapon switch animations, synced with the control scheme.
+    #[reflect(hidden)]
+    #[visit(skip)]
+    weapon_switch_speed: f32,
+
+    /// Time left to press a weapon switch button again to speed up the current switch.
+    #[reflect(hidden)]
+    #[visit(skip)]
+    quick_swap_timer: f32,
+
+    #[reflect(hidden)]
+    #[visit(skip)]
+    scope_sway: ScopeSway,
+
+    /// Whether the aim button was just pressed and the aim snap must be performed.
+    #[reflect(hidden)]
+    #[visit(skip)]
+    aim_snap_requested: bool,
+
     #[reflect(hidden)]
     item_display: Handle<Node>,
 
@@ -169,7 +320,10 @@ pub struct Player {
 impl Default for Player {
     fn default() -> Self {
         Self {
-            character: Default::default(),
+            character: Character {
+                team: Team::Players,
+                ..Default::default()
+            },
             rig_light: Default::default(),
             camera_controller: Default::default(),
             inventory_display: Default::default(),
@@ -205,6 +359,8 @@ impl Default for Player {
             velocity: Default::default(),
             run_factor: Default::default(),
             target_run_factor: Default::default(),
+            stamina: Player::MAX_STAMINA,
+            exhausted: false,
             target_velocity: Default::default(),
             weapon_display: Default::default(),
             last_health: 100.0,
@@ -225,6 +381,30 @@ impl Default for Player {
             model_pivot: Default::default(),
             model_sub_pivot: Default::default(),
             animation_player: Default::default(),
+            left_arm: Default::default(),
+            left_fore_arm: Default::default(),
+            left_hand: Default::default(),
+            left_hand_ik_weight: 0.0,
+            lean: 0.0,
+            melee_cooldown: 0.0,
+            lives: Player::DEFAULT_LIVES,
+            spawn_position: None,
+            respawn_timer: 0.0,
+            grenade_cook_time: None,
+            health_mode: Default::default(),
+            regeneration_rate: 2.0,
+            regeneration_delay: 5.0,
+            time_since_damage: 0.0,
+            flash_light_enabled: false,
+            flash_light_battery: Player::MAX_FLASH_LIGHT_BATTERY,
+            respawn_protection: 3.0,
+            legs_yaw: 0.0,
+            turning_in_place: false,
+            no_clip: false,
+            weapon_switch_speed: 1.0,
+            quick_swap_timer: 0.0,
+            scope_sway: Default::default(),
+            aim_snap_requested: false,
             machine: Default::default(),
             state_machine: Default::default(),
         }
@@ -249,6 +429,8 @@ impl Clone for Player {
             weapon_origin: self.weapon_origin,
             run_factor: self.run_factor,
             target_run_factor: self.target_run_factor,
+            stamina: self.stamina,
+            exhausted: self.exhausted,
             in_air_time: self.in_air_time,
             velocity: self.velocity,
             target_velocity: self.target_velocity,
@@ -266,6 +448,30 @@ impl Clone for Player {
             journal: Default::default(),
             controller: Default::default(),
             animation_player: self.animation_player,
+            left_arm: self.left_arm,
+            left_fore_arm: self.left_fore_arm,
+            left_hand: self.left_hand,
+            left_hand_ik_weight: self.left_hand_ik_weight,
+            lean: self.lean,
+            melee_cooldown: self.melee_cooldown,
+            lives: self.lives,
+            spawn_position: self.spawn_position,
+            respawn_timer: self.respawn_timer,
+            grenade_cook_time: self.grenade_cook_time,
+            health_mode: self.health_mode,
+            regeneration_rate: self.regeneration_rate,
+            regeneration_delay: self.regeneration_delay,
+            time_since_damage: self.time_since_damage,
+            flash_light_enabled: self.flash_light_enabled,
+            flash_light_battery: self.flash_light_battery,
+            respawn_protection: self.respawn_protection,
+            legs_yaw: self.legs_yaw,
+            turning_in_place: self.turning_in_place,
+            no_clip: self.no_clip,
+            weapon_switch_speed: self.weapon_switch_speed,
+            quick_swap_timer: self.quick_swap_timer,
+            scope_sway: self.scope_sway.clone(),
+            aim_snap_requested: self.aim_snap_requested,
             machine: self.machine,
             state_machine: self.state_machine.clone(),
         }
@@ -280,6 +486,60 @@ fn make_color_gradient() -> ColorGradient {
 }
 
 impl Player {
+    pub const MAX_STAMINA: f32 = 100.0;
+    /// Stamina units per second spent while sprinting.
+    const STAMINA_DRAIN_RATE: f32 = 20.0;
+    /// Stamina units per second restored while not sprinting.
+    const STAMINA_REGEN_RATE: f32 = 12.5;
+    /// Once exhausted, the player cannot sprint again until stamina goes above this value.
+    const STAMINA_RECOVERY_THRESHOLD: f32 = 30.0;
+    /// Stamina units per second spent while holding breath to steady a scoped weapon.
+    const BREATH_HOLD_DRAIN_RATE: f32 = 25.0;
+    /// Spine roll at full lean.
+    const MAX_LEAN_ANGLE: f32 = 20.0;
+    /// Max twist (in degrees) of the upper body relative to the legs, the legs step to catch up
+    /// with the camera when it is exceeded.
+    const TURN_IN_PLACE_THRESHOLD: f32 = 60.0;
+    /// Angular speed (in rad/s) of the legs while turning in place.
+    const TURN_IN_PLACE_SPEED: f32 = 5.0;
+    /// Speed of put back and grab animations at normal weapon switch speed.
+    const WEAPON_SWITCH_ANIMATION_SPEED: f32 = 3.0;
+    /// Weapon switch is this times faster when a switch button is double-tapped.
+    const QUICK_SWAP_SPEED_SCALE: f32 = 2.0;
+    /// Max time (in seconds) between taps of a quick swap.
+    const QUICK_SWAP_WINDOW: f32 = 0.3;
+    /// Vertical speed (in m/s) of the player in no-clip mode.
+    const NO_CLIP_VERTICAL_SPEED: f32 = 4.0;
+    /// How far to the side the space must be free to allow full lean.
+    const LEAN_CLEARANCE: f32 = 0.6;
+    pub const DEFAULT_LIVES: u32 = 3;
+    /// Time (in seconds) between death animation end and respawn.
+    const RESPAWN_TIME: f32 = 4.0;
+    /// Regeneration does not restore health above this value.
+    const MAX_REGENERATED_HEALTH: f32 = 100.0;
+    const BLEED_OUT_THRESHOLD: f32 = 25.0;
+    /// Amount of health lost per second while bleeding out.
+    const BLEED_OUT_RATE: f32 = 0.5;
+    /// Max distance from camera to an object the player can interact with.
+    const INTERACTION_DISTANCE: f32 = 2.5;
+    /// How close the object must be to the point the player is looking at.
+    const INTERACTION_RADIUS: f32 = 0.5;
+    const MELEE_RANGE: f32 = 1.3;
+    const MELEE_DAMAGE: f32 = 20.0;
+    /// Used when there is no melee animation to take duration from.
+    const MELEE_COOLDOWN: f32 = 0.8;
+    /// Amount of received damage that shakes camera as much as weapon recoil does.
+    const DAMAGE_PER_SHAKE_AMPLITUDE: f32 = 20.0;
+    const GRENADE_TOSS_SPEED: f32 = 15.0;
+    /// Time (in seconds) before the release point of the toss animation at which the animation
+    /// waits for the toss button to be released.
+    const GRENADE_HOLD_MARGIN: f32 = 0.05;
+    pub const MAX_FLASH_LIGHT_BATTERY: f32 = 100.0;
+    /// Battery charge per second spent while flash light is on.
+    const FLASH_LIGHT_DRAIN_RATE: f32 = 1.0;
+    /// Battery charge per second restored while flash light is off.
+    const FLASH_LIGHT_RECHARGE_RATE: f32 = 5.0;
+
     pub async fn add_to_scene(
         scene: &mut Scene,
         resource_manager: ResourceManager,
@@ -299,6 +559,7 @@ impl Player {
         PlayerPersistentData {
             inventory: self.inventory.clone(),
             health: self.health,
+            armor: self.armor,
             current_weapon: self.current_weapon,
             weapons: self
                 .weapons
@@ -319,22 +580,33 @@ impl Player {
         resource_manager: &ResourceManager,
     ) {
         let sender = &game.message_sender;
-        let items = &game.level.as_ref().unwrap().items;
+        let auto_pickup = game.control_scheme.auto_pickup;
+        let level = game.level.as_ref().unwrap();
+        let pickup_radius = level.pickup_radius;
+        let items = &level.items;
         for &item_handle in items.iter() {
             if let Some(item_node) = scene.graph.try_get(item_handle) {
                 let item = item_node.try_get_script::<Item>().unwrap();
+                if !item.is_available() {
+                    continue;
+                }
                 let self_position = scene.graph[self.body].global_position();
                 let item_position = item_node.global_position();
 
                 let distance = (item_position - self_position).norm();
-                if distance < 0.75 {
+                if distance < pickup_radius {
                     game.item_display.sync_to_model(
                         resource_manager.clone(),
                         item.get_kind(),
                         item.stack_size,
                     );
 
-                    if self.controller.action {
+                    // Weapons always require confirmation, so the player won't switch to a
+                    // picked weapon by accident.
+                    let picked_automatically =
+                        auto_pickup && item.get_kind().associated_weapon().is_none();
+
+                    if self.controller.action || picked_automatically {
                         self.push_command(CharacterCommand::PickupItem(item_handle));
                         sender.send(Message::SyncInventory);
 
@@ -353,6 +625,76 @@ impl Player {
         }
     }
 
+    /// Casts a ray in the view direction and tries to interact with an item or a door near the
+    /// point the player is looking at. Returns `true` if there was an interaction.
+    fn interact(
+        &mut self,
+        scene: &mut Scene,
+        items: &ItemContainer,
+        door_container: &DoorContainer,
+        sender: &MessageSender,
+    ) -> bool {
+        let camera = match scene
+            .graph
+            .try_get(self.camera_controller)
+            .and_then(|c| c.try_get_script::<CameraController>())
+        {
+            Some(camera_controller) => camera_controller.camera(),
+            None => return false,
+        };
+
+        let origin = scene.graph[camera].global_position();
+        let direction = scene.graph[camera]
+            .look_vector()
+            .try_normalize(f32::EPSILON)
+            .unwrap_or_else(Vector3::z)
+            .scale(Self::INTERACTION_DISTANCE);
+
+        let mut query_buffer = ArrayVec::<_, 16>::new();
+        scene.graph.physics.cast_ray(
+            RayCastOptions {
+                ray_origin: Point3::from(origin),
+                ray_direction: direction,
+                groups: InteractionGroups::default(),
+                max_len: Self::INTERACTION_DISTANCE,
+                sort_results: true,
+            },
+            &mut query_buffer,
+        );
+
+        let point = query_buffer
+            .iter()
+            .find(|hit| hit.collider != self.capsule_collider)
+            .map_or(origin + direction, |hit| hit.position.coords);
+
+        for &item_handle in items.iter() {
+            if let Some(item_node) = scene.graph.try_get(item_handle) {
+                let available = item_node
+                    .try_get_script::<Item>()
+                    .map_or(false, |item| item.is_available());
+                if available
+                    && item_node.global_position().metric_distance(&point)
+                        < Self::INTERACTION_RADIUS
+                {
+                    self.push_command(CharacterCommand::PickupItem(item_handle));
+                    sender.send(Message::SyncInventory);
+                    return true;
+                }
+            }
+        }
+
+        let has_key = self.inventory.has_key();
+        for &door_handle in &door_container.doors {
+            let door = door_mut(door_handle, &mut scene.graph);
+            if door.initial_position().metric_distance(&point) < Self::INTERACTION_RADIUS * 2.0 {
+                door.try_open(has_key);
+                return true;
+            }
+        }
+
+        false
+    }
+
     fn check_doors(&mut self, scene: &mut Scene, door_container: &DoorContainer) {
         let self_position = self.position(&scene.graph);
 
@@ -435,7 +777,7 @@ impl Player {
         }
     }
 
-    fn handle_jump_signal(&self, scene: &mut Scene, dt: f32) -> Option<f32> {
+    fn handle_jump_signal(&self, scene: &mut Scene, jump_scale: f32, dt: f32) -> Option<f32> {
         let mut new_y_vel = None;
         let animations_container =
             utils::fetch_animation_container_mut(&mut scene.graph, self.animation_player);
@@ -450,7 +792,7 @@ impl Player {
                         || active_transition == self.state_machine.walk_to_jump
                         || layer.active_state() == self.state_machine.jump_state)
                 {
-                    new_y_vel = Some(3.0 * dt);
+                    new_y_vel = Some(3.0 * jump_scale * dt);
                 }
             }
         }
@@ -472,6 +814,9 @@ impl Player {
                     RequiredWeapon::Specific(kind) => {
                         self.push_command(CharacterCommand::SelectWeapon(kind));
                     }
+                    RequiredWeapon::Slot(index) => {
+                        self.set_current_weapon(index as usize, &mut scene.graph)
+                    }
                 }
 
                 self.weapon_change_direction = RequiredWeapon::None;
@@ -494,11 +839,49 @@ impl Player {
         }
     }
 
+    fn spawn_grenade(
+        &mut self,
+        self_handle: Handle<Node>,
+        scene: &mut Scene,
+        resource_manager: &ResourceManager,
+        projectile_pool: &ProjectilePool,
+        speed: f32,
+        cook_time: f32,
+    ) {
+        if self.inventory.try_extract_exact_items(ItemKind::Grenade, 1) != 1 {
+            return;
+        }
+
+        let position = scene.graph[self.weapon_pivot].global_position();
+
+        let direction = scene
+            .graph
+            .try_get(self.camera_controller)
+            .and_then(|c| c.try_get_script::<CameraController>())
+            .map(|c| scene.graph[c.camera()].look_vector())
+            .unwrap_or_default();
+
+        let grenade = Projectile::add_to_scene(
+            ProjectileKind::Grenade,
+            resource_manager,
+            scene,
+            projectile_pool,
+            direction,
+            position,
+            self_handle,
+            direction.scale(speed),
+        );
+        if let Some(grenade) = scene.graph[grenade].try_get_script_mut::<Projectile>() {
+            grenade.cook(cook_time);
+        }
+    }
+
     fn handle_toss_grenade_signal(
         &mut self,
         self_handle: Handle<Node>,
         scene: &mut Scene,
         resource_manager: &ResourceManager,
+        projectile_pool: &ProjectilePool,
     ) {
         let animations_container =
             utils::fetch_animation_container_mut(&mut scene.graph, self.animation_player);
@@ -507,28 +890,166 @@ impl Player {
             .take_events();
         while let Some(event) = events.pop_front() {
             if event.name == StateMachine::TOSS_GRENADE_SIGNAL {
-                let position = scene.graph[self.weapon_pivot].global_position();
+                let cook_time = match self.grenade_cook_time.take() {
+                    Some(cook_time) => cook_time,
+                    // Grenade has already exploded in hand.
+                    None => continue,
+                };
 
-                let direction = scene
-                    .graph
-                    .try_get(self.camera_controller)
-                    .and_then(|c| c.try_get_script::<CameraController>())
-                    .map(|c| scene.graph[c.camera()].look_vector())
-                    .unwrap_or_default();
+                self.spawn_grenade(
+                    self_handle,
+                    scene,
+                    resource_manager,
+                    projectile_pool,
+                    Self::GRENADE_TOSS_SPEED,
+                    cook_time,
+                );
+            }
+        }
+    }
 
-                if self.inventory.try_extract_exact_items(ItemKind::Grenade, 1) == 1 {
-                    Projectile::add_to_scene(
-                        ProjectileKind::Grenade,
-                        resource_manager,
-                        scene,
-                        direction,
-                        position,
-                        self_handle,
-                        direction.scale(15.0),
-                    );
+    /// Burns the fuse of a grenade in hand while toss button is held. The toss animation is
+    /// held right before the release point meanwhile, so the grenade leaves the hand only when
+    /// the button is released. The grenade explodes in hand if it is held for too long.
+    fn update_grenade_cooking(
+        &mut self,
+        self_handle: Handle<Node>,
+        scene: &mut Scene,
+        resource_manager: &ResourceManager,
+        projectile_pool: &ProjectilePool,
+        dt: f32,
+    ) {
+        let is_holding = self.grenade_cook_time.is_some() && self.controller.toss_grenade;
+
+        let animation =
+            utils::fetch_animation_container_mut(&mut scene.graph, self.animation_player)
+                .get_mut(self.state_machine.toss_grenade_animation);
+        if is_holding {
+            let hold_time = animation
+                .signals()
+                .iter()
+                .find(|s| s.name == StateMachine::TOSS_GRENADE_SIGNAL)
+                .map(|s| (s.time - Self::GRENADE_HOLD_MARGIN).max(0.0));
+            if let Some(hold_time) = hold_time {
+                if animation.time_position() >= hold_time {
+                    animation.set_time_position(hold_time).set_speed(0.0);
+                }
+            }
+        } else {
+            animation.set_speed(1.0);
+        }
+
+        let cook_time = match self.grenade_cook_time.as_mut() {
+            Some(cook_time) if is_holding => cook_time,
+            _ => return,
+        };
+
+        *cook_time += dt;
+
+        let definition = Projectile::get_definition(ProjectileKind::Grenade);
+        if *cook_time >= definition.lifetime() {
+            self.grenade_cook_time = None;
+            self.controller.toss_grenade = false;
+
+            // A grenade with burnt fuse detonates right in hand on its first update.
+            self.spawn_grenade(
+                self_handle,
+                scene,
+                resource_manager,
+                projectile_pool,
+                0.0,
+                definition.lifetime(),
+            );
+        }
+    }
+
+    /// Returns remaining fuse time of a grenade in hand, if any.
+    pub fn grenade_fuse_left(&self) -> Option<f32> {
+        self.grenade_cook_time.map(|cook_time| {
+            (Projectile::get_definition(ProjectileKind::Grenade).lifetime() - cook_time).max(0.0)
+        })
+    }
+
+    fn try_start_melee(&mut self, graph: &mut Graph) {
+        if self.melee_cooldown > 0.0 || self.is_dead() {
+            return;
+        }
+
+        self.controller.melee = true;
+
+        if !self.state_machine.has_melee() {
+            self.melee_cooldown = Self::MELEE_COOLDOWN;
+            return;
+        }
+
+        let animations_container =
+            utils::fetch_animation_container_mut(graph, self.animation_player);
+        // Swing cannot be restarted until its animation has finished.
+        self.melee_cooldown =
+            match animations_container.try_get_mut(self.state_machine.melee_animation) {
+                Some(animation) => {
+                    animation.set_enabled(true).rewind();
+                    animation.length()
+                }
+                None => Self::MELEE_COOLDOWN,
+            };
+    }
+
+    fn melee_hit(&self, self_handle: Handle<Node>, scene: &mut Scene, actors: &[Handle<Node>]) {
+        let begin = scene.graph[self.weapon_pivot].global_position();
+        let direction = scene.graph[self.model_pivot]
+            .look_vector()
+            .try_normalize(f32::EPSILON)
+            .unwrap_or_else(Vector3::z);
+        let end = begin + direction.scale(Self::MELEE_RANGE);
+
+        if let Some(hit) = Weapon::ray_hit(
+            begin,
+            end,
+            self_handle,
+            actors,
+            &mut scene.graph,
+            self.capsule_collider,
+        ) {
+            if let Some(character) = try_get_character_mut(hit.actor, &mut scene.graph) {
+                character.push_command(CharacterCommand::Damage {
+                    who: self_handle,
+                    hitbox: hit.hit_box,
+                    amount: Self::MELEE_DAMAGE * HitBox::damage_factor_of(hit.hit_box),
+                    critical_shot_probability: 0.0,
+                });
+            }
+        }
+    }
+
+    fn handle_melee_hit_signal(
+        &mut self,
+        self_handle: Handle<Node>,
+        scene: &mut Scene,
+        actors: &[Handle<Node>],
+        dt: f32,
+    ) {
+        self.melee_cooldown = (self.melee_cooldown - dt).max(0.0);
+
+        if self.state_machine.has_melee() {
+            let animations_container =
+                utils::fetch_animation_container_mut(&mut scene.graph, self.animation_player);
+            if let Some(animation) =
+                animations_container.try_get_mut(self.state_machine.melee_animation)
+            {
+                let mut events = animation.take_events();
+                while let Some(event) = events.pop_front() {
+                    if event.name == StateMachine::MELEE_HIT_SIGNAL {
+                        self.melee_hit(self_handle, scene, actors);
+                    }
                 }
             }
+        } else if self.controller.melee {
+            // Melee state won't be entered - hit immediately.
+            self.melee_hit(self_handle, scene, actors);
         }
+
+        self.controller.melee = false;
     }
 
     fn update_velocity(&mut self, scene: &Scene, can_move: bool, dt: f32) {
@@ -577,17 +1098,236 @@ impl Player {
         self.velocity.follow(&self.target_velocity, 0.15);
     }
 
-    fn current_weapon_kind(&self, graph: &Graph) -> CombatWeaponKind {
-        if self.current_weapon().is_some() {
-            match weapon_ref(self.current_weapon(), graph).kind() {
-                WeaponKind::M4
-                | WeaponKind::Ak47
-                | WeaponKind::PlasmaRifle
-                | WeaponKind::RailGun => CombatWeaponKind::Rifle,
-                WeaponKind::Glock => CombatWeaponKind::Pistol,
+    /// Returns fraction of the lean to the given side that is not blocked by obstacles.
+    fn lean_clearance(&self, graph: &Graph, side: f32) -> f32 {
+        let origin = graph[self.spine].global_position();
+        // Side vector points to the left.
+        let direction = graph[self.model_pivot]
+            .side_vector()
+            .try_normalize(f32::EPSILON)
+            .unwrap_or_else(Vector3::x)
+            .scale(-side.signum() * Self::LEAN_CLEARANCE);
+
+        let mut query_buffer = ArrayVec::<_, 16>::new();
+        graph.physics.cast_ray(
+            RayCastOptions {
+                ray_origin: Point3::from(origin),
+                ray_direction: direction,
+                groups: InteractionGroups::default(),
+                max_len: Self::LEAN_CLEARANCE,
+                sort_results: true,
+            },
+            &mut query_buffer,
+        );
+
+        query_buffer
+            .iter()
+            .find(|hit| hit.collider != self.capsule_collider)
+            .map_or(1.0, |hit| (hit.toi / Self::LEAN_CLEARANCE).clamp(0.0, 1.0))
+    }
+
+    fn update_lean(&mut self, graph: &Graph, dt: f32) {
+        let mut target_lean = 0.0;
+        if self.controller.aim {
+            if self.controller.lean_left {
+                target_lean -= 1.0;
+            }
+            if self.controller.lean_right {
+                target_lean += 1.0;
             }
+        }
+        if target_lean != 0.0 {
+            target_lean *= self.lean_clearance(graph, target_lean);
+        }
+
+        self.lean += (target_lean - self.lean) * (6.0 * dt).min(1.0);
+    }
+
+    /// Keeps off-hand on a grip of current weapon while aiming. IK is blended in smoothly to
+    /// prevent snapping during transitions.
+    fn update_left_hand_ik(&mut self, scene: &mut Scene, dt: f32) {
+        let grip = try_weapon_ref(self.current_weapon(), &scene.graph)
+            .map_or(Handle::NONE, |w| w.left_hand_grip());
+
+        let target_weight =
+            if self.controller.aim && grip.is_some() && self.weapon_change_direction.is_none() {
+                1.0
+            } else {
+                0.0
+            };
+        self.left_hand_ik_weight +=
+            (target_weight - self.left_hand_ik_weight) * (8.0 * dt).min(1.0);
+
+        if grip.is_some() {
+            let target = scene.graph[grip].global_position();
+            utils::solve_two_bone_ik(
+                &mut scene.graph,
+                self.left_arm,
+                self.left_fore_arm,
+                self.left_hand,
+                target,
+                self.left_hand_ik_weight,
+            );
+        }
+    }
+
+    /// Returns kind of currently selected weapon (if any).
+    pub fn current_weapon_kind(&self, graph: &Graph) -> Option<WeaponKind> {
+        try_weapon_ref(self.current_weapon(), graph).map(|w| w.kind())
+    }
+
+    fn can_change_weapon(&self, graph: &Graph) -> bool {
+        let animations_container =
+            utils::fetch_animation_container_ref(graph, self.animation_player);
+
+        self.weapon_change_direction.is_none()
+            && animations_container[self.state_machine.grab_animation].has_ended()
+            && self.weapons.len() > 1
+    }
+
+    /// Drops current weapon as a pickup, its ammo is dropped too unless another weapon of the
+    /// player uses the same ammo. The only weapon is dropped only if `allow_last` is set.
+    /// Returns `true` if the weapon was dropped.
+    pub fn drop_weapon(&mut self, graph: &Graph, allow_last: bool) -> bool {
+        let weapon = self.current_weapon();
+        let kind = match try_weapon_ref(weapon, graph) {
+            Some(weapon) => weapon.kind(),
+            None => return false,
+        };
+
+        if !self.weapon_change_direction.is_none() || (self.weapons.len() <= 1 && !allow_last) {
+            return false;
+        }
+
+        self.push_command(CharacterCommand::DropItems {
+            item: kind.associated_item(),
+            count: 1,
+        });
+
+        let ammo_item = Weapon::definition(kind).ammo_item;
+        let ammo_shared = self
+            .weapons
+            .iter()
+            .filter(|&&other| other != weapon)
+            .any(|&other| {
+                Weapon::definition(weapon_ref(other, graph).kind()).ammo_item == ammo_item
+            });
+        let ammo = self.inventory.item_count(ammo_item);
+        if !ammo_shared && ammo > 0 {
+            self.push_command(CharacterCommand::DropItems {
+                item: ammo_item,
+                count: ammo,
+            });
+        }
+
+        true
+    }
+
+    /// Starts weapon change sequence - current weapon will be put back and then the required
+    /// weapon will be grabbed (see [`Self::handle_weapon_grab_signal`]).
+    fn request_weapon_change(&mut self, required: RequiredWeapon, graph: &mut Graph) {
+        self.weapon_change_direction = required;
+
+        let animations_container =
+            utils::fetch_animation_container_mut(graph, self.animation_player);
+
+        animations_container
+            .get_mut(self.state_machine.put_back_animation)
+            .rewind();
+
+        animations_container
+            .get_mut(self.state_machine.grab_animation)
+            .set_enabled(false)
+            .rewind();
+
+        self.quick_swap_timer = Self::QUICK_SWAP_WINDOW;
+        self.set_weapon_switch_animation_speed(graph, 1.0);
+    }
+
+    /// Sets speed of put back and grab animations. Grab and put back signals are bound to the
+    /// timelines of the animations, so they're scaled together with the speed and the weapon
+    /// model is still swapped at the right moment.
+    fn set_weapon_switch_animation_speed(&self, graph: &mut Graph, scale: f32) {
+        let speed = Self::WEAPON_SWITCH_ANIMATION_SPEED * self.weapon_switch_speed * scale;
+
+        let animations_container =
+            utils::fetch_animation_container_mut(graph, self.animation_player);
+
+        for animation in [
+            self.state_machine.put_back_animation,
+            self.state_machine.grab_animation,
+        ] {
+            animations_container.get_mut(animation).set_speed(speed);
+        }
+    }
+
+    /// Selects a weapon in the given slot (with put back/grab animations). Returns `false` if
+    /// the weapon cannot be changed right now or there is no such slot.
+    pub fn select_weapon_slot(&mut self, index: usize, graph: &mut Graph) -> bool {
+        if index < self.weapons.len()
+            && index != self.current_weapon as usize
+            && self.can_change_weapon(graph)
+        {
+            self.request_weapon_change(RequiredWeapon::Slot(index as u32), graph);
+            true
+        } else {
+            false
+        }
+    }
+
+    /// Switches to the next weapon (with put back/grab animations).
+    pub fn select_next_weapon(&mut self, graph: &mut Graph) -> bool {
+        if self.current_weapon < self.weapons.len().saturating_sub(1) as u32
+            && self.can_change_weapon(graph)
+        {
+            self.request_weapon_change(RequiredWeapon::Next, graph);
+            true
         } else {
-            CombatWeaponKind::Rifle
+            false
+        }
+    }
+
+    /// Switches to the previous weapon (with put back/grab animations).
+    pub fn select_prev_weapon(&mut self, graph: &mut Graph) -> bool {
+        if self.current_weapon > 0 && self.can_change_weapon(graph) {
+            self.request_weapon_change(RequiredWeapon::Previous, graph);
+            true
+        } else {
+            false
+        }
+    }
+
+    fn combat_weapon_kind(&self, graph: &Graph) -> CombatWeaponKind {
+        self.current_weapon_kind(graph)
+            .map_or(CombatWeaponKind::Rifle, CombatWeaponKind::from)
+    }
+
+    pub fn health_mode(&self) -> HealthMode {
+        self.health_mode
+    }
+
+    pub fn set_health_mode(&mut self, health_mode: HealthMode) {
+        self.health_mode = health_mode;
+    }
+
+    fn update_health_mode(&mut self, dt: f32) {
+        self.time_since_damage += dt;
+
+        match self.health_mode {
+            HealthMode::Normal => (),
+            HealthMode::Regeneration => {
+                if self.time_since_damage >= self.regeneration_delay
+                    && self.health < Self::MAX_REGENERATED_HEALTH
+                {
+                    self.health = (self.health + self.regeneration_rate * dt)
+                        .min(Self::MAX_REGENERATED_HEALTH);
+                }
+            }
+            HealthMode::BleedOut => {
+                if self.health < Self::BLEED_OUT_THRESHOLD {
+                    self.health -= Self::BLEED_OUT_RATE * dt;
+                }
+            }
         }
     }
 
@@ -610,6 +1350,39 @@ impl Player {
         self.last_health = self.health;
     }
 
+    /// While the player stands still and aims, the legs stay planted and only the upper body
+    /// follows the camera. Returns `true` while the legs are stepping to catch up with the camera.
+    fn update_turn_in_place(&mut self, is_walking: bool, dt: f32) -> bool {
+        if is_walking || !self.controller.aim {
+            self.legs_yaw = self.controller.yaw;
+            self.turning_in_place = false;
+            return false;
+        }
+
+        let twist = self.upper_body_twist();
+        if twist.abs() > Self::TURN_IN_PLACE_THRESHOLD.to_radians() {
+            self.turning_in_place = true;
+        }
+
+        if self.turning_in_place {
+            let step = Self::TURN_IN_PLACE_SPEED * dt;
+            if twist.abs() <= step {
+                self.legs_yaw = self.controller.yaw;
+                self.turning_in_place = false;
+            } else {
+                self.legs_yaw += step * twist.signum();
+            }
+        }
+
+        self.turning_in_place
+    }
+
+    /// Signed angle between the camera and the legs in `[-pi; pi]` range.
+    fn upper_body_twist(&self) -> f32 {
+        let delta = self.controller.yaw - self.legs_yaw;
+        delta.sin().atan2(delta.cos())
+    }
+
     fn is_walking(&self) -> bool {
         self.controller.walk_backward
             || self.controller.walk_forward
@@ -643,9 +1416,10 @@ impl Player {
         is_walking: bool,
         is_jumping: bool,
         has_ground_contact: bool,
+        fall_time_factor: f32,
         sound_manager: &SoundManager,
     ) {
-        let weapon_kind = self.current_weapon_kind(&scene.graph);
+        let weapon_kind = self.combat_weapon_kind(&scene.graph);
 
         let should_be_stunned = self.should_be_stunned();
         if should_be_stunned {
@@ -655,7 +1429,8 @@ impl Player {
         self.state_machine.apply(StateMachineInput {
             is_walking,
             is_jumping,
-            has_ground_contact: self.in_air_time <= 0.3,
+            // Short flights must not trigger fall animation, in low gravity they take longer.
+            has_ground_contact: self.in_air_time <= 0.3 * fall_time_factor,
             is_aiming: self.controller.aim,
             run_factor: self.run_factor,
             is_dead: self.is_dead(),
@@ -663,6 +1438,7 @@ impl Player {
             machine: self.machine,
             weapon_kind,
             toss_grenade: self.controller.toss_grenade,
+            melee: self.melee_cooldown > 0.0,
             change_weapon: self.weapon_change_direction != RequiredWeapon::None,
             scene,
         });
@@ -750,13 +1526,12 @@ impl Player {
                 if self.controller.shoot
                     && weapon_ref(current_weapon_handle, &scene.graph).can_shoot(elapsed_time)
                 {
-                    let ammo_per_shot = weapon_ref(current_weapon_handle, &scene.graph)
-                        .definition
-                        .ammo_consumption_per_shot;
+                    let definition = weapon_ref(current_weapon_handle, &scene.graph).definition;
+                    let ammo_per_shot = definition.ammo_consumption_per_shot;
 
                     if self
                         .inventory
-                        .try_extract_exact_items(ItemKind::Ammo, ammo_per_shot)
+                        .try_extract_exact_items(definition.ammo_item, ammo_per_shot)
                         == ammo_per_shot
                     {
                         weapon_mut(current_weapon_handle, &mut scene.graph).request_shot(None);
@@ -769,14 +1544,10 @@ impl Player {
                             camera_controller.request_shake_camera();
                         }
                         self.v_recoil.set_target(
-                            weapon_ref(current_weapon_handle, &scene.graph)
-                                .definition
-                                .gen_v_recoil_angle(),
+                            weapon_ref(current_weapon_handle, &scene.graph).gen_v_recoil_angle(),
                         );
                         self.h_recoil.set_target(
-                            weapon_ref(current_weapon_handle, &scene.graph)
-                                .definition
-                                .gen_h_recoil_angle(),
+                            weapon_ref(current_weapon_handle, &scene.graph).gen_h_recoil_angle(),
                         );
                     }
                 }
@@ -844,10 +1615,245 @@ impl Player {
             && !self.state_machine.is_stunned(scene, self.animation_player)
     }
 
+    fn update_stamina(&mut self, is_sprinting: bool, is_holding_breath: bool, dt: f32) {
+        if is_sprinting || is_holding_breath {
+            let rate = if is_sprinting {
+                Self::STAMINA_DRAIN_RATE
+            } else {
+                Self::BREATH_HOLD_DRAIN_RATE
+            };
+            self.stamina = (self.stamina - rate * dt).max(0.0);
+            if self.stamina <= 0.0 {
+                self.exhausted = true;
+                if is_holding_breath {
+                    self.scope_sway.start_shaking();
+                }
+            }
+        } else {
+            self.stamina = (self.stamina + Self::STAMINA_REGEN_RATE * dt).min(Self::MAX_STAMINA);
+            if self.stamina >= Self::STAMINA_RECOVERY_THRESHOLD {
+                self.exhausted = false;
+            }
+        }
+    }
+
+    fn update_scope_sway(&mut self, graph: &Graph, is_holding_breath: bool, dt: f32) {
+        let amplitude =
+            try_weapon_ref(self.current_weapon(), graph).map_or(0.0, |w| w.definition.scope_sway);
+        let delta = self.scope_sway.update(
+            amplitude,
+            self.controller.aim && !self.is_dead(),
+            is_holding_breath,
+            dt,
+        );
+        self.controller.yaw += delta.x;
+        self.controller.pitch =
+            (self.controller.pitch + delta.y).clamp(-90.0f32.to_radians(), 90.0f32.to_radians());
+    }
+
+    /// Turns the view to the closest (by angle) visible enemy within the given cone. It is an
+    /// accessibility option, it is performed once when the player starts aiming, the player is
+    /// free to adjust aim afterwards.
+    fn snap_aim(
+        &mut self,
+        graph: &Graph,
+        self_handle: Handle<Node>,
+        actors: &[Handle<Node>],
+        max_angle: f32,
+        strength: f32,
+    ) {
+        let camera = match graph
+            .try_get(self.camera_controller)
+            .and_then(|c| c.try_get_script::<CameraController>())
+        {
+            Some(camera_controller) => camera_controller.camera(),
+            None => return,
+        };
+
+        let origin = graph[camera].global_position();
+        let look = graph[camera].look_vector();
+        let max_angle = max_angle.to_radians();
+
+        let direction = actors
+            .iter()
+            .filter(|&&actor| actor != self_handle)
+            .filter_map(|&actor| try_get_character_ref(actor, graph))
+            .filter(|character| !character.is_dead() && character.team != self.team)
+            .map(|character| character.position(graph) - origin)
+            .filter(|direction| {
+                look.angle(direction) <= max_angle
+                    && !is_line_of_sight_blocked(graph, origin, origin + direction)
+            })
+            .min_by(|a, b| look.angle(a).total_cmp(&look.angle(b)));
+
+        if let Some(direction) = direction {
+            let wrap = |angle: f32| (angle + PI).rem_euclid(2.0 * PI) - PI;
+            let strength = strength.clamp(0.0, 1.0);
+            // Positive pitch means looking down.
+            let yaw = direction.x.atan2(direction.z);
+            let pitch = (-direction.y).atan2(direction.xz().norm());
+            self.controller.yaw += wrap(yaw - self.controller.yaw) * strength;
+            self.controller.pitch = (self.controller.pitch
+                + (pitch - self.controller.pitch) * strength)
+                .clamp(-90.0f32.to_radians(), 90.0f32.to_radians());
+        }
+    }
+
+    fn switch_flash_light(&mut self) {
+        // Dead battery must be recharged a bit before the light can be turned on again.
+        self.flash_light_enabled = !self.flash_light_enabled && self.flash_light_battery > 0.0;
+    }
+
+    fn update_flash_light(&mut self, graph: &mut Graph, dt: f32) {
+        if self.flash_light_enabled {
+            self.flash_light_battery =
+                (self.flash_light_battery - Self::FLASH_LIGHT_DRAIN_RATE * dt).max(0.0);
+            if self.flash_light_battery <= 0.0 {
+                self.flash_light_enabled = false;
+            }
+        } else {
+            self.flash_light_battery = (self.flash_light_battery
+                + Self::FLASH_LIGHT_RECHARGE_RATE * dt)
+                .min(Self::MAX_FLASH_LIGHT_BATTERY);
+        }
+
+        let current_weapon = self.current_weapon();
+        if let Some(weapon) = try_weapon_mut(current_weapon, graph) {
+            weapon.set_flash_light_enabled(self.flash_light_enabled);
+        }
+    }
+
+    fn update_weapon_accuracy(&self, graph: &mut Graph, is_walking: bool) {
+        let current_weapon = self.current_weapon();
+        if let Some(weapon) = try_weapon_mut(current_weapon, graph) {
+            weapon.set_accuracy_inputs(is_walking, self.controller.aim);
+        }
+    }
+
+    pub fn flash_light_battery(&self) -> f32 {
+        self.flash_light_battery
+    }
+
+    pub fn is_flash_light_enabled(&self) -> bool {
+        self.flash_light_enabled
+    }
+
+    pub fn stamina(&self) -> f32 {
+        self.stamina
+    }
+
+    pub fn is_exhausted(&self) -> bool {
+        self.exhausted
+    }
+
+    pub fn set_no_clip(&mut self, no_clip: bool) {
+        self.no_clip = no_clip;
+    }
+
+    pub fn is_no_clip(&self) -> bool {
+        self.no_clip
+    }
+
+    /// In no-clip mode the player flies up or down when moving while looking up or down.
+    fn no_clip_vertical_speed(&self) -> f32 {
+        let direction = match (self.controller.walk_forward, self.controller.walk_backward) {
+            (true, false) => 1.0,
+            (false, true) => -1.0,
+            _ => 0.0,
+        };
+        // Positive pitch means looking down.
+        -self.controller.pitch.sin() * direction * Self::NO_CLIP_VERTICAL_SPEED
+    }
+
     pub fn is_aiming(&self) -> bool {
         self.controller.aim
     }
 
+    /// Camera zoom of the current weapon when aiming down sights.
+    pub fn aim_zoom(&self, graph: &Graph) -> f32 {
+        try_weapon_ref(self.current_weapon(), graph).map_or(1.0, |w| w.definition.aim_zoom)
+    }
+
+    /// Current sway of the sight of a scoped weapon, used by the scope overlay.
+    pub fn scope_sway(&self) -> &ScopeSway {
+        &self.scope_sway
+    }
+
+    fn is_holding_breath(&self, graph: &Graph) -> bool {
+        self.controller.hold_breath
+            && self.controller.aim
+            && !self.exhausted
+            && !self.is_dead()
+            && try_weapon_ref(self.current_weapon(), graph)
+                .map_or(false, |w| w.definition.scope_sway > 0.0)
+    }
+
+    /// See [`StateMachine::aim_transition_time`].
+    pub fn aim_transition_time(&self, graph: &Graph) -> Option<f32> {
+        self.state_machine.aim_transition_time(graph)
+    }
+
+    pub fn camera_controller(&self) -> Handle<Node> {
+        self.camera_controller
+    }
+
+    /// Checks whether all players except the given one are dead and have no lives left.
+    fn are_other_players_out(
+        self_handle: Handle<Node>,
+        players: &[Handle<Node>],
+        graph: &Graph,
+    ) -> bool {
+        players
+            .iter()
+            .filter(|&&player| player != self_handle)
+            .all(|&player| {
+                graph
+                    .try_get(player)
+                    .and_then(|p| p.try_get_script::<Player>())
+                    .map_or(true, |p| p.is_dead() && p.lives == 0)
+            })
+    }
+
+    pub fn lives(&self) -> u32 {
+        self.lives
+    }
+
+    /// Brings dead player back to life at the point where the player has entered the level.
+    pub fn respawn(&mut self, scene: &mut Scene) {
+        self.lives = self.lives.saturating_sub(1);
+        self.respawn_timer = 0.0;
+        self.health = 100.0;
+        self.last_health = 100.0;
+        self.armor = 0.0;
+        self.clear_all_statuses();
+        let respawn_protection = self.respawn_protection;
+        self.protect(respawn_protection);
+
+        let animations_container =
+            utils::fetch_animation_container_mut(&mut scene.graph, self.animation_player);
+        animations_container
+            .get_mut(self.state_machine.dying_animation)
+            .set_enabled(false)
+            .rewind();
+
+        // Death state has no outgoing transitions, so every layer is put back to its entry state.
+        if let Some(absm) = scene
+            .graph
+            .try_get_mut_of_type::<AnimationBlendingStateMachine>(self.machine)
+        {
+            for layer in absm.machine_mut().get_value_mut_silent().layers_mut() {
+                layer.reset();
+            }
+        }
+
+        let body = scene.graph[self.body].as_rigid_body_mut();
+        body.set_lin_vel(Default::default());
+        body.set_ang_vel(Default::default());
+        if let Some(spawn_position) = self.spawn_position {
+            body.local_transform_mut().set_position(spawn_position);
+        }
+    }
+
     pub fn is_completely_dead(&self, scene: &Scene) -> bool {
         let animations_container =
             utils::fetch_animation_container_ref(&scene.graph, self.animation_player);
@@ -943,17 +1949,43 @@ impl ScriptTrait for Player {
 
         self.inventory.add_item(ItemKind::Grenade, 10);
 
+        let game = game_mut(context.plugins);
+        if let Some(lives) = game.player_lives.take() {
+            self.lives = lives;
+        }
+        self.health_mode = game.control_scheme.difficulty.health_mode();
+
+        // Bones of the left arm are found by name, unless they're assigned in the editor.
+        let model = self.model;
+        for (bone, name) in [
+            (&mut self.left_arm, "mixamorig:LeftArm"),
+            (&mut self.left_fore_arm, "mixamorig:LeftForeArm"),
+            (&mut self.left_hand, "mixamorig:LeftHand"),
+        ] {
+            if bone.is_none() {
+                *bone = context
+                    .scene
+                    .graph
+                    .find_by_name(model, name)
+                    .map(|(handle, _)| handle)
+                    .unwrap_or_default();
+            }
+        }
+
         let level = current_level_mut(context.plugins).unwrap();
 
         level.actors.push(context.handle);
         // Also register player in special variable to speed up access.
-        level.player = context.handle;
+        level.add_player(context.handle);
+
+        self.spawn_position = Some(self.position(&context.scene.graph));
     }
 
     fn on_start(&mut self, ctx: &mut ScriptContext) {
         let game = game_ref(ctx.plugins);
 
         self.state_machine = StateMachine::new(self.machine, &ctx.scene.graph).unwrap();
+        self.character.resolve_hit_boxes(&ctx.scene.graph);
 
         self.resolve(
             ctx.scene,
@@ -966,7 +1998,7 @@ impl ScriptTrait for Player {
 
     fn on_deinit(&mut self, context: &mut ScriptDeinitContext) {
         if let Some(level) = current_level_mut(context.plugins) {
-            level.player = Handle::NONE;
+            level.remove_player(context.node_handle);
 
             if let Some(position) = level.actors.iter().position(|a| *a == context.node_handle) {
                 level.actors.remove(position);
@@ -975,8 +2007,31 @@ impl ScriptTrait for Player {
     }
 
     fn on_os_event(&mut self, event: &Event<()>, context: &mut ScriptContext) {
+        // In local co-op each player handles input only from their own devices.
+        if !current_level_mut(context.plugins)
+            .map_or(true, |level| level.is_input_of(context.handle, event))
+        {
+            return;
+        }
+
+        // Free-fly camera takes over the input, the player just stands still meanwhile.
+        if context
+            .scene
+            .graph
+            .try_get(self.camera_controller)
+            .and_then(|c| c.try_get_script::<CameraController>())
+            .map_or(false, |c| c.is_free_fly())
+        {
+            self.controller = InputController {
+                yaw: self.controller.yaw,
+                pitch: self.controller.pitch,
+                ..Default::default()
+            };
+            return;
+        }
+
         let game = game_ref(context.plugins);
-        let control_scheme = &game.control_scheme;
+        let control_scheme = game.player_control_scheme(context.handle);
         let sender = &game.message_sender;
 
         let button_state = match event {
@@ -1033,20 +2088,17 @@ impl ScriptTrait for Player {
         let jump_anim = animations_container.get(self.state_machine.jump_animation);
         let can_jump = !jump_anim.is_enabled() || jump_anim.has_ended();
 
-        let can_change_weapon = self.weapon_change_direction.is_none()
-            && animations_container[self.state_machine.grab_animation].has_ended()
-            && self.weapons.len() > 1;
+        let can_change_weapon = self.can_change_weapon(&context.scene.graph);
 
-        let current_weapon_kind = if self.current_weapon().is_some() {
-            Some(weapon_ref(self.current_weapon(), &context.scene.graph).kind())
-        } else {
-            None
-        };
+        let current_weapon_kind = self.current_weapon_kind(&context.scene.graph);
 
         let mut weapon_change_direction = None;
 
         if let Some((button, state)) = button_state {
             if button == control_scheme.aim.button {
+                if state == ElementState::Pressed && !self.controller.aim {
+                    self.aim_snap_requested = true;
+                }
                 self.controller.aim = state == ElementState::Pressed;
                 if state == ElementState::Pressed {
                     context.scene.graph[self.inventory_display].set_visibility(false);
@@ -1081,12 +2133,13 @@ impl ScriptTrait for Player {
                 self.controller.jump = state == ElementState::Pressed && can_jump;
             } else if button == control_scheme.run.button {
                 self.controller.run = state == ElementState::Pressed;
+            } else if button == control_scheme.lean_left.button {
+                self.controller.lean_left = state == ElementState::Pressed;
+            } else if button == control_scheme.lean_right.button {
+                self.controller.lean_right = state == ElementState::Pressed;
             } else if button == control_scheme.flash_light.button {
                 if state == ElementState::Pressed {
-                    let current_weapon = self.current_weapon();
-                    if current_weapon.is_some() {
-                        weapon_mut(current_weapon, &mut context.scene.graph).switch_flash_light();
-                    }
+                    self.switch_flash_light();
                 }
             } else if button == control_scheme.grab_ak47.button && can_change_weapon {
                 if current_weapon_kind.map_or(false, |k| k != WeaponKind::Ak47) {
@@ -1116,10 +2169,31 @@ impl ScriptTrait for Player {
                 if state == ElementState::Pressed && self.current_weapon > 0 && can_change_weapon {
                     weapon_change_direction = Some(RequiredWeapon::Previous);
                 }
+            } else if button == control_scheme.hold_breath.button {
+                self.controller.hold_breath = state == ElementState::Pressed;
+            } else if button == control_scheme.toggle_suppressor.button {
+                if state == ElementState::Pressed {
+                    if let Some(weapon) =
+                        try_weapon_mut(self.current_weapon(), &mut context.scene.graph)
+                    {
+                        if weapon.definition.suppressor {
+                            let suppressed = !weapon.is_suppressed();
+                            weapon.set_suppressed(suppressed);
+                        }
+                    }
+                }
+            } else if button == control_scheme.drop_weapon.button {
+                if state == ElementState::Pressed
+                    && self.drop_weapon(&context.scene.graph, control_scheme.allow_drop_last_weapon)
+                {
+                    sender.send(Message::SyncInventory);
+                }
             } else if button == control_scheme.toss_grenade.button {
                 if self.inventory.item_count(ItemKind::Grenade) > 0 {
                     self.controller.toss_grenade = state == ElementState::Pressed;
                     if state == ElementState::Pressed {
+                        self.grenade_cook_time.get_or_insert(0.0);
+
                         let animations_container = utils::fetch_animation_container_mut(
                             &mut context.scene.graph,
                             self.animation_player,
@@ -1131,6 +2205,10 @@ impl ScriptTrait for Player {
                             .rewind();
                     }
                 }
+            } else if button == control_scheme.melee.button {
+                if state == ElementState::Pressed && !self.controller.aim {
+                    self.try_start_melee(&mut context.scene.graph);
+                }
             } else if button == control_scheme.shoot.button {
                 self.controller.shoot = state == ElementState::Pressed;
             } else if button == control_scheme.cursor_up.button {
@@ -1166,22 +2244,35 @@ impl ScriptTrait for Player {
             }
         }
 
-        if let Some(weapon_change_direction) = weapon_change_direction {
-            self.weapon_change_direction = weapon_change_direction;
-
-            let animations_container = utils::fetch_animation_container_mut(
-                &mut context.scene.graph,
-                self.animation_player,
-            );
+        // Pressing a weapon switch button again right after a switch has started plays the
+        // rest of the switch faster.
+        if let Some((button, ElementState::Pressed)) = button_state {
+            let is_weapon_switch_button = [
+                &control_scheme.grab_ak47,
+                &control_scheme.grab_m4,
+                &control_scheme.grab_plasma_gun,
+                &control_scheme.grab_pistol,
+                &control_scheme.next_weapon,
+                &control_scheme.prev_weapon,
+            ]
+            .iter()
+            .any(|definition| definition.button == button);
 
-            animations_container
-                .get_mut(self.state_machine.put_back_animation)
-                .rewind();
+            if is_weapon_switch_button
+                && weapon_change_direction.is_none()
+                && !self.weapon_change_direction.is_none()
+                && self.quick_swap_timer > 0.0
+            {
+                self.quick_swap_timer = 0.0;
+                self.set_weapon_switch_animation_speed(
+                    &mut context.scene.graph,
+                    Self::QUICK_SWAP_SPEED_SCALE,
+                );
+            }
+        }
 
-            animations_container
-                .get_mut(self.state_machine.grab_animation)
-                .set_enabled(false)
-                .rewind();
+        if let Some(weapon_change_direction) = weapon_change_direction {
+            self.request_weapon_change(weapon_change_direction, &mut context.scene.graph);
         }
     }
 
@@ -1193,33 +2284,84 @@ impl ScriptTrait for Player {
         let game = game_ref(ctx.plugins);
         let level = current_level_ref(ctx.plugins).unwrap();
 
+        self.weapon_switch_speed = game.control_scheme.weapon_switch_speed;
+        self.quick_swap_timer -= ctx.dt;
+
+        let health_before_commands = self.health;
+
+        self.update_status_effects(ctx.dt);
+        let model = self.model;
+        self.character.update_spawn_protection(
+            &mut ctx.scene.graph,
+            model,
+            game.accessibility.flashing,
+            ctx.dt,
+        );
+
         while self
             .poll_command(
                 ctx.scene,
                 ctx.handle,
                 ctx.resource_manager,
                 &level.sound_manager,
+                &game.message_sender,
+                level.friendly_fire,
             )
             .is_some()
         {
             // TODO: Handle commands here
         }
 
+        if self.health < health_before_commands {
+            self.time_since_damage = 0.0;
+
+            let damage = health_before_commands - self.health;
+            if let Some(camera_controller) = ctx
+                .scene
+                .graph
+                .try_get_mut(self.camera_controller)
+                .and_then(|c| c.try_get_script_mut::<CameraController>())
+            {
+                camera_controller.shake((damage / Self::DAMAGE_PER_SHAKE_AMPLITUDE).min(2.0), 0.3);
+            }
+        }
+
         self.update_health_cylinder(ctx.scene);
 
         let has_ground_contact = self.has_ground_contact(&ctx.scene.graph);
         let is_walking = self.is_walking();
         let is_jumping = has_ground_contact && self.controller.jump;
+        let is_turning_in_place = self.update_turn_in_place(is_walking, ctx.dt);
 
         self.update_animation_machines(
             ctx.scene,
-            is_walking,
+            // Legs make steps while turning in place.
+            is_walking || is_turning_in_place,
             is_jumping,
             has_ground_contact,
+            level.physics.fall_time_factor(),
             &level.sound_manager,
         );
 
-        let is_running = self.is_running(ctx.scene);
+        let is_running = self.is_running(ctx.scene) && !self.exhausted;
+
+        let is_holding_breath = self.is_holding_breath(&ctx.scene.graph);
+        self.update_stamina(is_running && is_walking, is_holding_breath, ctx.dt);
+        self.update_scope_sway(&ctx.scene.graph, is_holding_breath, ctx.dt);
+        if std::mem::take(&mut self.aim_snap_requested)
+            && game.accessibility.aim_snap
+            && !self.is_dead()
+        {
+            self.snap_aim(
+                &ctx.scene.graph,
+                ctx.handle,
+                &level.actors,
+                game.accessibility.aim_snap_angle,
+                game.accessibility.aim_snap_strength,
+            );
+        }
+        self.update_flash_light(&mut ctx.scene.graph, ctx.dt);
+        self.update_weapon_accuracy(&mut ctx.scene.graph, is_walking);
 
         if !self.is_dead() {
             if is_running {
@@ -1229,31 +2371,73 @@ impl ScriptTrait for Player {
             }
             self.run_factor += (self.target_run_factor - self.run_factor) * 0.1;
 
-            let can_move = self.can_move(&ctx.scene.graph);
+            self.update_health_mode(ctx.dt);
+
+            let can_move = self.no_clip || self.can_move(&ctx.scene.graph);
             self.update_velocity(ctx.scene, can_move, ctx.dt);
-            let new_y_vel = self.handle_jump_signal(ctx.scene, ctx.dt);
+            let new_y_vel = self.handle_jump_signal(ctx.scene, level.physics.jump_scale, ctx.dt);
             self.handle_weapon_grab_signal(ctx.scene);
             self.handle_put_back_weapon_end_signal(ctx.scene);
-            self.handle_toss_grenade_signal(Default::default(), ctx.scene, ctx.resource_manager);
+            self.handle_toss_grenade_signal(
+                ctx.handle,
+                ctx.scene,
+                ctx.resource_manager,
+                &level.projectile_pool,
+            );
+            self.handle_melee_hit_signal(ctx.handle, ctx.scene, &level.actors, ctx.dt);
+            self.update_grenade_cooking(
+                ctx.handle,
+                ctx.scene,
+                ctx.resource_manager,
+                &level.projectile_pool,
+                ctx.dt,
+            );
 
+            if let Some(collider) = ctx
+                .scene
+                .graph
+                .try_get_mut(self.capsule_collider)
+                .and_then(|c| c.cast_mut::<Collider>())
+            {
+                collider.set_is_sensor(self.no_clip);
+            }
+
+            let knockback = self.update_knockback(ctx.dt);
             let body = ctx.scene.graph[self.body].as_rigid_body_mut();
             body.set_ang_vel(Default::default());
-            if let Some(new_y_vel) = new_y_vel {
+            body.set_gravity_scale(if self.no_clip {
+                0.0
+            } else {
+                level.physics.gravity_scale
+            });
+            if self.no_clip {
                 body.set_lin_vel(Vector3::new(
                     self.velocity.x / ctx.dt,
-                    new_y_vel / ctx.dt,
+                    self.no_clip_vertical_speed(),
                     self.velocity.z / ctx.dt,
                 ));
+            } else if let Some(new_y_vel) = new_y_vel {
+                body.set_lin_vel(Vector3::new(
+                    self.velocity.x / ctx.dt + knockback.x,
+                    new_y_vel / ctx.dt,
+                    self.velocity.z / ctx.dt + knockback.z,
+                ));
             } else {
                 body.set_lin_vel(Vector3::new(
-                    self.velocity.x / ctx.dt,
+                    self.velocity.x / ctx.dt + knockback.x,
                     body.lin_vel().y,
-                    self.velocity.z / ctx.dt,
+                    self.velocity.z / ctx.dt + knockback.z,
                 ));
             }
 
             if self.controller.aim {
-                self.spine_pitch.set_target(self.controller.pitch);
+                // Camera can look almost straight up or down, but the spine can't bend that much.
+                let max_spine_pitch = Character::MAX_SPINE_PITCH.to_radians();
+                self.spine_pitch.set_target(
+                    self.controller
+                        .pitch
+                        .clamp(-max_spine_pitch, max_spine_pitch),
+                );
             } else {
                 self.spine_pitch.set_target(0.0);
             }
@@ -1267,7 +2451,7 @@ impl ScriptTrait for Player {
                     .local_transform_mut()
                     .set_rotation(UnitQuaternion::from_axis_angle(
                         &Vector3::y_axis(),
-                        self.controller.yaw,
+                        self.legs_yaw,
                     ));
 
                 // Apply additional rotation to model - it will turn in front of walking direction.
@@ -1291,7 +2475,8 @@ impl ScriptTrait for Player {
                             )
                             * UnitQuaternion::from_axis_angle(
                                 &Vector3::y_axis(),
-                                -(self.model_yaw.angle + 37.5f32.to_radians()),
+                                self.upper_body_twist()
+                                    - (self.model_yaw.angle + 37.5f32.to_radians()),
                             ),
                     );
                     additional_hips_rotation =
@@ -1349,6 +2534,28 @@ impl ScriptTrait for Player {
             }
 
             self.apply_weapon_angular_correction(ctx.scene, can_move, ctx.dt);
+            self.update_left_hand_ik(ctx.scene, ctx.dt);
+
+            if self
+                .character
+                .update_fall_damage(
+                    ctx.handle,
+                    &ctx.scene.graph,
+                    &level.physics,
+                    &game.message_sender,
+                )
+                .is_some()
+            {
+                let position = self.position(&ctx.scene.graph);
+                level.sound_manager.play_sound(
+                    &mut ctx.scene.graph,
+                    "data/sounds/agent_pain_1.wav",
+                    position,
+                    1.0,
+                    4.0,
+                    3.0,
+                );
+            }
 
             if has_ground_contact {
                 self.in_air_time = 0.0;
@@ -1371,17 +2578,34 @@ impl ScriptTrait for Player {
 
             ctx.scene.graph[self.item_display].set_visibility(false);
 
+            if self.controller.action
+                && self.interact(
+                    ctx.scene,
+                    &level.items,
+                    &level.doors_container,
+                    &game.message_sender,
+                )
+            {
+                self.controller.action = false;
+            }
+
             self.check_doors(ctx.scene, &level.doors_container);
             self.check_elevators(ctx.scene, &level.elevators);
             self.update_shooting(ctx.scene, ctx.dt, ctx.elapsed_time);
             self.check_items(game_mut(ctx.plugins), ctx.scene, ctx.resource_manager);
 
+            self.update_lean(&ctx.scene.graph, ctx.dt);
+
             let spine_transform = ctx.scene.graph[self.spine].local_transform_mut();
             let rotation = **spine_transform.rotation();
             spine_transform.set_rotation(
                 rotation
                     * UnitQuaternion::from_axis_angle(&Vector3::x_axis(), self.v_recoil.angle())
-                    * UnitQuaternion::from_axis_angle(&Vector3::y_axis(), self.h_recoil.angle()),
+                    * UnitQuaternion::from_axis_angle(&Vector3::y_axis(), self.h_recoil.angle())
+                    * UnitQuaternion::from_axis_angle(
+                        &Vector3::z_axis(),
+                        self.lean * Self::MAX_LEAN_ANGLE.to_radians(),
+                    ),
             );
         } else {
             for &dying_animation in &[
@@ -1403,7 +2627,18 @@ impl ScriptTrait for Player {
             body.set_lin_vel(Vector3::new(0.0, body.lin_vel().y, 0.0));
 
             if self.is_completely_dead(ctx.scene) {
-                game.message_sender.send(Message::EndMatch);
+                if self.lives > 0 {
+                    self.respawn_timer += ctx.dt;
+                    if self.respawn_timer >= Self::RESPAWN_TIME {
+                        self.respawn_timer = 0.0;
+                        game.message_sender
+                            .send(Message::SpawnPlayer { player: ctx.handle });
+                    }
+                } else if Self::are_other_players_out(ctx.handle, &level.players, &ctx.scene.graph)
+                {
+                    // In co-op the match ends only when every player is out of lives.
+                    game.message_sender.send(Message::EndMatch);
+                }
             }
         }
     }
@@ -0,0 +1,77 @@
+//! Sway of scoped weapons while aiming down sights. The player can steady the aim by holding
+//! breath, which costs stamina, once stamina runs out the aim gets shaky for a while.
+
+use fyrox::core::algebra::Vector2;
+
+#[derive(Default, Debug, Clone)]
+pub struct ScopeSway {
+    phase: f32,
+    /// Current sway scale, it smoothly follows the target one.
+    factor: f32,
+    /// Time left (in seconds) of the shaky period after running out of breath.
+    shaky_timer: f32,
+    /// Offset (yaw and pitch, in radians) applied to the view last frame.
+    applied: Vector2<f32>,
+}
+
+impl ScopeSway {
+    /// Fraction of sway that remains while the breath is held.
+    const STEADY_FACTOR: f32 = 0.05;
+    const SHAKY_FACTOR: f32 = 1.8;
+    const SHAKY_TIME: f32 = 3.0;
+    /// Angular frequency (in radians per second) of the sway.
+    const FREQUENCY: f32 = 0.9;
+    const SHAKY_FREQUENCY_SCALE: f32 = 2.5;
+    /// Fraction of difference between current and target sway scale applied per second.
+    const FACTOR_FOLLOW_RATE: f32 = 4.0;
+
+    /// Must be called when the player runs out of breath while holding it.
+    pub fn start_shaking(&mut self) {
+        self.shaky_timer = Self::SHAKY_TIME;
+    }
+
+    /// Updates the sway and returns a change of the view angles (yaw and pitch, in radians)
+    /// since the last update. `amplitude` is given in degrees, zero disables the sway.
+    pub fn update(
+        &mut self,
+        amplitude: f32,
+        is_aiming: bool,
+        is_holding_breath: bool,
+        dt: f32,
+    ) -> Vector2<f32> {
+        let is_shaky = self.shaky_timer > 0.0;
+        self.shaky_timer -= dt;
+
+        let target_factor = if !is_aiming || amplitude <= 0.0 {
+            0.0
+        } else if is_holding_breath {
+            Self::STEADY_FACTOR
+        } else if is_shaky {
+            Self::SHAKY_FACTOR
+        } else {
+            1.0
+        };
+        self.factor += (target_factor - self.factor) * (Self::FACTOR_FOLLOW_RATE * dt).min(1.0);
+
+        let frequency = if is_shaky {
+            Self::FREQUENCY * Self::SHAKY_FREQUENCY_SCALE
+        } else {
+            Self::FREQUENCY
+        };
+        self.phase += frequency * dt;
+
+        // Figure-eight pattern, the sight drifts more horizontally than vertically.
+        let offset = Vector2::new(self.phase.sin(), 0.5 * (2.0 * self.phase).sin())
+            .scale(amplitude.to_radians() * self.factor);
+
+        let delta = offset - self.applied;
+        self.applied = offset;
+        delta
+    }
+
+    /// Current sway scale, zero means that the aim is perfectly steady and one - usual sway.
+    /// It could go above one when the aim is shaky.
+    pub fn magnitude(&self) -> f32 {
+        self.factor
+    }
+}
@@ -1,4 +1,4 @@
-use crate::{character::Character, sound::SoundManager, utils};
+use crate::{character::Character, sound::SoundManager, utils, weapon::definition::WeaponKind};
 use fyrox::{
     animation::{
         machine::{MachineLayer, Parameter, State, Transition},
@@ -20,6 +20,20 @@ pub enum CombatWeaponKind {
     Rifle = 1,
 }
 
+impl From<WeaponKind> for CombatWeaponKind {
+    /// Defines which set of animations (aim, idle, hit reaction, etc.) is used for a weapon.
+    fn from(kind: WeaponKind) -> Self {
+        match kind {
+            WeaponKind::M4
+            | WeaponKind::Ak47
+            | WeaponKind::PlasmaRifle
+            | WeaponKind::RailGun
+            | WeaponKind::Shotgun => Self::Rifle,
+            WeaponKind::Glock => Self::Pistol,
+        }
+    }
+}
+
 pub struct StateMachineInput<'a> {
     pub is_walking: bool,
     pub is_jumping: bool,
@@ -27,6 +41,7 @@ pub struct StateMachineInput<'a> {
     pub has_ground_contact: bool,
     pub is_aiming: bool,
     pub toss_grenade: bool,
+    pub melee: bool,
     pub weapon_kind: CombatWeaponKind,
     pub change_weapon: bool,
     pub is_dead: bool,
@@ -57,6 +72,8 @@ pub struct StateMachine {
     pub toss_grenade_animation: Handle<Animation>,
     pub put_back_animation: Handle<Animation>,
     pub grab_animation: Handle<Animation>,
+    pub melee_state: Handle<State>,
+    pub melee_animation: Handle<Animation>,
 }
 
 impl StateMachine {
@@ -65,6 +82,7 @@ impl StateMachine {
     pub const GRAB_WEAPON_SIGNAL: &'static str = "Grab";
     pub const PUT_BACK_WEAPON_END_SIGNAL: &'static str = "PutBack";
     pub const TOSS_GRENADE_SIGNAL: &'static str = "TossGrenade";
+    pub const MELEE_HIT_SIGNAL: &'static str = "MeleeHit";
 
     pub fn new(machine_handle: Handle<Node>, graph: &Graph) -> Option<Self> {
         let absm = graph.try_get_of_type::<AnimationBlendingStateMachine>(machine_handle)?;
@@ -101,9 +119,24 @@ impl StateMachine {
             toss_grenade_animation: animations.find_by_name_ref("agent_toss_grenade")?.0,
             put_back_animation: animations.find_by_name_ref("agent_put_back")?.0,
             grab_animation: animations.find_by_name_ref("agent_grab")?.0,
+            // Melee is optional, older models do not have it.
+            melee_state: upper_body
+                .find_state_by_name_ref("Melee")
+                .map(|(h, _)| h)
+                .unwrap_or_default(),
+            melee_animation: animations
+                .find_by_name_ref("agent_melee")
+                .map(|(h, _)| h)
+                .unwrap_or_default(),
         })
     }
 
+    /// Returns true if the machine is able to play melee attack, models without the melee state
+    /// or animation deal melee damage instantly.
+    pub fn has_melee(&self) -> bool {
+        self.melee_state.is_some() && self.melee_animation.is_some()
+    }
+
     pub fn fetch_layer<'a>(&self, graph: &'a Graph, name: &str) -> Option<&'a MachineLayer> {
         graph
             .try_get_of_type::<AnimationBlendingStateMachine>(self.machine_handle)
@@ -118,6 +151,18 @@ impl StateMachine {
         self.fetch_layer(graph, "UpperBody")
     }
 
+    /// Duration of the transition to the aim pose, anything synchronized with aiming should use
+    /// it to match the animation.
+    pub fn aim_transition_time(&self, graph: &Graph) -> Option<f32> {
+        self.upper_body_layer(graph).and_then(|layer| {
+            layer
+                .transitions()
+                .iter()
+                .find(|transition| transition.dest() == self.aim_state)
+                .map(|transition| transition.transition_time())
+        })
+    }
+
     pub fn handle_animation_events(
         &self,
         character: &Character,
@@ -185,6 +230,7 @@ impl StateMachine {
             has_ground_contact,
             is_aiming,
             toss_grenade,
+            melee,
             weapon_kind,
             change_weapon,
             is_dead,
@@ -218,6 +264,9 @@ impl StateMachine {
         let toss_grenade_animation_ended = animations_container
             .get(self.toss_grenade_animation)
             .has_ended();
+        let melee_animation_ended = animations_container
+            .try_get(self.melee_animation)
+            .map_or(true, |a| a.has_ended());
 
         scene
             .graph
@@ -247,7 +296,9 @@ impl StateMachine {
             .set_parameter(
                 "GrenadeTossed",
                 Parameter::Rule(toss_grenade_animation_ended),
-            );
+            )
+            .set_parameter("Melee", Parameter::Rule(melee))
+            .set_parameter("MeleeEnded", Parameter::Rule(melee_animation_ended));
     }
 
     pub fn hit_reaction_animations(&self) -> [Handle<Animation>; 2] {
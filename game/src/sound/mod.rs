@@ -1,17 +1,29 @@
+use crate::{config::SoundConfig, sound::music::MusicPlayer, utils};
 use fyrox::{
     core::{
-        algebra::Vector3, futures::executor::block_on, pool::Handle, sstorage::ImmutableString,
+        algebra::{Point3, Vector3},
+        arrayvec::ArrayVec,
+        futures::executor::block_on,
+        math::ray::Ray,
+        pool::Handle,
+        sstorage::ImmutableString,
     },
     engine::resource_manager::ResourceManager,
     material::PropertyValue,
     rand::{self, seq::SliceRandom},
     scene::{
         base::BaseBuilder,
-        graph::{physics::FeatureId, Graph},
+        collider::InteractionGroups,
+        graph::{
+            physics::{FeatureId, RayCastOptions},
+            Graph,
+        },
         mesh::Mesh,
         node::Node,
         sound::{
+            dsp::filters::{Biquad, BiquadKind},
             effect::{BaseEffectBuilder, Effect, EffectInput, ReverbEffectBuilder},
+            listener::Listener,
             SoundBuilder, Status,
         },
         transform::TransformBuilder,
@@ -20,7 +32,9 @@ use fyrox::{
     utils::log::{Log, MessageKind},
 };
 use serde::Deserialize;
-use std::{collections::HashMap, fs::File, ops::Range, path::Path, path::PathBuf};
+use std::{cell::RefCell, collections::HashMap, fs::File, ops::Range, path::Path, path::PathBuf};
+
+pub mod music;
 
 #[derive(Debug)]
 pub struct TriangleRange {
@@ -38,6 +52,13 @@ pub enum MaterialType {
     Flesh,
 }
 
+impl MaterialType {
+    /// Hard materials deflect projectiles that hit them at shallow angles.
+    pub fn is_hard(self) -> bool {
+        matches!(self, Self::Metal | Self::Stone | Self::Chain)
+    }
+}
+
 #[derive(Deserialize, Hash, Eq, PartialEq, Copy, Clone, Debug)]
 pub enum SoundKind {
     Impact,
@@ -173,32 +194,203 @@ impl SoundMap {
     }
 }
 
+/// A bus is a group of sound sources that share the same volume control.
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum SoundBus {
+    /// One-shot sounds - shots, impacts, footsteps, etc.
+    Sfx,
+    /// Music and ambient loops.
+    Music,
+}
+
+/// Mixer holds gains of every bus. Final gain of a source is `bus * source_gain`, master gain is
+/// applied by the sound engine to every sound, including the ones that are not on any bus.
+#[derive(Clone, Debug)]
+pub struct Mixer {
+    sfx: f32,
+    music: f32,
+}
+
+impl Default for Mixer {
+    fn default() -> Self {
+        Self {
+            sfx: 1.0,
+            music: 1.0,
+        }
+    }
+}
+
+impl Mixer {
+    pub fn from_config(config: &SoundConfig) -> Self {
+        Self {
+            sfx: config.sfx_volume,
+            music: config.music_volume,
+        }
+    }
+
+    pub fn set_sfx_gain(&mut self, gain: f32) {
+        self.sfx = gain.clamp(0.0, 1.0);
+    }
+
+    pub fn sfx_gain(&self) -> f32 {
+        self.sfx
+    }
+
+    pub fn set_music_gain(&mut self, gain: f32) {
+        self.music = gain.clamp(0.0, 1.0);
+    }
+
+    pub fn music_gain(&self) -> f32 {
+        self.music
+    }
+
+    pub fn bus_gain(&self, bus: SoundBus) -> f32 {
+        match bus {
+            SoundBus::Sfx => self.sfx,
+            SoundBus::Music => self.music,
+        }
+    }
+}
+
 #[derive(Default)]
 pub struct SoundManager {
     reverb: Handle<Effect>,
+    // Pass-through effect that muffles the direct signal of occluded sources.
+    occlusion: Handle<Effect>,
     sound_base: SoundBase,
     sound_map: SoundMap,
     resource_manager: Option<ResourceManager>,
+    music_player: MusicPlayer,
+    listener: Handle<Node>,
+    listener_position: Option<Vector3<f32>>,
+    // Occlusion results of current frame, key is a source position snapped to a grid.
+    occlusion_cache: RefCell<HashMap<Vector3<i32>, bool>>,
+    // Mixer is always taken from the config, so loading a save won't revert volume settings.
+    mixer: Mixer,
 }
 
 impl SoundManager {
-    pub fn new(scene: &mut Scene, resource_manager: ResourceManager) -> Self {
+    /// Gain multiplier for sources that are hidden from the listener by level geometry.
+    pub const OCCLUDED_GAIN: f32 = 0.35;
+    /// Normalized (frequency / sample rate) cutoff frequency of the low-pass filter applied
+    /// to occluded sources. ~1.1 kHz at 44.1 kHz.
+    pub const OCCLUDED_CUTOFF: f32 = 0.025;
+    /// Size of a cell of the occlusion cache grid.
+    const OCCLUSION_CELL_SIZE: f32 = 0.5;
+    /// Sounds are often played right at the surface (impacts, footsteps), so the ray stops a bit
+    /// earlier to not hit the surface itself.
+    const OCCLUSION_TOLERANCE: f32 = 0.15;
+
+    pub fn new(scene: &mut Scene, resource_manager: ResourceManager, mixer: Mixer) -> Self {
         let reverb = ReverbEffectBuilder::new(BaseEffectBuilder::new().with_gain(0.7))
             .with_wet(0.5)
             .with_dry(0.5)
             .with_decay_time(3.0)
             .build(&mut scene.graph.sound_context);
 
+        // No reverberation, the effect only passes inputs through their low-pass filters.
+        let occlusion = ReverbEffectBuilder::new(BaseEffectBuilder::new().with_gain(1.0))
+            .with_wet(0.0)
+            .with_dry(1.0)
+            .build(&mut scene.graph.sound_context);
+
         let sound_base = SoundBase::load();
 
         Self {
             reverb,
+            occlusion,
             sound_map: SoundMap::new(scene, &sound_base),
             sound_base,
             resource_manager: Some(resource_manager),
+            music_player: Default::default(),
+            listener: Handle::NONE,
+            listener_position: None,
+            occlusion_cache: Default::default(),
+            mixer,
+        }
+    }
+
+    /// Crossfades current music track to a new one.
+    pub fn play_music<P: AsRef<Path>>(&mut self, graph: &mut Graph, track: P, fade_time: f32) {
+        if let Some(resource_manager) = self.resource_manager.as_ref() {
+            self.music_player
+                .play(graph, resource_manager, track, fade_time);
         }
     }
 
+    pub fn update(&mut self, graph: &mut Graph, in_combat: bool, dt: f32) {
+        // Listener is searched only when it's gone, for example when the camera was re-created.
+        if graph
+            .try_get(self.listener)
+            .and_then(|node| node.cast::<Listener>())
+            .is_none()
+        {
+            self.listener = graph
+                .pair_iter()
+                .find_map(|(handle, node)| node.cast::<Listener>().map(|_| handle))
+                .unwrap_or_default();
+        }
+        self.listener_position = graph
+            .try_get(self.listener)
+            .map(|listener| listener.global_position());
+        self.occlusion_cache.borrow_mut().clear();
+
+        if let Some(resource_manager) = self.resource_manager.as_ref() {
+            self.music_player
+                .update(graph, resource_manager, &self.mixer, in_combat, dt);
+        }
+    }
+
+    pub fn mixer(&self) -> &Mixer {
+        &self.mixer
+    }
+
+    pub fn mixer_mut(&mut self) -> &mut Mixer {
+        &mut self.mixer
+    }
+
+    /// Checks whether there is any level geometry between the listener and the given position.
+    /// Results are cached until next [`Self::update`] call.
+    pub fn is_occluded(&self, graph: &Graph, position: Vector3<f32>) -> bool {
+        let listener_position = match self.listener_position {
+            Some(listener_position) => listener_position,
+            None => return false,
+        };
+
+        let key = position
+            .scale(1.0 / Self::OCCLUSION_CELL_SIZE)
+            .map(|c| c.round() as i32);
+
+        *self
+            .occlusion_cache
+            .borrow_mut()
+            .entry(key)
+            .or_insert_with(|| {
+                let ray = Ray::from_two_points(listener_position, position);
+                let max_len = ray.dir.norm() - Self::OCCLUSION_TOLERANCE;
+                if max_len <= 0.0 {
+                    return false;
+                }
+
+                let mut query_buffer = ArrayVec::<_, 64>::new();
+                graph.physics.cast_ray(
+                    RayCastOptions {
+                        ray_origin: Point3::from(ray.origin),
+                        ray_direction: ray.dir,
+                        groups: InteractionGroups::default(),
+                        max_len,
+                        sort_results: false,
+                    },
+                    &mut query_buffer,
+                );
+
+                // Actors and triggers must not block sounds.
+                query_buffer
+                    .iter()
+                    .any(|hit| utils::is_obstacle(graph, hit.collider))
+            })
+    }
+
     pub fn play_sound<P: AsRef<Path>>(
         &self,
         graph: &mut Graph,
@@ -214,6 +406,8 @@ impl SoundManager {
                 .unwrap()
                 .request_sound_buffer(path.as_ref()),
         ) {
+            let occluded = self.is_occluded(graph, position);
+
             let sound = SoundBuilder::new(
                 BaseBuilder::new().with_local_transform(
                     TransformBuilder::new()
@@ -224,19 +418,43 @@ impl SoundManager {
             .with_buffer(buffer.into())
             .with_status(Status::Playing)
             .with_play_once(true)
-            .with_gain(gain)
+            .with_gain(
+                gain * self.mixer.bus_gain(SoundBus::Sfx)
+                    * if occluded { Self::OCCLUDED_GAIN } else { 1.0 },
+            )
             .with_radius(radius)
             .with_rolloff_factor(rolloff_factor)
             .build(graph);
 
+            let occlusion_filter = || {
+                Biquad::new(
+                    BiquadKind::LowPass,
+                    Self::OCCLUDED_CUTOFF,
+                    0.0,
+                    std::f32::consts::FRAC_1_SQRT_2,
+                )
+            };
+
             graph
                 .sound_context
                 .effect_mut(self.reverb)
                 .inputs_mut()
                 .push(EffectInput {
                     sound,
-                    filter: None,
+                    filter: occluded.then(occlusion_filter),
                 });
+
+            // Both the reverberated and the direct signal must be muffled.
+            if occluded {
+                graph
+                    .sound_context
+                    .effect_mut(self.occlusion)
+                    .inputs_mut()
+                    .push(EffectInput {
+                        sound,
+                        filter: Some(occlusion_filter()),
+                    });
+            }
         } else {
             Log::writeln(
                 MessageKind::Error,
@@ -245,6 +463,24 @@ impl SoundManager {
         }
     }
 
+    /// Returns material type at the given feature of the collider, if it is known.
+    pub fn material_of(&self, collider: Handle<Node>, feature: FeatureId) -> Option<MaterialType> {
+        self.sound_map
+            .ranges_of(collider)
+            .and_then(|ranges| match feature {
+                FeatureId::Face(idx) => ranges
+                    .iter()
+                    .find(|range| range.range.contains(&idx))
+                    .map(|range| range.material),
+                _ => {
+                    // Some object have convex shape colliders, they're not provide any
+                    // useful info about the point of impact, so we have to use first
+                    // available material.
+                    ranges.first().map(|first_range| first_range.material)
+                }
+            })
+    }
+
     pub fn play_environment_sound(
         &self,
         graph: &mut Graph,
@@ -256,28 +492,7 @@ impl SoundManager {
         rolloff_factor: f32,
         radius: f32,
     ) {
-        let material = self.sound_map.ranges_of(collider).and_then(|ranges| {
-            match feature {
-                FeatureId::Face(idx) => {
-                    let mut material = None;
-                    for range in ranges {
-                        if range.range.contains(&idx) {
-                            material = Some(range.material);
-                            break;
-                        }
-                    }
-                    material
-                }
-                _ => {
-                    // Some object have convex shape colliders, they're not provide any
-                    // useful info about the point of impact, so we have to use first
-                    // available material.
-                    ranges.first().map(|first_range| first_range.material)
-                }
-            }
-        });
-
-        if let Some(material) = material {
+        if let Some(material) = self.material_of(collider, feature) {
             if let Some(map) = self.sound_base.material_to_sound.get(&material) {
                 if let Some(sound_list) = map.get(&sound_kind) {
                     if let Some(sound) = sound_list.choose(&mut rand::thread_rng()) {
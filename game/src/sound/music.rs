@@ -0,0 +1,208 @@
+//! Background music player. Music is non-spatial and tracks the music bus of the mixer. Tracks
+//! are defined in `data/configs/music.ron`.
+
+use crate::sound::{Mixer, SoundBus};
+use fyrox::{
+    core::{futures::executor::block_on, pool::Handle},
+    engine::resource_manager::ResourceManager,
+    lazy_static::lazy_static,
+    scene::{
+        base::BaseBuilder,
+        graph::Graph,
+        node::Node,
+        sound::{SoundBuilder, Status},
+    },
+    utils::log::Log,
+};
+use serde::Deserialize;
+use std::{
+    fs::File,
+    path::{Path, PathBuf},
+};
+
+#[derive(Deserialize, Debug)]
+pub struct MusicDefinition {
+    pub ambient: PathBuf,
+    /// Track played while bots are engaging the player, `None` keeps the ambient track playing
+    /// all the time.
+    #[serde(default)]
+    pub combat: Option<PathBuf>,
+}
+
+impl MusicDefinition {
+    pub fn new() -> Self {
+        let file = File::open("data/configs/music.ron").unwrap();
+        ron::de::from_reader(file).unwrap()
+    }
+}
+
+lazy_static! {
+    static ref DEFINITION: MusicDefinition = MusicDefinition::new();
+}
+
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum MusicKind {
+    Ambient,
+    Combat,
+}
+
+impl MusicKind {
+    pub fn track(self) -> &'static Path {
+        match self {
+            MusicKind::Ambient => &DEFINITION.ambient,
+            MusicKind::Combat => DEFINITION.combat.as_ref().unwrap_or(&DEFINITION.ambient),
+        }
+    }
+}
+
+#[derive(Debug)]
+struct MusicTrack {
+    path: PathBuf,
+    source: Handle<Node>,
+    // Fade factor in [0; 1] range, multiplied with music bus gain.
+    fade: f32,
+    // Signed speed of fading per second.
+    fade_speed: f32,
+}
+
+#[derive(Default, Debug)]
+pub struct MusicPlayer {
+    current: Option<MusicTrack>,
+    fading_out: Vec<MusicTrack>,
+    kind: Option<MusicKind>,
+    combat_cooldown: f32,
+}
+
+impl MusicPlayer {
+    pub const CROSSFADE_TIME: f32 = 2.0;
+    /// Amount of seconds the combat track will keep playing after last engagement.
+    pub const COMBAT_COOLDOWN: f32 = 10.0;
+    /// Name of sound sources of music tracks, it allows to find the sources saved with a scene.
+    const SOURCE_NAME: &'static str = "MusicTrack";
+
+    /// Removes music sources saved with the scene. The player itself is not saved, so it would
+    /// start a new track while the old one keeps looping.
+    pub fn remove_saved_sources(graph: &mut Graph) {
+        let sources = graph
+            .pair_iter()
+            .filter(|(_, node)| node.name() == Self::SOURCE_NAME)
+            .map(|(handle, _)| handle)
+            .collect::<Vec<_>>();
+        for source in sources {
+            graph.remove_node(source);
+        }
+    }
+
+    /// Starts playing the track, current track (if any) will fade out during `fade_time`
+    /// seconds while the new one fades in. A track that is still fading out is faded back in
+    /// from its current position instead of being restarted.
+    pub fn play<P: AsRef<Path>>(
+        &mut self,
+        graph: &mut Graph,
+        resource_manager: &ResourceManager,
+        track: P,
+        fade_time: f32,
+    ) {
+        let fade_speed = 1.0 / fade_time.max(f32::EPSILON);
+
+        if let Some(current) = self.current.as_mut() {
+            if current.path == track.as_ref() {
+                current.fade_speed = fade_speed;
+                return;
+            }
+        }
+
+        if let Some(mut current) = self.current.take() {
+            current.fade_speed = -fade_speed;
+            self.fading_out.push(current);
+        }
+
+        if let Some(index) = self
+            .fading_out
+            .iter()
+            .position(|fading| fading.path == track.as_ref())
+        {
+            let mut fading = self.fading_out.remove(index);
+            fading.fade_speed = fade_speed;
+            self.current = Some(fading);
+            return;
+        }
+
+        match block_on(resource_manager.request_sound_buffer(track.as_ref())) {
+            Ok(buffer) => {
+                let source = SoundBuilder::new(BaseBuilder::new().with_name(Self::SOURCE_NAME))
+                    .with_buffer(buffer.into())
+                    .with_looping(true)
+                    .with_status(Status::Playing)
+                    .with_spatial_blend(0.0)
+                    .with_gain(0.0)
+                    .build(graph);
+
+                self.current = Some(MusicTrack {
+                    path: track.as_ref().to_owned(),
+                    source,
+                    fade: 0.0,
+                    fade_speed,
+                });
+            }
+            Err(e) => Log::err(format!(
+                "Unable to play music track {:?}. Reason: {:?}",
+                track.as_ref(),
+                e
+            )),
+        }
+    }
+
+    pub fn update(
+        &mut self,
+        graph: &mut Graph,
+        resource_manager: &ResourceManager,
+        mixer: &Mixer,
+        in_combat: bool,
+        dt: f32,
+    ) {
+        if in_combat {
+            self.combat_cooldown = Self::COMBAT_COOLDOWN;
+        } else {
+            self.combat_cooldown = (self.combat_cooldown - dt).max(0.0);
+        }
+
+        let desired_kind = if self.combat_cooldown > 0.0 {
+            MusicKind::Combat
+        } else {
+            MusicKind::Ambient
+        };
+
+        if self.kind != Some(desired_kind) {
+            self.kind = Some(desired_kind);
+            self.play(
+                graph,
+                resource_manager,
+                desired_kind.track(),
+                Self::CROSSFADE_TIME,
+            );
+        }
+
+        let bus_gain = mixer.bus_gain(SoundBus::Music);
+
+        if let Some(current) = self.current.as_mut() {
+            current.fade = (current.fade + current.fade_speed * dt).min(1.0);
+            graph[current.source]
+                .as_sound_mut()
+                .set_gain(current.fade * bus_gain);
+        }
+
+        self.fading_out.retain_mut(|track| {
+            track.fade = (track.fade + track.fade_speed * dt).max(0.0);
+            if track.fade > 0.0 {
+                graph[track.source]
+                    .as_sound_mut()
+                    .set_gain(track.fade * bus_gain);
+                true
+            } else {
+                graph.remove_node(track.source);
+                false
+            }
+        });
+    }
+}
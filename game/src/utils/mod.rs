@@ -1,3 +1,4 @@
+use crate::character::try_get_character_ref;
 use fyrox::{
     animation::{
         machine::{MachineLayer, PoseNode, State},
@@ -6,6 +7,7 @@ use fyrox::{
     asset::core::rand::Rng,
     core::{
         algebra::{Point3, Unit, UnitQuaternion, Vector3},
+        math::Matrix4Ext,
         pool::Handle,
     },
     engine::resource_manager::ResourceManager,
@@ -15,13 +17,14 @@ use fyrox::{
         animation::AnimationPlayer,
         base::BaseBuilder,
         camera::{CameraBuilder, SkyBoxBuilder},
+        collider::{Collider, ColliderShape},
         graph::Graph,
         node::Node,
         sound::{self, context::SoundContext, listener::ListenerBuilder},
         transform::TransformBuilder,
         Scene,
     },
-    utils::log::Log,
+    utils::{log::Log, navmesh::Navmesh},
 };
 use std::collections::HashMap;
 
@@ -145,13 +148,115 @@ pub async fn create_camera(
     .build(graph)
 }
 
+/// Checks whether the collider is a part of the environment that blocks line of sight and sounds.
+/// Sensors (triggers, pickup areas), capsules and hit boxes of characters are not obstacles.
+pub fn is_obstacle(graph: &Graph, collider: Handle<Node>) -> bool {
+    let collider_ref = match graph.try_get(collider).and_then(|n| n.cast::<Collider>()) {
+        Some(collider_ref) => collider_ref,
+        None => return false,
+    };
+
+    if collider_ref.is_sensor() || matches!(collider_ref.shape(), ColliderShape::Capsule(_)) {
+        return false;
+    }
+
+    // Hit boxes are attached to bones of characters.
+    let mut parent = collider_ref.parent();
+    while let Some(parent_ref) = graph.try_get(parent) {
+        if try_get_character_ref(parent, graph).is_some() {
+            return false;
+        }
+        parent = parent_ref.parent();
+    }
+
+    true
+}
+
+/// Returns a point on the triangle that is closest to the given point.
+pub fn closest_point_on_triangle(
+    p: Vector3<f32>,
+    a: Vector3<f32>,
+    b: Vector3<f32>,
+    c: Vector3<f32>,
+) -> Vector3<f32> {
+    let ab = b - a;
+    let ac = c - a;
+    let ap = p - a;
+    let d1 = ab.dot(&ap);
+    let d2 = ac.dot(&ap);
+    if d1 <= 0.0 && d2 <= 0.0 {
+        return a;
+    }
+
+    let bp = p - b;
+    let d3 = ab.dot(&bp);
+    let d4 = ac.dot(&bp);
+    if d3 >= 0.0 && d4 <= d3 {
+        return b;
+    }
+
+    let vc = d1 * d4 - d3 * d2;
+    if vc <= 0.0 && d1 >= 0.0 && d3 <= 0.0 {
+        return a + ab.scale(d1 / (d1 - d3));
+    }
+
+    let cp = p - c;
+    let d5 = ab.dot(&cp);
+    let d6 = ac.dot(&cp);
+    if d6 >= 0.0 && d5 <= d6 {
+        return c;
+    }
+
+    let vb = d5 * d2 - d1 * d6;
+    if vb <= 0.0 && d2 >= 0.0 && d6 <= 0.0 {
+        return a + ac.scale(d2 / (d2 - d6));
+    }
+
+    let va = d3 * d6 - d5 * d4;
+    if va <= 0.0 && (d4 - d3) >= 0.0 && (d5 - d6) >= 0.0 {
+        return b + (c - b).scale((d4 - d3) / ((d4 - d3) + (d5 - d6)));
+    }
+
+    let denom = 1.0 / (va + vb + vc);
+    a + ab.scale(vb * denom) + ac.scale(vc * denom)
+}
+
+/// Projects the point onto the surface of the navmesh. Returns `None` if the navmesh is empty.
+pub fn closest_point_on_navmesh(navmesh: &Navmesh, point: Vector3<f32>) -> Option<Vector3<f32>> {
+    let vertices = navmesh.vertices();
+    navmesh
+        .triangles()
+        .iter()
+        .map(|triangle| {
+            let [a, b, c] = triangle.0;
+            closest_point_on_triangle(
+                point,
+                vertices[a as usize].position(),
+                vertices[b as usize].position(),
+                vertices[c as usize].position(),
+            )
+        })
+        .min_by(|a, b| {
+            a.metric_distance(&point)
+                .total_cmp(&b.metric_distance(&point))
+        })
+}
+
+/// Switches sound context to HRTF renderer. Falls back to default (stereo) renderer if HRIR
+/// sphere cannot be loaded.
 pub fn use_hrtf(context: &mut SoundContext) {
-    let hrtf_sphere =
-        fyrox::scene::sound::HrirSphere::from_file("data/sounds/hrtf.bin", sound::SAMPLE_RATE)
-            .unwrap();
-    context.set_renderer(fyrox::scene::sound::Renderer::HrtfRenderer(
-        fyrox::scene::sound::HrtfRenderer::new(hrtf_sphere),
-    ));
+    match fyrox::scene::sound::HrirSphere::from_file("data/sounds/hrtf.bin", sound::SAMPLE_RATE) {
+        Ok(hrtf_sphere) => context.set_renderer(fyrox::scene::sound::Renderer::HrtfRenderer(
+            fyrox::scene::sound::HrtfRenderer::new(hrtf_sphere),
+        )),
+        Err(e) => {
+            Log::warn(format!(
+                "Unable to load HRIR sphere, falling back to stereo. Reason: {:?}",
+                e
+            ));
+            context.set_renderer(fyrox::scene::sound::Renderer::Default);
+        }
+    }
 }
 
 pub fn create_play_animation_state(
@@ -191,3 +296,80 @@ pub fn fetch_animation_container_mut(
         .unwrap()
         .animations_mut()
 }
+
+fn global_rotation(graph: &Graph, node: Handle<Node>) -> UnitQuaternion<f32> {
+    UnitQuaternion::from_matrix(&graph[node].global_transform().basis())
+}
+
+/// Analytic two-bone IK (shoulder-elbow-wrist like chains). Rotates `root` and `middle` bones so
+/// the `end` bone reaches `target` point. `weight` blends between animated and solved poses.
+pub fn solve_two_bone_ik(
+    graph: &mut Graph,
+    root: Handle<Node>,
+    middle: Handle<Node>,
+    end: Handle<Node>,
+    target: Vector3<f32>,
+    weight: f32,
+) {
+    if weight <= 0.0
+        || [root, middle, end]
+            .iter()
+            .any(|h| graph.try_get(*h).is_none())
+    {
+        return;
+    }
+
+    let a = graph[root].global_position();
+    let b = graph[middle].global_position();
+    let c = graph[end].global_position();
+
+    let lab = (b - a).norm();
+    let lcb = (b - c).norm();
+    if lab <= f32::EPSILON || lcb <= f32::EPSILON {
+        return;
+    }
+    // Keep the chain slightly bent, fully stretched chain has no defined bend axis.
+    let eps = 0.001;
+    let lat = (target - a).norm().clamp(eps, lab + lcb - eps);
+
+    let dir = |v: Vector3<f32>| v.try_normalize(f32::EPSILON).unwrap_or_default();
+    let safe_acos = |x: f32| x.clamp(-1.0, 1.0).acos();
+
+    // Current angles.
+    let ac_ab_0 = safe_acos(dir(c - a).dot(&dir(b - a)));
+    let ba_bc_0 = safe_acos(dir(a - b).dot(&dir(c - b)));
+    let ac_at_0 = safe_acos(dir(c - a).dot(&dir(target - a)));
+
+    // Desired angles (law of cosines).
+    let ac_ab_1 = safe_acos((lcb * lcb - lab * lab - lat * lat) / (-2.0 * lab * lat));
+    let ba_bc_1 = safe_acos((lat * lat - lab * lab - lcb * lcb) / (-2.0 * lab * lcb));
+
+    let axis0 = dir((c - a).cross(&(b - a)));
+    if axis0.norm_squared() == 0.0 {
+        return;
+    }
+    let axis1 = dir((c - a).cross(&(target - a)));
+
+    let a_gr_inv = global_rotation(graph, root).inverse();
+    let b_gr_inv = global_rotation(graph, middle).inverse();
+
+    let r0 =
+        UnitQuaternion::from_axis_angle(&Unit::new_normalize(a_gr_inv * axis0), ac_ab_1 - ac_ab_0);
+    let r1 =
+        UnitQuaternion::from_axis_angle(&Unit::new_normalize(b_gr_inv * axis0), ba_bc_1 - ba_bc_0);
+    let r2 = if axis1.norm_squared() > 0.0 {
+        UnitQuaternion::from_axis_angle(&Unit::new_normalize(a_gr_inv * axis1), ac_at_0)
+    } else {
+        UnitQuaternion::identity()
+    };
+
+    let weight = weight.min(1.0);
+
+    let root_transform = graph[root].local_transform_mut();
+    let root_rotation = **root_transform.rotation();
+    root_transform.set_rotation(root_rotation.slerp(&(root_rotation * r0 * r2), weight));
+
+    let middle_transform = graph[middle].local_transform_mut();
+    let middle_rotation = **middle_transform.rotation();
+    middle_transform.set_rotation(middle_rotation.slerp(&(middle_rotation * r1), weight));
+}
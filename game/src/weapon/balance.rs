@@ -0,0 +1,124 @@
+//! Central table of weapon balance multipliers, it allows to tune weapons without touching
+//! their definitions or recompiling the game.
+
+use crate::{level::item::ItemKind, weapon::definition::WeaponKind};
+use fyrox::lazy_static::lazy_static;
+use serde::Deserialize;
+use std::{collections::HashMap, fs::File};
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct WeaponBalanceEntry {
+    /// Multiplier of damage of each projectile or ray shot by the weapon.
+    #[serde(default = "default_scale")]
+    pub damage_scale: f32,
+    /// Multiplier of shots per second, values above one make the weapon shoot faster.
+    #[serde(default = "default_scale")]
+    pub fire_rate_scale: f32,
+    /// Multiplier of vertical and horizontal recoil angles.
+    #[serde(default = "default_scale")]
+    pub recoil_scale: f32,
+}
+
+fn default_scale() -> f32 {
+    1.0
+}
+
+impl Default for WeaponBalanceEntry {
+    fn default() -> Self {
+        Self {
+            damage_scale: 1.0,
+            fire_rate_scale: 1.0,
+            recoil_scale: 1.0,
+        }
+    }
+}
+
+#[derive(Deserialize, Default)]
+pub struct WeaponBalance {
+    #[serde(default)]
+    weapons: HashMap<WeaponKind, WeaponBalanceEntry>,
+    /// Amount of ammo given by an ammo item picked up from the level.
+    #[serde(default)]
+    ammo_pickup: HashMap<ItemKind, u32>,
+}
+
+impl WeaponBalance {
+    pub fn new() -> Self {
+        let file = File::open("data/configs/balance.ron").unwrap();
+        ron::de::from_reader(file).unwrap()
+    }
+
+    fn entry(&self, kind: WeaponKind) -> &WeaponBalanceEntry {
+        lazy_static! {
+            static ref DEFAULT: WeaponBalanceEntry = WeaponBalanceEntry::default();
+        }
+
+        self.weapons.get(&kind).unwrap_or(&DEFAULT)
+    }
+
+    fn ammo_pickup(&self, ammo_item: ItemKind) -> u32 {
+        self.ammo_pickup.get(&ammo_item).cloned().unwrap_or(24)
+    }
+
+    /// Returns balance of the given weapon, weapons missing in the table are left as is.
+    pub fn weapon(kind: WeaponKind) -> &'static WeaponBalanceEntry {
+        BALANCE.entry(kind)
+    }
+
+    pub fn ammo_pickup_amount(ammo_item: ItemKind) -> u32 {
+        BALANCE.ammo_pickup(ammo_item)
+    }
+}
+
+lazy_static! {
+    static ref BALANCE: WeaponBalance = WeaponBalance::new();
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::weapon::projectile::Damage;
+
+    #[test]
+    fn test_empty_table_keeps_weapons_as_is() {
+        let balance: WeaponBalance = ron::de::from_str("()").unwrap();
+
+        let entry = balance.entry(WeaponKind::M4);
+        assert_eq!(entry.damage_scale, 1.0);
+        assert_eq!(entry.fire_rate_scale, 1.0);
+        assert_eq!(entry.recoil_scale, 1.0);
+        assert_eq!(balance.ammo_pickup(ItemKind::Ammo), 24);
+    }
+
+    #[test]
+    fn test_custom_table_changes_damage() {
+        let balance: WeaponBalance = ron::de::from_str(
+            "(weapons: { M4: (damage_scale: 2.0) }, ammo_pickup: { ShotgunShells: 4 })",
+        )
+        .unwrap();
+
+        let m4 = balance.entry(WeaponKind::M4);
+        assert_eq!(Damage::Point(19.0).scale(m4.damage_scale).amount(), 38.0);
+        assert_eq!(m4.fire_rate_scale, 1.0);
+        assert_eq!(m4.recoil_scale, 1.0);
+
+        // Weapons missing in the table are not affected.
+        let ak47 = balance.entry(WeaponKind::Ak47);
+        assert_eq!(Damage::Point(19.0).scale(ak47.damage_scale).amount(), 19.0);
+
+        assert_eq!(balance.ammo_pickup(ItemKind::ShotgunShells), 4);
+        assert_eq!(balance.ammo_pickup(ItemKind::Ammo), 24);
+    }
+
+    #[test]
+    fn test_shipped_table_is_neutral() {
+        let path = concat!(env!("CARGO_MANIFEST_DIR"), "/../data/configs/balance.ron");
+        let balance: WeaponBalance = ron::de::from_reader(File::open(path).unwrap()).unwrap();
+
+        for entry in balance.weapons.values() {
+            assert_eq!(entry.damage_scale, 1.0);
+            assert_eq!(entry.fire_rate_scale, 1.0);
+            assert_eq!(entry.recoil_scale, 1.0);
+        }
+    }
+}
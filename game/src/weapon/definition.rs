@@ -31,6 +31,7 @@ pub enum WeaponKind {
     PlasmaRifle = 2,
     Glock = 3,
     RailGun = 4,
+    Shotgun = 5,
 }
 
 #[derive(Copy, Clone, PartialEq, Eq, Debug, Deserialize, Hash)]
@@ -55,6 +56,7 @@ impl WeaponKind {
             WeaponKind::PlasmaRifle => ItemKind::PlasmaGun,
             WeaponKind::Glock => ItemKind::Glock,
             WeaponKind::RailGun => ItemKind::RailGun,
+            WeaponKind::Shotgun => ItemKind::Shotgun,
         }
     }
 }
@@ -65,9 +67,19 @@ pub enum WeaponProjectile {
     /// For high-speed "projectiles".
     Ray {
         damage: Damage,
+        /// Amount of surfaces or actors the ray can pass through before it stops.
+        #[serde(default)]
+        penetration: u8,
+        /// Damage multiplier applied for each surface or actor the ray has passed through.
+        #[serde(default = "default_penetration_damage_scale")]
+        penetration_damage_scale: f32,
     },
 }
 
+fn default_penetration_damage_scale() -> f32 {
+    0.6
+}
+
 #[derive(Deserialize, Debug)]
 pub struct WeaponDefinition {
     pub model: String,
@@ -82,9 +94,72 @@ pub struct WeaponDefinition {
     pub h_recoil: (f32, f32),
     pub shot_effect: ShotEffect,
     pub base_critical_shot_probability: f32,
+    /// Kind of item that is used as ammo.
+    #[serde(default = "default_ammo_item")]
+    pub ammo_item: ItemKind,
+    /// Amount of projectiles spawned per shot. Damage of the projectile is dealt per pellet, so
+    /// weapons with many pellets define reduced damage to be lethal only when most pellets hit.
+    #[serde(default = "default_pellet_count")]
+    pub pellet_count: u32,
+    /// Half-angle (in degrees) of a cone in which pellets are spread.
+    #[serde(default)]
+    pub pellet_spread: f32,
+    /// Distance (in meters) weapon model is pushed back on each shot. Purely cosmetic.
+    #[serde(default = "default_kickback")]
+    pub kickback: f32,
+    /// Heat (fraction of max heat) produced by each shot. Zero means that the weapon does not
+    /// heat up at all, which is the case for ballistic weapons.
+    #[serde(default)]
+    pub heat_per_shot: f32,
+    /// Camera zoom applied while aiming down sights, 1.0 means no zoom.
+    #[serde(default = "default_aim_zoom")]
+    pub aim_zoom: f32,
+    /// Amplitude (in degrees) of the sight sway while aiming down sights, the player can steady
+    /// it by holding breath. Zero disables the sway.
+    #[serde(default)]
+    pub scope_sway: f32,
+    /// Position of a grip for off-hand of a character in local coordinates of the weapon. Used
+    /// only if the weapon model has no "LeftHandGrip" node, `None` means that the weapon is
+    /// held with one hand.
+    #[serde(default)]
+    pub left_hand_grip: Option<(f32, f32, f32)>,
+    /// Whether the weapon can be fitted with a suppressor.
+    #[serde(default)]
+    pub suppressor: bool,
+    /// Sounds of suppressed shots, if empty usual shot sounds are played quieter.
+    #[serde(default)]
+    pub suppressed_shot_sounds: Vec<String>,
+    /// Multiplier of damage that is used to push hit actors back, see
+    /// [`crate::character::CharacterCommand::Knockback`].
+    #[serde(default = "default_knockback_scale")]
+    pub knockback_scale: f32,
+}
+
+fn default_ammo_item() -> ItemKind {
+    ItemKind::Ammo
+}
+
+fn default_pellet_count() -> u32 {
+    1
+}
+
+fn default_kickback() -> f32 {
+    0.04
+}
+
+fn default_aim_zoom() -> f32 {
+    1.25
+}
+
+fn default_knockback_scale() -> f32 {
+    1.0
 }
 
 impl WeaponDefinition {
+    pub fn uses_heat(&self) -> bool {
+        self.heat_per_shot > 0.0
+    }
+
     pub fn ammo_indicator_offset(&self) -> Vector3<f32> {
         Vector3::new(
             self.ammo_indicator_offset.0,
@@ -93,6 +168,10 @@ impl WeaponDefinition {
         )
     }
 
+    pub fn left_hand_grip(&self) -> Option<Vector3<f32>> {
+        self.left_hand_grip.map(|(x, y, z)| Vector3::new(x, y, z))
+    }
+
     pub fn gen_v_recoil_angle(&self) -> f32 {
         fyrox::rand::thread_rng()
             .gen_range(self.v_recoil.0.to_radians()..self.v_recoil.1.to_radians())
@@ -102,6 +181,35 @@ impl WeaponDefinition {
         fyrox::rand::thread_rng()
             .gen_range(self.h_recoil.0.to_radians()..self.h_recoil.1.to_radians())
     }
+
+    /// Generates random direction of a pellet within a spread cone around the given direction.
+    pub fn gen_pellet_direction(&self, direction: Vector3<f32>) -> Vector3<f32> {
+        gen_cone_direction(direction, self.pellet_spread)
+    }
+}
+
+/// Generates random direction within a cone with the given half-angle (in degrees) around the
+/// given direction.
+pub fn gen_cone_direction(direction: Vector3<f32>, half_angle: f32) -> Vector3<f32> {
+    if half_angle <= 0.0 {
+        return direction;
+    }
+
+    let mut rng = fyrox::rand::thread_rng();
+
+    let side = direction
+        .cross(&Vector3::y())
+        .try_normalize(f32::EPSILON)
+        .unwrap_or_else(Vector3::x);
+    let up = side.cross(&direction);
+
+    let angle = rng.gen_range(0.0..half_angle.to_radians());
+    let roll = rng.gen_range(0.0..std::f32::consts::TAU);
+
+    (direction.scale(angle.cos())
+        + (side.scale(roll.cos()) + up.scale(roll.sin())).scale(angle.sin()))
+    .try_normalize(f32::EPSILON)
+    .unwrap_or(direction)
 }
 
 #[derive(Deserialize, Default)]
@@ -119,3 +227,34 @@ impl WeaponDefinitionContainer {
 lazy_static! {
     pub static ref DEFINITIONS: WeaponDefinitionContainer = WeaponDefinitionContainer::new();
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use std::{collections::HashSet, path::Path, str::FromStr};
+    use strum::VariantNames;
+
+    #[test]
+    fn test_each_weapon_has_own_existing_shot_sounds() {
+        let root = concat!(env!("CARGO_MANIFEST_DIR"), "/..");
+        let path = Path::new(root).join("data/configs/weapons.ron");
+        let definitions: WeaponDefinitionContainer =
+            ron::de::from_reader(File::open(path).unwrap()).unwrap();
+
+        let mut used_sounds = HashSet::new();
+        for name in WeaponKind::VARIANTS {
+            let kind = WeaponKind::from_str(name).unwrap();
+            let definition = &definitions.map[&kind];
+
+            assert!(
+                !definition.shot_sounds.is_empty(),
+                "{:?} has no sounds",
+                kind
+            );
+            for sound in definition.shot_sounds.iter() {
+                assert!(Path::new(root).join(sound).exists(), "{} is missing", sound);
+                assert!(used_sounds.insert(sound.clone()), "{} is shared", sound);
+            }
+        }
+    }
+}
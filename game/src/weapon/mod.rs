@@ -8,11 +8,14 @@ use crate::{
     },
     current_level_mut, current_level_ref, effects,
     effects::EffectKind,
+    game_ref,
+    level::decal::{DecalContainer, HitMarks},
     level::trail::ShotTrail,
     sound::{SoundKind, SoundManager},
     weapon::{
+        balance::{WeaponBalance, WeaponBalanceEntry},
         definition::{ShotEffect, WeaponDefinition, WeaponKind, WeaponProjectile},
-        projectile::{Damage, Projectile},
+        projectile::{Damage, Projectile, ProjectilePool},
         sight::{LaserSight, SightReaction},
     },
     CollisionGroups, Decal,
@@ -21,7 +24,7 @@ use fyrox::{
     core::{
         algebra::{Matrix3, Point3, UnitQuaternion, Vector3},
         color::Color,
-        math::{ray::Ray, vector_to_quat, Matrix4Ext},
+        math::{ray::Ray, vector_to_quat, Matrix4Ext, Vector3Ext},
         pool::Handle,
         reflect::prelude::*,
         sstorage::ImmutableString,
@@ -44,6 +47,7 @@ use fyrox::{
             MeshBuilder, RenderPath,
         },
         node::{Node, TypeUuidProvider},
+        pivot::PivotBuilder,
         rigidbody::RigidBody,
         transform::TransformBuilder,
         Scene,
@@ -51,8 +55,12 @@ use fyrox::{
     script::{Script, ScriptContext, ScriptDeinitContext, ScriptTrait},
     utils::{self, log::Log},
 };
-use std::hash::{Hash, Hasher};
+use std::{
+    hash::{Hash, Hasher},
+    sync::Arc,
+};
 
+pub mod balance;
 pub mod definition;
 pub mod projectile;
 pub mod sight;
@@ -74,6 +82,19 @@ pub struct Weapon {
     flash_light_enabled: bool,
     pub enabled: bool,
 
+    /// Suppressed weapons make much less noise when shooting.
+    #[visit(optional)]
+    suppressed: bool,
+
+    #[reflect(hidden)]
+    #[visit(optional)]
+    heat: f32,
+
+    // Set when the weapon reaches max heat, cleared when it cools down enough.
+    #[reflect(hidden)]
+    #[visit(optional)]
+    overheated: bool,
+
     #[reflect(hidden)]
     laser_sight: LaserSight,
 
@@ -95,6 +116,152 @@ pub struct Weapon {
     #[reflect(hidden)]
     #[visit(skip)]
     self_handle: Handle<Node>,
+
+    // Optional point for off-hand of a character, found by name in the weapon model.
+    #[reflect(hidden)]
+    #[visit(skip)]
+    left_hand_grip: Handle<Node>,
+
+    #[reflect(hidden)]
+    #[visit(skip)]
+    kickback: Kickback,
+
+    #[reflect(hidden)]
+    #[visit(skip)]
+    accuracy: Accuracy,
+
+    #[reflect(hidden)]
+    #[visit(skip)]
+    sway: Sway,
+}
+
+/// Procedural kick of a weapon model, simulated as a damped spring pulling the model back to
+/// its rest position.
+#[derive(Default, Debug, Clone)]
+struct Kickback {
+    offset: f32,
+    velocity: f32,
+    // Offset that was applied to local position of the weapon last frame.
+    applied: Vector3<f32>,
+}
+
+impl Kickback {
+    const STIFFNESS: f32 = 400.0;
+    const DAMPING: f32 = 30.0;
+    /// How much the weapon rises relative to the backward movement.
+    const RISE_FACTOR: f32 = 0.35;
+
+    fn kick(&mut self, amplitude: f32) {
+        // Immediate displacement looks snappier than an impulse.
+        self.offset = (self.offset + amplitude).min(amplitude * 2.0);
+        self.velocity = 0.0;
+    }
+
+    fn update(&mut self, node: &mut Node, dt: f32) {
+        let acceleration = -Self::STIFFNESS * self.offset - Self::DAMPING * self.velocity;
+        self.velocity += acceleration * dt;
+        self.offset += self.velocity * dt;
+
+        let transform = node.local_transform_mut();
+        let rest_position = **transform.position() - self.applied;
+        self.applied = transform
+            .rotation()
+            .transform_vector(&Vector3::new(0.0, Self::RISE_FACTOR, -1.0).scale(self.offset));
+        transform.set_position(rest_position + self.applied);
+    }
+}
+
+/// Normalized spread of a weapon, it is not applied to shots and used only for visual feedback
+/// (for example to animate a crosshair).
+#[derive(Default, Debug, Clone)]
+struct Accuracy {
+    spread: f32,
+    is_moving: bool,
+    is_aiming: bool,
+}
+
+impl Accuracy {
+    const IDLE_SPREAD: f32 = 0.25;
+    const AIM_SPREAD: f32 = 0.0;
+    /// Added on top of the base spread while owner moves.
+    const MOVEMENT_SPREAD: f32 = 0.35;
+    const SHOT_SPREAD: f32 = 0.2;
+    /// Fraction of difference between current and base spread restored per second.
+    const RECOVERY_RATE: f32 = 6.0;
+
+    fn on_shot(&mut self) {
+        self.spread = (self.spread + Self::SHOT_SPREAD).min(1.0);
+    }
+
+    fn update(&mut self, dt: f32) {
+        let mut base = if self.is_aiming {
+            Self::AIM_SPREAD
+        } else {
+            Self::IDLE_SPREAD
+        };
+        if self.is_moving {
+            base += Self::MOVEMENT_SPREAD;
+        }
+        self.spread += (base - self.spread) * (Self::RECOVERY_RATE * dt).min(1.0);
+        self.spread = self.spread.clamp(0.0, 1.0);
+    }
+}
+
+/// Procedural sway of a weapon model, it lags behind when the owner looks around and bobs while
+/// the owner moves. Purely cosmetic.
+#[derive(Default, Debug, Clone)]
+struct Sway {
+    offset: Vector3<f32>,
+    last_look: Option<Vector3<f32>>,
+    bob_phase: f32,
+    // Offset that was applied to local position of the weapon last frame.
+    applied: Vector3<f32>,
+}
+
+impl Sway {
+    const LOOK_FACTOR: f32 = 0.05;
+    const MAX_LOOK_OFFSET: f32 = 0.03;
+    const BOB_AMPLITUDE: f32 = 0.006;
+    const BOB_FREQUENCY: f32 = 9.0;
+    /// Fraction of sway that remains while aiming.
+    const AIM_FACTOR: f32 = 0.25;
+
+    fn update(&mut self, node: &mut Node, accuracy: &Accuracy, magnitude: f32, dt: f32) {
+        let look = node.look_vector();
+        let look_delta = self
+            .last_look
+            .map_or_else(Vector3::default, |last| look - last);
+        self.last_look = Some(look);
+
+        let mut target = Vector3::new(
+            -look_delta.dot(&node.side_vector()),
+            -look_delta.dot(&node.up_vector()),
+            0.0,
+        )
+        .scale(Self::LOOK_FACTOR / dt.max(f32::EPSILON))
+        .cap_magnitude(Self::MAX_LOOK_OFFSET);
+
+        if accuracy.is_moving {
+            self.bob_phase += Self::BOB_FREQUENCY * dt;
+            target += Vector3::new(
+                self.bob_phase.cos(),
+                (2.0 * self.bob_phase).sin().abs(),
+                0.0,
+            )
+            .scale(Self::BOB_AMPLITUDE);
+        }
+
+        if accuracy.is_aiming {
+            target = target.scale(Self::AIM_FACTOR);
+        }
+
+        self.offset.follow(&target.scale(magnitude), 0.1);
+
+        let transform = node.local_transform_mut();
+        let rest_position = **transform.position() - self.applied;
+        self.applied = transform.rotation().transform_vector(&self.offset);
+        transform.set_position(rest_position + self.applied);
+    }
 }
 
 impl Default for Weapon {
@@ -112,9 +279,16 @@ impl Default for Weapon {
             flash_light: Default::default(),
             flash_light_enabled: false,
             enabled: true,
+            suppressed: false,
+            heat: 0.0,
+            overheated: false,
             laser_sight: Default::default(),
             shot_request: None,
             self_handle: Default::default(),
+            left_hand_grip: Default::default(),
+            kickback: Default::default(),
+            accuracy: Default::default(),
+            sway: Default::default(),
         }
     }
 }
@@ -128,7 +302,7 @@ pub struct Hit {
     pub collider: Handle<Node>,
     pub feature: FeatureId,
     pub hit_box: Option<HitBox>,
-    pub query_buffer: Vec<Intersection>,
+    pub query_buffer: Arc<[Intersection]>,
 }
 
 impl PartialEq for Hit {
@@ -151,7 +325,33 @@ impl Hash for Hit {
 
 impl Eq for Hit {}
 
+/// Picks intersections hit by a round that can pierce `penetration` surfaces or actors. `owners`
+/// are actors (`Handle::NONE` for level geometry) of intersections along the path of the round,
+/// sorted by distance. The round hits an actor only once, even if it crosses several of its hit
+/// boxes. Returns indices of hit intersections.
+fn pierced_intersections(owners: &[Handle<Node>], penetration: u8) -> Vec<usize> {
+    let mut hits: Vec<usize> = Vec::new();
+    for (i, &owner) in owners.iter().enumerate() {
+        if hits.len() > penetration as usize {
+            break;
+        }
+        if owner.is_some() && hits.iter().any(|&hit| owners[hit] == owner) {
+            continue;
+        }
+        hits.push(i);
+    }
+    hits
+}
+
 impl Weapon {
+    const SUPPRESSED_SHOT_GAIN: f32 = 0.3;
+    const SUPPRESSED_SHOT_RADIUS: f32 = 1.0;
+    /// Fraction of max heat dissipated per second.
+    const HEAT_COOLING_RATE: f32 = 0.25;
+    /// Overheated weapon can't shoot until its heat drops below this value.
+    const OVERHEAT_RECOVERY_THRESHOLD: f32 = 0.3;
+    const VENT_SOUND: &'static str = "data/sounds/lamp_buzz_idle.wav";
+
     /// Checks intersection of given ray with actors and environment.
     pub fn ray_hit(
         begin: Vector3<f32>,
@@ -161,10 +361,25 @@ impl Weapon {
         graph: &mut Graph,
         ignored_collider: Handle<Node>,
     ) -> Option<Hit> {
+        Self::ray_hits(begin, end, shooter, actors, graph, ignored_collider, 0)
+            .into_iter()
+            .next()
+    }
+
+    /// Checks intersections of given ray with actors and environment for a round that can pierce
+    /// `penetration` surfaces or actors. Returns hits sorted by distance from ray origin.
+    pub fn ray_hits(
+        begin: Vector3<f32>,
+        end: Vector3<f32>,
+        shooter: Handle<Node>,
+        actors: &[Handle<Node>],
+        graph: &mut Graph,
+        ignored_collider: Handle<Node>,
+        penetration: u8,
+    ) -> Vec<Hit> {
         let physics = &mut graph.physics;
         let ray = Ray::from_two_points(begin, end);
 
-        // TODO: Avoid allocation.
         let mut query_buffer = Vec::default();
 
         physics.cast_ray(
@@ -180,56 +395,53 @@ impl Weapon {
             },
             &mut query_buffer,
         );
-
-        // List of hits sorted by distance from ray origin.
-        if let Some(hit) = query_buffer.iter().find(|i| i.collider != ignored_collider) {
-            let mut is_hitbox_hit = false;
-
-            // Check if there was an intersection with an actor.
-            'actor_loop: for &actor_handle in actors.iter() {
-                let character = character_ref(actor_handle, graph);
-                for hit_box in character.hit_boxes.iter() {
-                    if hit_box.collider == hit.collider {
-                        is_hitbox_hit = true;
-
-                        // Ignore intersections with owners.
-                        if shooter == actor_handle {
-                            continue 'actor_loop;
-                        }
-
-                        return Some(Hit {
-                            actor: actor_handle,
-                            who: shooter,
-                            position: hit.position.coords,
-                            normal: hit.normal,
-                            collider: hit.collider,
-                            feature: hit.feature,
-                            hit_box: Some(*hit_box),
-                            query_buffer,
-                        });
-                    }
-                }
+        query_buffer.retain(|intersection| intersection.collider != ignored_collider);
+
+        // Find actors of intersections, an intersection with the shooter stops the ray.
+        let mut owners = Vec::with_capacity(query_buffer.len());
+        for intersection in query_buffer.iter() {
+            let owner = actors.iter().find_map(|&actor_handle| {
+                character_ref(actor_handle, graph)
+                    .hit_boxes
+                    .iter()
+                    .find(|hit_box| hit_box.collider == intersection.collider)
+                    .map(|hit_box| (actor_handle, *hit_box))
+            });
+            if owner.map_or(false, |(actor_handle, _)| actor_handle == shooter) {
+                break;
             }
+            owners.push(owner);
+        }
 
-            if is_hitbox_hit {
-                None
-            } else {
-                Some(Hit {
-                    actor: Handle::NONE,
+        let actor_handles = owners
+            .iter()
+            .map(|owner| owner.map_or(Handle::NONE, |(actor_handle, _)| actor_handle))
+            .collect::<Vec<_>>();
+
+        // All hits of the ray share the same intersections.
+        let query_buffer: Arc<[Intersection]> = query_buffer.into();
+
+        pierced_intersections(&actor_handles, penetration)
+            .into_iter()
+            .map(|i| {
+                let intersection = &query_buffer[i];
+                Hit {
+                    actor: actor_handles[i],
                     who: shooter,
-                    position: hit.position.coords,
-                    normal: hit.normal,
-                    collider: hit.collider,
-                    feature: hit.feature,
-                    hit_box: None,
-                    query_buffer,
-                })
-            }
-        } else {
-            None
-        }
+                    position: intersection.position.coords,
+                    normal: intersection.normal,
+                    collider: intersection.collider,
+                    feature: intersection.feature,
+                    hit_box: owners[i].map(|(_, hit_box)| hit_box),
+                    query_buffer: query_buffer.clone(),
+                }
+            })
+            .collect()
     }
 
+    /// Shoots a ray that can pierce `penetration` surfaces or actors, each pierced one scales
+    /// damage of the ray by `penetration_damage_scale`. Returns hits sorted by distance from the
+    /// shot position.
     pub fn shoot_ray(
         graph: &mut Graph,
         resource_manager: &ResourceManager,
@@ -238,116 +450,46 @@ impl Weapon {
         begin: Vector3<f32>,
         end: Vector3<f32>,
         damage: Damage,
+        penetration: u8,
+        penetration_damage_scale: f32,
+        knockback_scale: f32,
         shot_effect: ShotEffect,
         sound_manager: &SoundManager,
         critical_shot_probability: f32,
-    ) -> Option<Hit> {
+        decals: &DecalContainer,
+        gore: bool,
+    ) -> Vec<Hit> {
         // Do immediate intersection test and solve it.
-        let (trail_len, hit_point, hit) = if let Some(hit) =
-            Weapon::ray_hit(begin, end, shooter, actors, graph, Default::default())
-        {
-            effects::create(
-                if hit.actor.is_some() {
-                    EffectKind::BloodSpray
-                } else {
-                    EffectKind::BulletImpact
-                },
-                graph,
-                resource_manager,
-                hit.position,
-                vector_to_quat(hit.normal),
-            );
+        let hits = Weapon::ray_hits(
+            begin,
+            end,
+            shooter,
+            actors,
+            graph,
+            Handle::NONE,
+            penetration,
+        );
 
-            sound_manager.play_environment_sound(
+        let mut damage = damage;
+        for hit in hits.iter() {
+            Self::apply_ray_hit(
                 graph,
-                hit.collider,
-                hit.feature,
-                hit.position,
-                SoundKind::Impact,
-                1.0,
-                1.0,
-                0.5,
-            );
-
-            if let Some(character) = try_get_character_mut(hit.actor, graph) {
-                character.push_command(CharacterCommand::Damage {
-                    who: hit.who,
-                    hitbox: hit.hit_box,
-                    amount: damage
-                        .scale(hit.hit_box.map_or(1.0, |h| h.damage_factor))
-                        .amount(),
-                    critical_shot_probability,
-                });
-            }
-
-            let dir = hit.position - begin;
-
-            let hit_collider_body = graph[hit.collider].parent();
-            let parent =
-                if let Some(collider_parent) = graph[hit_collider_body].cast_mut::<RigidBody>() {
-                    collider_parent.apply_force_at_point(
-                        dir.try_normalize(f32::EPSILON)
-                            .unwrap_or_default()
-                            .scale(30.0),
-                        hit.position,
-                    );
-                    hit_collider_body
-                } else {
-                    Default::default()
-                };
-
-            if let Some(hit_box) = hit.hit_box {
-                if let Some(bot) = try_get_bot_mut(hit.actor, graph) {
-                    bot.commands_queue.push_back(BotCommand::HandleImpact {
-                        handle: hit_box.bone,
-                        impact_point: hit.position,
-                        direction: dir,
-                    });
-                }
-            }
-
-            Decal::new_bullet_hole(
                 resource_manager,
-                graph,
-                hit.position,
-                hit.normal,
-                parent,
-                if hit.actor.is_some() {
-                    Color::opaque(160, 0, 0)
-                } else {
-                    Color::opaque(20, 20, 20)
-                },
+                begin,
+                hit,
+                damage,
+                knockback_scale,
+                sound_manager,
+                critical_shot_probability,
+                decals,
+                gore,
             );
+            damage = damage.scale(penetration_damage_scale);
+        }
 
-            // Add blood splatter on a surface behind an actor that was shot.
-            if try_get_character_ref(hit.actor, graph).is_some() {
-                for intersection in hit.query_buffer.iter() {
-                    if matches!(
-                        graph[intersection.collider].as_collider().shape(),
-                        ColliderShape::Trimesh(_)
-                    ) && intersection.position.coords.metric_distance(&hit.position) < 2.0
-                    {
-                        Decal::add_to_graph(
-                            graph,
-                            intersection.position.coords,
-                            dir,
-                            Handle::NONE,
-                            Color::opaque(255, 255, 255),
-                            Vector3::new(0.45, 0.45, 0.2),
-                            resource_manager.request_texture(
-                                "data/textures/decals/BloodSplatter_BaseColor.png",
-                            ),
-                        );
-
-                        break;
-                    }
-                }
-            }
-
-            (dir.norm(), hit.position, Some(hit))
-        } else {
-            (30.0, end, None)
-        };
+        let (trail_len, hit_point) = hits.last().map_or((30.0, end), |hit| {
+            ((hit.position - begin).norm(), hit.position)
+        });
 
         match shot_effect {
             ShotEffect::Smoke => {
@@ -415,7 +557,119 @@ impl Weapon {
             }
         }
 
-        hit
+        hits
+    }
+
+    fn apply_ray_hit(
+        graph: &mut Graph,
+        resource_manager: &ResourceManager,
+        begin: Vector3<f32>,
+        hit: &Hit,
+        damage: Damage,
+        knockback_scale: f32,
+        sound_manager: &SoundManager,
+        critical_shot_probability: f32,
+        decals: &DecalContainer,
+        gore: bool,
+    ) {
+        let marks = HitMarks::of_hit(hit.actor.is_some(), gore);
+
+        effects::create(
+            if marks.blood {
+                EffectKind::BloodSpray
+            } else {
+                EffectKind::BulletImpact
+            },
+            graph,
+            resource_manager,
+            hit.position,
+            vector_to_quat(hit.normal),
+        );
+
+        sound_manager.play_environment_sound(
+            graph,
+            hit.collider,
+            hit.feature,
+            hit.position,
+            SoundKind::Impact,
+            1.0,
+            1.0,
+            0.5,
+        );
+
+        let dir = hit.position - begin;
+
+        if let Some(character) = try_get_character_mut(hit.actor, graph) {
+            let amount = damage.scale(HitBox::damage_factor_of(hit.hit_box)).amount();
+            character.push_command(CharacterCommand::Damage {
+                who: hit.who,
+                hitbox: hit.hit_box,
+                amount,
+                critical_shot_probability,
+            });
+            character.push_command(CharacterCommand::Knockback {
+                who: hit.who,
+                direction: dir,
+                amount: amount * knockback_scale,
+            });
+        }
+
+        let hit_collider_body = graph[hit.collider].parent();
+        if let Some(collider_parent) = graph[hit_collider_body].cast_mut::<RigidBody>() {
+            collider_parent.apply_force_at_point(
+                dir.try_normalize(f32::EPSILON)
+                    .unwrap_or_default()
+                    .scale(30.0),
+                hit.position,
+            );
+        }
+
+        if let Some(hit_box) = hit.hit_box {
+            if let Some(bot) = try_get_bot_mut(hit.actor, graph) {
+                bot.commands_queue.push_back(BotCommand::HandleImpact {
+                    handle: hit_box.bone,
+                    impact_point: hit.position,
+                    direction: dir,
+                });
+            }
+        }
+
+        if marks.impact_decal {
+            let bullet_hole = Decal::new_impact(
+                resource_manager,
+                graph,
+                hit.position,
+                hit.normal,
+                hit.collider,
+                hit.actor.is_some(),
+            );
+            decals.register(bullet_hole, graph);
+        }
+
+        // Add blood splatter on a surface behind an actor that was shot.
+        if marks.blood && try_get_character_ref(hit.actor, graph).is_some() {
+            for intersection in hit.query_buffer.iter() {
+                if matches!(
+                    graph[intersection.collider].as_collider().shape(),
+                    ColliderShape::Trimesh(_)
+                ) && intersection.position.coords.metric_distance(&hit.position) < 2.0
+                {
+                    let splatter = Decal::add_to_graph(
+                        graph,
+                        intersection.position.coords,
+                        dir,
+                        Handle::NONE,
+                        Color::opaque(255, 255, 255),
+                        Vector3::new(0.45, 0.45, 0.2),
+                        resource_manager
+                            .request_texture("data/textures/decals/BloodSplatter_BaseColor.png"),
+                    );
+                    decals.register(splatter, graph);
+
+                    break;
+                }
+            }
+        }
     }
 
     pub fn definition(kind: WeaponKind) -> &'static WeaponDefinition {
@@ -451,8 +705,34 @@ impl Weapon {
         self.owner = owner;
     }
 
-    pub fn switch_flash_light(&mut self) {
-        self.flash_light_enabled = !self.flash_light_enabled;
+    /// Returns current spread of the weapon in `[0; 1]` range, where zero is perfect accuracy.
+    /// Widens while the owner moves and on each shot, narrows while the owner aims.
+    pub fn accuracy_state(&self) -> f32 {
+        self.accuracy.spread
+    }
+
+    /// Must be called by the owner each frame to keep [`Self::accuracy_state`] up to date.
+    pub fn set_accuracy_inputs(&mut self, is_moving: bool, is_aiming: bool) {
+        self.accuracy.is_moving = is_moving;
+        self.accuracy.is_aiming = is_aiming;
+    }
+
+    pub fn set_suppressed(&mut self, suppressed: bool) {
+        self.suppressed = suppressed;
+    }
+
+    pub fn is_suppressed(&self) -> bool {
+        self.suppressed
+    }
+
+    pub fn set_flash_light_enabled(&mut self, enabled: bool) {
+        self.flash_light_enabled = enabled;
+    }
+
+    /// Returns a handle of a node the off-hand of a character should be attached to. Could be
+    /// [`Handle::NONE`] if the weapon does not need a grip.
+    pub fn left_hand_grip(&self) -> Handle<Node> {
+        self.left_hand_grip
     }
 
     pub fn laser_sight(&self) -> &LaserSight {
@@ -464,7 +744,40 @@ impl Weapon {
     }
 
     pub fn can_shoot(&self, elapsed_time: f32) -> bool {
-        elapsed_time - self.last_shot_time >= self.definition.shoot_interval
+        !self.overheated && elapsed_time - self.last_shot_time >= self.shoot_interval()
+    }
+
+    pub fn balance(&self) -> &'static WeaponBalanceEntry {
+        WeaponBalance::weapon(self.kind)
+    }
+
+    /// Time (in seconds) between shots with respect to weapon balance.
+    pub fn shoot_interval(&self) -> f32 {
+        self.definition.shoot_interval / self.balance().fire_rate_scale.max(f32::EPSILON)
+    }
+
+    pub fn gen_v_recoil_angle(&self) -> f32 {
+        self.definition.gen_v_recoil_angle() * self.balance().recoil_scale
+    }
+
+    pub fn gen_h_recoil_angle(&self) -> f32 {
+        self.definition.gen_h_recoil_angle() * self.balance().recoil_scale
+    }
+
+    /// Returns heat of the weapon in `[0; 1]` range. Always zero for weapons that do not use heat.
+    pub fn heat(&self) -> f32 {
+        self.heat
+    }
+
+    pub fn is_overheated(&self) -> bool {
+        self.overheated
+    }
+
+    fn cool_down(&mut self, dt: f32) {
+        self.heat = (self.heat - Self::HEAT_COOLING_RATE * dt).max(0.0);
+        if self.overheated && self.heat <= Self::OVERHEAT_RECOVERY_THRESHOLD {
+            self.overheated = false;
+        }
     }
 
     pub fn set_sight_reaction(&mut self, reaction: SightReaction) {
@@ -484,17 +797,50 @@ impl Weapon {
         direction: Option<Vector3<f32>>,
         sound_manager: &SoundManager,
         actors: &[Handle<Node>],
+        projectile_pool: &ProjectilePool,
+        decals: &DecalContainer,
+        gore: bool,
     ) {
         self.last_shot_time = elapsed_time;
+        self.kickback.kick(self.definition.kickback);
+        self.accuracy.on_shot();
 
         let position = self.shot_position(&scene.graph);
 
-        if let Some(random_shot_sound) = self
-            .definition
-            .shot_sounds
-            .choose(&mut fyrox::rand::thread_rng())
-        {
-            sound_manager.play_sound(&mut scene.graph, random_shot_sound, position, 1.0, 5.0, 3.0);
+        if self.definition.uses_heat() {
+            self.heat = (self.heat + self.definition.heat_per_shot).min(1.0);
+            if self.heat >= 1.0 {
+                self.overheated = true;
+                sound_manager.play_sound(
+                    &mut scene.graph,
+                    Self::VENT_SOUND,
+                    position,
+                    1.0,
+                    3.0,
+                    2.0,
+                );
+            }
+        }
+
+        let shot_sounds = if self.suppressed && !self.definition.suppressed_shot_sounds.is_empty() {
+            &self.definition.suppressed_shot_sounds
+        } else {
+            &self.definition.shot_sounds
+        };
+        if let Some(random_shot_sound) = shot_sounds.choose(&mut fyrox::rand::thread_rng()) {
+            let (gain, radius) = if self.suppressed {
+                (Self::SUPPRESSED_SHOT_GAIN, Self::SUPPRESSED_SHOT_RADIUS)
+            } else {
+                (1.0, 3.0)
+            };
+            sound_manager.play_sound(
+                &mut scene.graph,
+                random_shot_sound,
+                position,
+                gain,
+                5.0,
+                radius,
+            );
         }
 
         if self.muzzle_flash.is_some() {
@@ -530,30 +876,45 @@ impl Weapon {
 
         match self.definition.projectile {
             WeaponProjectile::Projectile(projectile) => {
-                Projectile::add_to_scene(
-                    projectile,
-                    resource_manager,
-                    scene,
-                    direction,
-                    position,
-                    self_handle,
-                    Default::default(),
-                );
+                for _ in 0..self.definition.pellet_count {
+                    Projectile::add_to_scene(
+                        projectile,
+                        resource_manager,
+                        scene,
+                        projectile_pool,
+                        self.definition.gen_pellet_direction(direction),
+                        position,
+                        self_handle,
+                        Default::default(),
+                    );
+                }
             }
-            WeaponProjectile::Ray { damage } => {
-                if let Some(hit) = Self::shoot_ray(
-                    &mut scene.graph,
-                    resource_manager,
-                    actors,
-                    self_handle,
-                    position,
-                    position + direction.scale(1000.0),
-                    damage,
-                    self.definition.shot_effect,
-                    sound_manager,
-                    self.definition.base_critical_shot_probability,
-                ) {
-                    if hit.actor.is_some() {
+            WeaponProjectile::Ray {
+                damage,
+                penetration,
+                penetration_damage_scale,
+            } => {
+                let damage = damage.scale(self.balance().damage_scale);
+                for _ in 0..self.definition.pellet_count {
+                    let pellet_direction = self.definition.gen_pellet_direction(direction);
+                    let hits = Self::shoot_ray(
+                        &mut scene.graph,
+                        resource_manager,
+                        actors,
+                        self_handle,
+                        position,
+                        position + pellet_direction.scale(1000.0),
+                        damage,
+                        penetration,
+                        penetration_damage_scale,
+                        self.definition.knockback_scale,
+                        self.definition.shot_effect,
+                        sound_manager,
+                        self.definition.base_critical_shot_probability,
+                        decals,
+                        gore,
+                    );
+                    if hits.iter().any(|hit| hit.actor.is_some()) {
                         self.set_sight_reaction(SightReaction::HitDetected);
                     }
                 }
@@ -578,6 +939,29 @@ impl ScriptTrait for Weapon {
     fn on_start(&mut self, ctx: &mut ScriptContext) {
         self.definition = Self::definition(self.kind);
         self.self_handle = ctx.handle;
+        self.left_hand_grip = ctx
+            .scene
+            .graph
+            .find_by_name(ctx.handle, "LeftHandGrip")
+            .map(|(handle, _)| handle)
+            .unwrap_or_default();
+
+        // Models do not have grip nodes yet, so the grip is created from the definition.
+        if self.left_hand_grip.is_none() {
+            if let Some(grip_position) = self.definition.left_hand_grip() {
+                self.left_hand_grip = PivotBuilder::new(
+                    BaseBuilder::new()
+                        .with_name("LeftHandGrip")
+                        .with_local_transform(
+                            TransformBuilder::new()
+                                .with_local_position(grip_position)
+                                .build(),
+                        ),
+                )
+                .build(&mut ctx.scene.graph);
+                ctx.scene.graph.link_nodes(self.left_hand_grip, ctx.handle);
+            }
+        }
     }
 
     fn on_deinit(&mut self, ctx: &mut ScriptDeinitContext) {
@@ -607,6 +991,13 @@ impl ScriptTrait for Weapon {
         let node = &mut ctx.scene.graph[ctx.handle];
         self.shot_position = node.global_position();
 
+        self.kickback.update(node, ctx.dt);
+        self.accuracy.update(ctx.dt);
+        self.cool_down(ctx.dt);
+        let sway_magnitude = game_ref(ctx.plugins).control_scheme.weapon_sway;
+        self.sway
+            .update(node, &self.accuracy, sway_magnitude, ctx.dt);
+
         self.muzzle_flash_timer -= ctx.dt;
         if self.muzzle_flash_timer <= 0.0 && self.muzzle_flash.is_some() {
             ctx.scene.graph[self.muzzle_flash].set_visibility(false);
@@ -642,6 +1033,9 @@ impl ScriptTrait for Weapon {
                 request.direction,
                 &level.sound_manager,
                 &level.actors,
+                &level.projectile_pool,
+                &level.decals,
+                game_ref(ctx.plugins).accessibility.gore,
             );
         }
     }
@@ -655,10 +1049,36 @@ pub fn weapon_mut(handle: Handle<Node>, graph: &mut Graph) -> &mut Weapon {
     graph[handle].try_get_script_mut::<Weapon>().unwrap()
 }
 
+pub fn try_weapon_mut(handle: Handle<Node>, graph: &mut Graph) -> Option<&mut Weapon> {
+    graph
+        .try_get_mut(handle)
+        .and_then(|w| w.try_get_script_mut::<Weapon>())
+}
+
 pub fn try_weapon_ref(handle: Handle<Node>, graph: &Graph) -> Option<&Weapon> {
-    graph[handle].try_get_script::<Weapon>()
+    graph
+        .try_get(handle)
+        .and_then(|w| w.try_get_script::<Weapon>())
 }
 
 pub fn weapon_ref(handle: Handle<Node>, graph: &Graph) -> &Weapon {
     try_weapon_ref(handle, graph).unwrap()
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_penetrating_round_through_two_stacked_actors() {
+        let first = Handle::new(1, 1);
+        let second = Handle::new(2, 1);
+        // Each actor is crossed through two hit boxes, the wall is behind them.
+        let owners = [first, first, second, second, Handle::NONE];
+
+        assert_eq!(pierced_intersections(&owners, 0), vec![0]);
+        assert_eq!(pierced_intersections(&owners, 1), vec![0, 2]);
+        assert_eq!(pierced_intersections(&owners, 2), vec![0, 2, 4]);
+        assert_eq!(pierced_intersections(&owners, 5), vec![0, 2, 4]);
+    }
+}
@@ -1,11 +1,13 @@
 use crate::{
-    character::{try_get_character_mut, CharacterCommand},
+    character::{try_get_character_mut, CharacterCommand, HitBox, StatusEffect},
     current_level_ref, effects,
     effects::EffectKind,
     game_ref,
+    level::decal::HitMarks,
     message::Message,
-    weapon::{sight::SightReaction, Hit},
-    Turret, Weapon,
+    sound::{MaterialType, SoundManager},
+    weapon::{sight::SightReaction, try_weapon_ref, Hit},
+    Decal, Turret, Weapon,
 };
 use fyrox::{
     core::{
@@ -21,15 +23,19 @@ use fyrox::{
     impl_component_provider,
     lazy_static::lazy_static,
     scene::{
+        collider::{BitMask, Collider, InteractionGroups},
+        graph::Graph,
         node::{Node, TypeUuidProvider},
-        rigidbody::RigidBody,
+        rigidbody::{RigidBody, RigidBodyType},
         sprite::Sprite,
         Scene,
     },
     script::{ScriptContext, ScriptTrait},
+    utils::log::Log,
 };
 use serde::Deserialize;
 use std::{
+    cell::RefCell,
     collections::{HashMap, HashSet},
     fs::File,
 };
@@ -97,6 +103,33 @@ pub struct Projectile {
     /// Position of projectile on the previous frame, it is used to simulate
     /// continuous intersection detection from fast moving projectiles.
     last_position: Vector3<f32>,
+    /// Accumulated downward speed of kinematic projectiles affected by gravity.
+    #[visit(optional)]
+    fall_speed: f32,
+    /// Total distance the projectile has travelled since it was fired.
+    #[visit(optional)]
+    travelled_distance: f32,
+    /// Time the fuse was burning before the projectile was released (for grenades).
+    #[visit(optional)]
+    cook_time: f32,
+    /// Fraction of the definition speed left after ricochets.
+    #[visit(optional)]
+    speed_factor: f32,
+    /// Amount of surfaces and actors the projectile has passed through.
+    #[visit(optional)]
+    penetrations: u8,
+    /// Last actor the projectile has passed through, it must not be hit twice.
+    #[visit(skip)]
+    #[reflect(hidden)]
+    pierced_actor: Handle<Node>,
+    /// Velocity of the rigid body on the previous frame, sharp changes of it mean that the
+    /// projectile has bounced off something.
+    #[visit(skip)]
+    #[reflect(hidden)]
+    last_velocity: Vector3<f32>,
+    #[visit(skip)]
+    #[reflect(hidden)]
+    bounce_sound_timer: f32,
 
     #[visit(skip)]
     #[reflect(hidden)]
@@ -105,6 +138,27 @@ pub struct Projectile {
     #[visit(skip)]
     #[reflect(hidden)]
     hits: HashSet<Hit>,
+
+    /// Parked projectiles are kept in [`ProjectilePool`] and do nothing until re-used.
+    #[visit(optional)]
+    #[reflect(hidden)]
+    parked: bool,
+
+    #[visit(optional)]
+    #[reflect(hidden)]
+    parked_body_type: Option<RigidBodyType>,
+
+    /// Colliders of a parked projectile with their original collision groups, parked colliders
+    /// do not interact with anything.
+    #[visit(optional)]
+    #[reflect(hidden)]
+    parked_colliders: Vec<ParkedCollider>,
+}
+
+#[derive(Default, Visit, Debug, Clone)]
+struct ParkedCollider {
+    collider: Handle<Node>,
+    groups: InteractionGroups,
 }
 
 impl_component_provider!(Projectile);
@@ -125,8 +179,19 @@ impl Default for Projectile {
             owner: Default::default(),
             initial_velocity: Default::default(),
             last_position: Default::default(),
+            fall_speed: 0.0,
+            travelled_distance: 0.0,
+            cook_time: 0.0,
+            speed_factor: 1.0,
+            penetrations: 0,
+            pierced_actor: Default::default(),
+            last_velocity: Default::default(),
+            bounce_sound_timer: 0.0,
             definition: Self::get_definition(ProjectileKind::Plasma),
             hits: Default::default(),
+            parked: false,
+            parked_body_type: None,
+            parked_colliders: Default::default(),
         }
     }
 }
@@ -139,13 +204,102 @@ pub struct ProjectileDefinition {
     /// Means that movement of projectile controlled by code, not physics.
     /// However projectile still could have rigid body to detect collisions.
     is_kinematic: bool,
+    /// Scale of gravity applied to kinematic projectiles, zero means straight flight. Movement
+    /// of non-kinematic projectiles is simulated by physics engine.
+    #[serde(default)]
+    gravity_scale: f32,
+    /// Projectile is destroyed when it travels more than this distance, so missed shots won't
+    /// fly forever in open areas.
+    #[serde(default = "default_max_distance")]
+    max_distance: f32,
+    /// Status effect applied to actors hit by the projectile.
+    #[serde(default)]
+    status_effect: Option<StatusEffect>,
+    /// Kinematic projectiles with this flag bounce off hard surfaces when they hit them at
+    /// shallow angles.
+    #[serde(default)]
+    ricochet: bool,
+    /// Maximum angle (in degrees) between flight direction and a surface at which the
+    /// projectile still ricochets.
+    #[serde(default = "default_max_ricochet_angle")]
+    max_ricochet_angle: f32,
+    /// Fraction of speed lost on each ricochet.
+    #[serde(default = "default_ricochet_speed_loss")]
+    ricochet_speed_loss: f32,
+    /// Projectile is destroyed instead of bouncing when its speed would drop below this value.
+    #[serde(default = "default_min_ricochet_speed")]
+    min_ricochet_speed: f32,
+    /// Amount of surfaces or actors the projectile can pass through before it stops.
+    #[serde(default)]
+    penetration: u8,
+    /// Damage multiplier applied for each surface or actor the projectile has passed through.
+    #[serde(default = "default_penetration_damage_scale")]
+    penetration_damage_scale: f32,
+    /// Multiplier of damage that is used to push hit actors back.
+    #[serde(default = "default_knockback_scale")]
+    knockback_scale: f32,
     impact_sound: String,
+    /// Sound played when a projectile simulated by physics bounces off a surface, its volume
+    /// depends on impact speed.
+    #[serde(default)]
+    bounce_sound: Option<String>,
     model: String,
 }
 
+fn default_max_distance() -> f32 {
+    100.0
+}
+
+fn default_max_ricochet_angle() -> f32 {
+    25.0
+}
+
+fn default_ricochet_speed_loss() -> f32 {
+    0.3
+}
+
+fn default_min_ricochet_speed() -> f32 {
+    0.05
+}
+
+fn default_penetration_damage_scale() -> f32 {
+    0.6
+}
+
+fn default_knockback_scale() -> f32 {
+    1.0
+}
+
+impl ProjectileDefinition {
+    /// Distance travelled by a kinematic projectile per frame.
+    pub fn speed(&self) -> f32 {
+        self.speed
+    }
+
+    pub fn is_kinematic(&self) -> bool {
+        self.is_kinematic
+    }
+
+    pub fn lifetime(&self) -> f32 {
+        self.lifetime
+    }
+
+    pub fn damage(&self) -> Damage {
+        self.damage
+    }
+}
+
 #[derive(Deserialize, Default)]
 pub struct ProjectileDefinitionContainer {
     map: HashMap<ProjectileKind, ProjectileDefinition>,
+    /// Max amount of projectiles flying at the same time, the oldest ones are removed when new
+    /// projectiles exceed the limit.
+    #[serde(default = "default_max_active")]
+    max_active: usize,
+}
+
+fn default_max_active() -> usize {
+    256
 }
 
 impl ProjectileDefinitionContainer {
@@ -159,7 +313,75 @@ lazy_static! {
     static ref DEFINITIONS: ProjectileDefinitionContainer = ProjectileDefinitionContainer::new();
 }
 
+/// Keeps scene nodes of dead projectiles to re-use them instead of instantiating projectile
+/// model on each shot. Parked projectiles are hidden and their colliders do not interact with
+/// anything.
+#[derive(Default, Visit, Debug)]
+pub struct ProjectilePool {
+    free: RefCell<Vec<Handle<Node>>>,
+    /// Projectiles in flight, from the oldest to the newest.
+    #[visit(optional)]
+    active: RefCell<Vec<Handle<Node>>>,
+}
+
+impl ProjectilePool {
+    /// Projectiles above this amount are removed from the scene as usual.
+    const MAX_FREE: usize = 64;
+
+    /// Takes a parked projectile of the given kind out of the pool, if any.
+    pub fn acquire(&self, kind: ProjectileKind, graph: &Graph) -> Option<Handle<Node>> {
+        let mut free = self.free.borrow_mut();
+        let index = free.iter().position(|&handle| {
+            graph
+                .try_get(handle)
+                .and_then(|n| n.try_get_script::<Projectile>())
+                .map_or(false, |p| p.kind == kind && p.parked)
+        })?;
+        Some(free.swap_remove(index))
+    }
+
+    /// Puts parked projectile into the pool. Returns `false` if the pool is full, in this case
+    /// the projectile must be removed from the scene.
+    pub fn release(&self, handle: Handle<Node>) -> bool {
+        self.active.borrow_mut().retain(|&active| active != handle);
+
+        let mut free = self.free.borrow_mut();
+        if free.len() < Self::MAX_FREE {
+            free.push(handle);
+            true
+        } else {
+            false
+        }
+    }
+
+    /// Registers projectile in flight. Returns the oldest projectile if the amount of active
+    /// projectiles exceeds the limit, it must be evicted.
+    fn add_active(&self, handle: Handle<Node>) -> Option<Handle<Node>> {
+        let mut active = self.active.borrow_mut();
+        active.push(handle);
+        if active.len() > DEFINITIONS.max_active {
+            Some(active.remove(0))
+        } else {
+            None
+        }
+    }
+}
+
 impl Projectile {
+    const GRAVITY: f32 = 9.81;
+    /// Distance (in meters) the projectile is pushed out of a surface it ricocheted off.
+    const RICOCHET_SURFACE_OFFSET: f32 = 0.01;
+    /// Distance (in meters) behind a pierced surface from which the projectile continues to
+    /// check for hits.
+    const PENETRATION_OFFSET: f32 = 0.05;
+    /// Min change of speed (in m/s) of a physical projectile in one frame that is heard as a
+    /// bounce. Rolling and tiny bounces are silent.
+    const MIN_BOUNCE_SPEED: f32 = 1.0;
+    /// Bounce sound plays at full volume at this impact speed (in m/s).
+    const FULL_BOUNCE_GAIN_SPEED: f32 = 6.0;
+    /// Min time (in seconds) between two bounce sounds of the same projectile.
+    const BOUNCE_SOUND_INTERVAL: f32 = 0.15;
+
     pub fn get_definition(kind: ProjectileKind) -> &'static ProjectileDefinition {
         DEFINITIONS.map.get(&kind).unwrap()
     }
@@ -168,11 +390,49 @@ impl Projectile {
         kind: ProjectileKind,
         resource_manager: &ResourceManager,
         scene: &mut Scene,
+        pool: &ProjectilePool,
+        dir: Vector3<f32>,
+        position: Vector3<f32>,
+        owner: Handle<Node>,
+        initial_velocity: Vector3<f32>,
+    ) -> Handle<Node> {
+        let handle = Self::spawn(
+            kind,
+            resource_manager,
+            scene,
+            pool,
+            dir,
+            position,
+            owner,
+            initial_velocity,
+        );
+
+        if let Some(oldest) = pool.add_active(handle) {
+            Log::warn(format!(
+                "Projectile limit of {} is exceeded, the oldest projectile is removed.",
+                DEFINITIONS.max_active
+            ));
+            Self::evict(oldest, &mut scene.graph, pool);
+        }
+
+        handle
+    }
+
+    fn spawn(
+        kind: ProjectileKind,
+        resource_manager: &ResourceManager,
+        scene: &mut Scene,
+        pool: &ProjectilePool,
         dir: Vector3<f32>,
         position: Vector3<f32>,
         owner: Handle<Node>,
         initial_velocity: Vector3<f32>,
     ) -> Handle<Node> {
+        if let Some(handle) = pool.acquire(kind, &scene.graph) {
+            Self::respawn(handle, scene, dir, position, owner, initial_velocity);
+            return handle;
+        }
+
         let definition = Self::get_definition(kind);
 
         let instance_handle = block_on(resource_manager.request_model(definition.model.clone()))
@@ -194,6 +454,125 @@ impl Projectile {
         instance_handle
     }
 
+    fn respawn(
+        handle: Handle<Node>,
+        scene: &mut Scene,
+        dir: Vector3<f32>,
+        position: Vector3<f32>,
+        owner: Handle<Node>,
+        initial_velocity: Vector3<f32>,
+    ) {
+        let (parked_body_type, parked_colliders) = scene.graph[handle]
+            .try_get_script_mut::<Projectile>()
+            .map(|p| {
+                (
+                    p.parked_body_type.take(),
+                    std::mem::take(&mut p.parked_colliders),
+                )
+            })
+            .unwrap_or_default();
+
+        for parked_collider in parked_colliders {
+            if let Some(collider) = scene
+                .graph
+                .try_get_mut(parked_collider.collider)
+                .and_then(|n| n.cast_mut::<Collider>())
+            {
+                collider.set_collision_groups(parked_collider.groups);
+            }
+        }
+
+        let node = &mut scene.graph[handle];
+
+        node.set_visibility(true);
+        node.local_transform_mut().set_position(position);
+        if let Some(rigid_body) = node.cast_mut::<RigidBody>() {
+            if let Some(body_type) = parked_body_type {
+                rigid_body.set_body_type(body_type);
+            }
+            rigid_body.set_ang_vel(Default::default());
+            rigid_body.set_lin_vel(initial_velocity);
+        }
+
+        if let Some(projectile) = scene.graph[handle].try_get_script_mut::<Projectile>() {
+            projectile.parked = false;
+            projectile.cook_time = 0.0;
+            projectile.speed_factor = 1.0;
+            projectile.penetrations = 0;
+            projectile.pierced_actor = Handle::NONE;
+            projectile.last_velocity = initial_velocity;
+            projectile.bounce_sound_timer = 0.0;
+            projectile.lifetime = projectile.definition.lifetime;
+            projectile.rotation_angle = 0.0;
+            projectile.last_position = position;
+            projectile.fall_speed = 0.0;
+            projectile.travelled_distance = 0.0;
+            projectile.initial_velocity = initial_velocity;
+            projectile.dir = dir
+                .try_normalize(std::f32::EPSILON)
+                .unwrap_or_else(Vector3::y);
+            projectile.owner = owner;
+            projectile.hits.clear();
+        }
+    }
+
+    /// Hides projectile, freezes its body and disables its colliders, so it can be stored in
+    /// [`ProjectilePool`].
+    fn park(&mut self, handle: Handle<Node>, graph: &mut Graph) {
+        self.parked = true;
+        self.owner = Handle::NONE;
+
+        let colliders = graph
+            .traverse_handle_iter(handle)
+            .filter(|h| graph[*h].is_collider())
+            .collect::<Vec<_>>();
+        for collider_handle in colliders {
+            let collider = graph[collider_handle].as_collider_mut();
+            self.parked_colliders.push(ParkedCollider {
+                collider: collider_handle,
+                groups: collider.collision_groups(),
+            });
+            collider.set_collision_groups(InteractionGroups::new(BitMask(0), BitMask(0)));
+        }
+
+        let node = &mut graph[handle];
+        node.set_visibility(false);
+        if let Some(rigid_body) = node.cast_mut::<RigidBody>() {
+            self.parked_body_type = Some(rigid_body.body_type());
+            rigid_body.set_lin_vel(Default::default());
+            rigid_body.set_ang_vel(Default::default());
+            rigid_body.set_body_type(RigidBodyType::KinematicPositionBased);
+        }
+    }
+
+    /// Parks projectile in flight (or removes it if the pool is full) as if its lifetime has
+    /// ended.
+    fn evict(handle: Handle<Node>, graph: &mut Graph, pool: &ProjectilePool) {
+        let mut projectile = match graph
+            .try_get_mut(handle)
+            .and_then(|n| n.try_get_script_mut::<Projectile>())
+        {
+            Some(projectile) if !projectile.parked => std::mem::take(projectile),
+            _ => return,
+        };
+
+        projectile.park(handle, graph);
+
+        if let Some(script) = graph[handle].try_get_script_mut::<Projectile>() {
+            *script = projectile;
+        }
+
+        if !pool.release(handle) {
+            graph.remove_node(handle);
+        }
+    }
+
+    /// Shortens lifetime of the projectile by the time its fuse was burning before release.
+    pub fn cook(&mut self, time: f32) {
+        self.cook_time = time;
+        self.lifetime = self.definition.lifetime - time;
+    }
+
     pub fn is_dead(&self) -> bool {
         self.lifetime <= 0.0
     }
@@ -201,17 +580,95 @@ impl Projectile {
     pub fn kill(&mut self) {
         self.lifetime = 0.0;
     }
+
+    /// Plays bounce sound when a projectile simulated by physics (a grenade for example) hits
+    /// a surface hard enough.
+    fn update_bounce_sound(
+        &mut self,
+        graph: &mut Graph,
+        handle: Handle<Node>,
+        sound_manager: &SoundManager,
+        dt: f32,
+    ) {
+        self.bounce_sound_timer -= dt;
+
+        let (velocity, position) = match graph[handle].cast::<RigidBody>() {
+            Some(body) => (body.lin_vel(), body.global_position()),
+            None => return,
+        };
+        let impact_speed = (velocity - self.last_velocity).norm();
+        self.last_velocity = velocity;
+
+        if let Some(bounce_sound) = self.definition.bounce_sound.as_ref() {
+            if impact_speed >= Self::MIN_BOUNCE_SPEED && self.bounce_sound_timer <= 0.0 {
+                self.bounce_sound_timer = Self::BOUNCE_SOUND_INTERVAL;
+                let gain = (impact_speed / Self::FULL_BOUNCE_GAIN_SPEED).min(1.0);
+                sound_manager.play_sound(graph, bounce_sound, position, gain, 2.0, 1.0);
+            }
+        }
+    }
+
+    /// Reflects flight direction of the projectile off a hard surface if it was hit at a shallow
+    /// angle. Returns `false` if the projectile must stop at the hit point.
+    fn try_ricochet(&mut self, hit: &Hit, material: Option<MaterialType>) -> bool {
+        if !self.definition.ricochet
+            || !self.definition.is_kinematic
+            || hit.actor.is_some()
+            || !material.map_or(false, |m| m.is_hard())
+        {
+            return false;
+        }
+
+        let dir = match ricochet_direction(self.dir, hit.normal, self.definition.max_ricochet_angle)
+        {
+            Some(dir) => dir,
+            None => return false,
+        };
+
+        let speed_factor = self.speed_factor * (1.0 - self.definition.ricochet_speed_loss);
+        if self.definition.speed * speed_factor < self.definition.min_ricochet_speed {
+            return false;
+        }
+
+        self.speed_factor = speed_factor;
+        self.dir = dir;
+
+        true
+    }
+}
+
+/// Reflects flight direction off a surface with the given normal. Returns `None` if the
+/// direction does not point into the surface or the angle between the direction and the surface
+/// plane is above `max_angle` (in degrees).
+fn ricochet_direction(
+    dir: Vector3<f32>,
+    normal: Vector3<f32>,
+    max_angle: f32,
+) -> Option<Vector3<f32>> {
+    let normal = normal.try_normalize(f32::EPSILON)?;
+
+    let dot = dir.dot(&normal);
+    if dot >= 0.0 || dot.abs().asin() > max_angle.to_radians() {
+        return None;
+    }
+
+    Some(
+        (dir - normal.scale(2.0 * dot))
+            .try_normalize(f32::EPSILON)
+            .unwrap_or(normal),
+    )
 }
 
 impl ScriptTrait for Projectile {
     fn on_init(&mut self, context: &mut ScriptContext) {
         let definition = Self::get_definition(self.kind);
 
-        self.lifetime = definition.lifetime;
+        self.lifetime = definition.lifetime - self.cook_time;
 
         let node = &mut context.scene.graph[context.handle];
 
         self.last_position = node.global_position();
+        self.last_velocity = self.initial_velocity;
 
         if let Some(rigid_body) = node.cast_mut::<RigidBody>() {
             rigid_body.set_lin_vel(self.initial_velocity);
@@ -223,6 +680,10 @@ impl ScriptTrait for Projectile {
     }
 
     fn on_update(&mut self, context: &mut ScriptContext) {
+        if self.parked {
+            return;
+        }
+
         let game = game_ref(context.plugins);
 
         // Fetch current position of projectile.
@@ -252,13 +713,57 @@ impl ScriptTrait for Projectile {
             collider,
         );
 
+        // Hits of an actor that was just pierced are ignored, otherwise other hit boxes of the
+        // same actor would stop the projectile.
+        let ray_hit = ray_hit.filter(|hit| hit.actor.is_none() || hit.actor != self.pierced_actor);
+
+        let mut ricochet_position = None;
+        let mut pierce_position = None;
         let (effect_position, effect_normal, effect_kind) = if let Some(hit) = ray_hit {
             let position = hit.position;
             let normal = hit.normal;
-            let blood_effect = hit.actor.is_some();
+            let marks = HitMarks::of_hit(hit.actor.is_some(), game.accessibility.gore);
+            let blood_effect = marks.blood;
+
+            if marks.impact_decal {
+                let decal = Decal::new_impact(
+                    context.resource_manager,
+                    &mut context.scene.graph,
+                    position,
+                    normal,
+                    hit.collider,
+                    blood_effect,
+                );
+                current_level_ref(context.plugins)
+                    .unwrap()
+                    .decals
+                    .register(decal, &mut context.scene.graph);
+            }
 
-            self.hits.insert(hit);
-            self.kill();
+            let level = current_level_ref(context.plugins).unwrap();
+            let material = level.sound_manager.material_of(hit.collider, hit.feature);
+            if self.try_ricochet(&hit, material) {
+                level.sound_manager.play_sound(
+                    &mut context.scene.graph,
+                    &self.definition.impact_sound,
+                    position,
+                    1.0,
+                    4.0,
+                    3.0,
+                );
+                ricochet_position = Some(position + normal.scale(Self::RICOCHET_SURFACE_OFFSET));
+            } else if self.penetrations < self.definition.penetration {
+                // Next ray cast starts right behind the hit point, so anything behind the
+                // pierced surface or actor will be hit too.
+                pierce_position = Some(position + self.dir.scale(Self::PENETRATION_OFFSET));
+                if hit.actor.is_some() {
+                    self.pierced_actor = hit.actor;
+                }
+                self.hits.insert(hit);
+            } else {
+                self.hits.insert(hit);
+                self.kill();
+            }
 
             (
                 position,
@@ -277,12 +782,26 @@ impl ScriptTrait for Projectile {
             )
         };
 
+        if !self.definition.is_kinematic {
+            self.update_bounce_sound(
+                &mut context.scene.graph,
+                context.handle,
+                &game.level.as_ref().unwrap().sound_manager,
+                context.dt,
+            );
+        }
+
         // Movement of kinematic projectiles are controlled explicitly.
         if self.definition.is_kinematic {
-            let total_velocity = self.dir.scale(self.definition.speed);
-            context.scene.graph[context.handle]
-                .local_transform_mut()
-                .offset(total_velocity);
+            self.fall_speed += Self::GRAVITY * self.definition.gravity_scale * context.dt;
+            let total_velocity = self.dir.scale(self.definition.speed * self.speed_factor)
+                - Vector3::new(0.0, self.fall_speed * context.dt, 0.0);
+            let transform = context.scene.graph[context.handle].local_transform_mut();
+            // Continue flight from the point of ricochet, not from behind the surface.
+            if let Some(ricochet_position) = ricochet_position {
+                transform.set_position(ricochet_position);
+            }
+            transform.offset(total_velocity);
         }
 
         // TODO: Replace with animation.
@@ -297,6 +816,11 @@ impl ScriptTrait for Projectile {
 
         self.lifetime -= context.dt;
 
+        self.travelled_distance += position.metric_distance(&self.last_position);
+        if self.travelled_distance >= self.definition.max_distance {
+            self.kill();
+        }
+
         if self.lifetime <= 0.0 {
             effects::create(
                 effect_kind,
@@ -314,13 +838,35 @@ impl ScriptTrait for Projectile {
                 4.0,
                 3.0,
             );
+
+            // Fuse has run out before the projectile hit anything.
+            if let (true, Damage::Splash { radius, amount }) =
+                (self.hits.is_empty(), self.definition.damage)
+            {
+                game.message_sender.send(Message::ApplySplashDamage {
+                    amount,
+                    radius,
+                    center: effect_position,
+                    who: self.owner,
+                    critical_shot_probability: 0.0,
+                });
+            }
         }
 
+        // Projectiles shot by weapons respect weapon balance, each pierced surface or actor
+        // weakens the projectile.
+        let damage_scale = try_weapon_ref(self.owner, &context.scene.graph)
+            .map_or(1.0, |weapon| weapon.balance().damage_scale)
+            * self
+                .definition
+                .penetration_damage_scale
+                .powi(self.penetrations as i32);
+
         for hit in self.hits.drain() {
             let damage = self
                 .definition
                 .damage
-                .scale(hit.hit_box.map_or(1.0, |h| h.damage_factor));
+                .scale(HitBox::damage_factor_of(hit.hit_box) * damage_scale);
 
             let critical_shot_probability =
                 context
@@ -360,15 +906,34 @@ impl ScriptTrait for Projectile {
                             amount,
                             critical_shot_probability,
                         });
+                        character.push_command(CharacterCommand::Knockback {
+                            who: hit.who,
+                            direction: self.dir,
+                            amount: amount * self.definition.knockback_scale,
+                        });
+                        if let Some(effect) = self.definition.status_effect {
+                            character.push_command(CharacterCommand::ApplyStatus(StatusEffect {
+                                who: hit.who,
+                                ..effect
+                            }));
+                        }
                     }
                 }
             }
         }
 
-        self.last_position = position;
+        if pierce_position.is_some() {
+            self.penetrations += 1;
+        }
+
+        self.last_position = ricochet_position.or(pierce_position).unwrap_or(position);
 
         if self.is_dead() {
-            context.scene.graph.remove_node(context.handle);
+            let pool = &current_level_ref(context.plugins).unwrap().projectile_pool;
+            self.park(context.handle, &mut context.scene.graph);
+            if !pool.release(context.handle) {
+                context.scene.graph.remove_node(context.handle);
+            }
         }
     }
 
@@ -376,3 +941,30 @@ impl ScriptTrait for Projectile {
         Self::type_uuid()
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn assert_vec_eq(a: Vector3<f32>, b: Vector3<f32>) {
+        assert!(a.metric_distance(&b) < 1.0e-5, "{:?} != {:?}", a, b);
+    }
+
+    #[test]
+    fn test_ricochet_off_45_degree_wall() {
+        // Wall turned by 45 degrees to the flight direction.
+        let normal = Vector3::new(-1.0, 0.0, -1.0);
+        let dir = ricochet_direction(Vector3::x(), normal, 60.0).unwrap();
+        assert_vec_eq(dir, Vector3::new(0.0, 0.0, -1.0));
+
+        // Too steep for a projectile that ricochets only at shallow angles.
+        assert!(ricochet_direction(Vector3::x(), normal, 30.0).is_none());
+    }
+
+    #[test]
+    fn test_no_ricochet_when_flying_away() {
+        let normal = Vector3::new(1.0, 0.0, 1.0);
+        assert!(ricochet_direction(Vector3::x(), normal, 90.0).is_none());
+        assert!(ricochet_direction(Vector3::x(), Vector3::default(), 90.0).is_none());
+    }
+}